@@ -0,0 +1,29 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use dashmap::DashMap;
+use std::collections::hash_map::RandomState;
+
+fn rows(n: u64) -> impl Iterator<Item = (u64, u64)> {
+    (0..n).map(|i| (i, i))
+}
+
+fn bulk_load(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bulk_load");
+    group.sample_size(10);
+
+    for n in [100_000, 1_000_000, 10_000_000] {
+        group.throughput(Throughput::Elements(n));
+
+        group.bench_with_input(BenchmarkId::new("collect", n), &n, |b, &n| {
+            b.iter(|| rows(n).collect::<DashMap<u64, u64>>())
+        });
+
+        group.bench_with_input(BenchmarkId::new("from_sorted_iter", n), &n, |b, &n| {
+            b.iter(|| DashMap::from_sorted_iter(rows(n), RandomState::new()))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bulk_load);
+criterion_main!(benches);
@@ -0,0 +1,47 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use dashmap::{DashMap, DashSet};
+use dashmap_bench::{run, BenchMap, Workload};
+use std::time::Duration;
+
+const OPS_PER_THREAD: u64 = 1 << 14;
+
+fn thread_counts() -> Vec<usize> {
+    let max = std::thread::available_parallelism().map_or(1, usize::from);
+    let mut counts: Vec<usize> = (0..).map(|i| 1 << i).take_while(|&n| n < max).collect();
+    counts.push(max);
+    counts
+}
+
+fn bench_map<M: BenchMap>(c: &mut Criterion, name: &str, new: impl Fn() -> M) {
+    for workload in Workload::ALL.iter() {
+        let mut group = c.benchmark_group(format!("{}/{}", workload.name, name));
+
+        for threads in thread_counts() {
+            group.throughput(Throughput::Elements(OPS_PER_THREAD * threads as u64));
+            group.bench_with_input(
+                BenchmarkId::from_parameter(threads),
+                &threads,
+                |b, &threads| {
+                    b.iter_custom(|iters| {
+                        (0..iters)
+                            .map(|_| run(&new(), workload, threads, OPS_PER_THREAD))
+                            .sum::<Duration>()
+                    })
+                },
+            );
+        }
+
+        group.finish();
+    }
+}
+
+fn dashmap(c: &mut Criterion) {
+    bench_map(c, "DashMap", DashMap::<u64, u64>::new);
+}
+
+fn dashset(c: &mut Criterion) {
+    bench_map(c, "DashSet", DashSet::<u64>::new);
+}
+
+criterion_group!(benches, dashmap, dashset);
+criterion_main!(benches);
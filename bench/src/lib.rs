@@ -0,0 +1,203 @@
+//! Workloads for benchmarking concurrent maps, modeled after the mixes used by `conc-map-bench`.
+//!
+//! The `workloads` benchmark runs them against DashMap and DashSet. To compare your own
+//! hasher or shard configuration, implement [`BenchMap`] for it (or use the existing impls
+//! with a different `S`) and call [`run`] from a criterion benchmark.
+
+use dashmap::{DashMap, DashSet};
+use std::hash::BuildHasher;
+use std::sync::Barrier;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The share of each operation in a workload, in percent.
+#[derive(Clone, Copy, Debug)]
+pub struct Mix {
+    pub read: u8,
+    pub insert: u8,
+    pub remove: u8,
+    pub update: u8,
+}
+
+impl Mix {
+    /// Almost only reads of present keys.
+    pub const READ_HEAVY: Mix = Mix {
+        read: 98,
+        insert: 1,
+        remove: 1,
+        update: 0,
+    };
+
+    /// Inserts and removes in equal parts, keeping the map at a steady size.
+    pub const EXCHANGE: Mix = Mix {
+        read: 10,
+        insert: 40,
+        remove: 40,
+        update: 10,
+    };
+
+    /// Mostly inserts, so the map keeps growing.
+    pub const RAPID_GROW: Mix = Mix {
+        read: 5,
+        insert: 80,
+        remove: 5,
+        update: 10,
+    };
+}
+
+/// A named workload.
+#[derive(Clone, Copy, Debug)]
+pub struct Workload {
+    pub name: &'static str,
+    pub mix: Mix,
+    /// The number of entries each thread inserts before the measurement starts.
+    pub prefill: u64,
+    /// Whether inserts use fresh keys. Otherwise they reuse existing or removed keys.
+    pub grow: bool,
+}
+
+impl Workload {
+    pub const ALL: [Workload; 3] = [
+        Workload {
+            name: "read_heavy",
+            mix: Mix::READ_HEAVY,
+            prefill: 1 << 14,
+            grow: false,
+        },
+        Workload {
+            name: "exchange",
+            mix: Mix::EXCHANGE,
+            prefill: 1 << 14,
+            grow: false,
+        },
+        Workload {
+            name: "rapid_grow",
+            mix: Mix::RAPID_GROW,
+            prefill: 1 << 8,
+            grow: true,
+        },
+    ];
+}
+
+/// The operations a workload performs on a map.
+pub trait BenchMap: Send + Sync {
+    fn read(&self, key: u64) -> bool;
+    fn insert(&self, key: u64);
+    fn remove(&self, key: u64);
+    fn update(&self, key: u64);
+}
+
+impl<S: BuildHasher + Clone + Send + Sync> BenchMap for DashMap<u64, u64, S> {
+    fn read(&self, key: u64) -> bool {
+        self.get(&key).is_some()
+    }
+
+    fn insert(&self, key: u64) {
+        DashMap::insert(self, key, key);
+    }
+
+    fn remove(&self, key: u64) {
+        DashMap::remove(self, &key);
+    }
+
+    fn update(&self, key: u64) {
+        if let Some(mut v) = self.get_mut(&key) {
+            *v += 1;
+        }
+    }
+}
+
+impl<S: BuildHasher + Clone + Send + Sync> BenchMap for DashSet<u64, S> {
+    fn read(&self, key: u64) -> bool {
+        self.contains(&key)
+    }
+
+    fn insert(&self, key: u64) {
+        DashSet::insert(self, key);
+    }
+
+    fn remove(&self, key: u64) {
+        DashSet::remove(self, &key);
+    }
+
+    fn update(&self, key: u64) {
+        // Sets have no values to update, so re-insert the key instead.
+        DashSet::insert(self, key);
+    }
+}
+
+/// A small and fast generator, so that key generation doesn't dominate the measurement.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+/// Prefills `map` and runs `ops` operations of the workload on each of `threads` threads,
+/// returning the time taken by the slowest thread.
+pub fn run<M: BenchMap>(map: &M, workload: &Workload, threads: usize, ops: u64) -> Duration {
+    let Mix {
+        read,
+        insert,
+        remove,
+        ..
+    } = workload.mix;
+    let (read, insert, remove) = (read as u64, insert as u64, remove as u64);
+    let barrier = Barrier::new(threads);
+
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|t| {
+                let barrier = &barrier;
+                scope.spawn(move || {
+                    let mut rng = SplitMix64(t as u64);
+                    // Every thread works on its own keys, all of which below `next_key`
+                    // are or were present in the map.
+                    let base = (t as u64) << 40;
+                    let mut next_key = workload.prefill.max(1);
+
+                    for key in 0..workload.prefill {
+                        map.insert(base + key);
+                    }
+
+                    barrier.wait();
+                    let start = Instant::now();
+
+                    for _ in 0..ops {
+                        let op = rng.next() % 100;
+                        let key = base + rng.next() % next_key;
+
+                        if op < read {
+                            std::hint::black_box(map.read(key));
+                        } else if op < read + insert {
+                            if workload.grow {
+                                map.insert(base + next_key);
+                                next_key += 1;
+                            } else {
+                                map.insert(key);
+                            }
+                        } else if op < read + insert + remove {
+                            map.remove(key);
+                        } else {
+                            map.update(key);
+                        }
+                    }
+
+                    start.elapsed()
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|h| h.join().unwrap())
+            .max()
+            .unwrap_or_default()
+    })
+}
@@ -0,0 +1,92 @@
+//! Applies a sequence of operations to a DashMap and to a `HashMap` model, checking that they agree.
+//!
+//! Run with `cargo +nightly fuzz run ops` from the `fuzz` directory.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use dashmap::DashMap;
+use libfuzzer_sys::fuzz_target;
+use std::collections::HashMap;
+
+#[derive(Arbitrary, Debug)]
+enum Op {
+    Insert(u8, u16),
+    Remove(u8),
+    RemoveIf(u8, u16),
+    Get(u8),
+    GetMutDowngrade(u8, u16),
+    OrInsert(u8, u16),
+    AndModify(u8),
+    Alter(u8),
+    Retain(u8),
+    RemoveKeys(Vec<u8>),
+    ShrinkToFit,
+    Clear,
+    Clone,
+}
+
+fuzz_target!(|ops: Vec<Op>| {
+    let map = DashMap::with_shard_amount(4);
+    let mut model = HashMap::new();
+
+    for op in ops {
+        match op {
+            Op::Insert(k, v) => assert_eq!(map.insert(k, v), model.insert(k, v)),
+            Op::Remove(k) => assert_eq!(map.remove(&k), model.remove_entry(&k)),
+            Op::RemoveIf(k, v) => {
+                let expected = match model.get(&k) {
+                    Some(&old) if old < v => model.remove_entry(&k),
+                    _ => None,
+                };
+                assert_eq!(map.remove_if(&k, |_, old| *old < v), expected);
+            }
+            Op::Get(k) => assert_eq!(map.get(&k).map(|r| *r), model.get(&k).copied()),
+            Op::GetMutDowngrade(k, v) => {
+                let mut r = map.get_mut(&k);
+                if let Some(r) = r.as_mut() {
+                    **r = v;
+                    model.insert(k, v);
+                }
+                assert_eq!(r.map(|r| *r.downgrade()), model.get(&k).copied());
+            }
+            Op::OrInsert(k, v) => {
+                assert_eq!(*map.entry(k).or_insert(v), *model.entry(k).or_insert(v));
+            }
+            Op::AndModify(k) => {
+                map.entry(k).and_modify(|v| *v = v.wrapping_add(1));
+                model.entry(k).and_modify(|v| *v = v.wrapping_add(1));
+            }
+            Op::Alter(k) => {
+                map.alter(&k, |_, v| v.rotate_left(3));
+                if let Some(v) = model.get_mut(&k) {
+                    *v = v.rotate_left(3);
+                }
+            }
+            Op::Retain(m) => {
+                let m = u16::from(m) + 1;
+                map.retain(|_, v| *v % m != 0);
+                model.retain(|_, v| *v % m != 0);
+            }
+            Op::RemoveKeys(keys) => {
+                let expected = keys.iter().filter(|k| model.remove(k).is_some()).count();
+                assert_eq!(map.remove_keys(keys.iter()), expected);
+            }
+            Op::ShrinkToFit => map.shrink_to_fit(),
+            Op::Clear => {
+                map.clear();
+                model.clear();
+            }
+            Op::Clone => {
+                let clone = map.clone();
+                assert_eq!(clone.len(), model.len());
+                assert!(clone.iter().all(|r| model.get(r.key()) == Some(r.value())));
+            }
+        }
+
+        assert_eq!(map.len(), model.len());
+    }
+
+    assert_eq!(map.len_exact(), model.len());
+    assert_eq!(map.into_iter().collect::<HashMap<_, _>>(), model);
+});
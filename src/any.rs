@@ -0,0 +1,150 @@
+use crate::mapref::one::{MappedRef, MappedRefMut};
+use crate::DashMap;
+use core::any::{Any, TypeId};
+use core::fmt;
+use core::hash::BuildHasher;
+use std::collections::hash_map::RandomState;
+
+type AnyValue = Box<dyn Any + Send + Sync>;
+
+/// A concurrent map holding at most one value of every type, keyed by the type itself.
+///
+/// Built on a [`DashMap<TypeId, Box<dyn Any + Send + Sync>>`](DashMap), this takes care of
+/// the downcasting, so values are accessed through typed references.
+///
+/// # Examples
+///
+/// ```
+/// use dashmap::DashAnyMap;
+///
+/// struct Config { verbose: bool }
+///
+/// let plugins = DashAnyMap::new();
+/// plugins.insert(Config { verbose: true });
+/// plugins.insert(5u32);
+///
+/// assert!(plugins.get::<Config>().unwrap().verbose);
+/// *plugins.get_mut::<u32>().unwrap() += 1;
+/// assert_eq!(plugins.remove::<u32>(), Some(6));
+/// assert!(plugins.get::<String>().is_none());
+/// ```
+pub struct DashAnyMap<S = RandomState> {
+    inner: DashMap<TypeId, AnyValue, S>,
+}
+
+impl DashAnyMap<RandomState> {
+    /// Creates a new DashAnyMap with a capacity of 0.
+    pub fn new() -> Self {
+        Self::with_hasher(RandomState::new())
+    }
+}
+
+impl<S: Default + BuildHasher> Default for DashAnyMap<S> {
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<S: BuildHasher> DashAnyMap<S> {
+    /// Creates a new DashAnyMap with a capacity of 0 and the provided hasher.
+    pub fn with_hasher(hasher: S) -> Self {
+        Self {
+            inner: DashMap::with_hasher(hasher),
+        }
+    }
+
+    /// Consumes the map, returning the underlying [`DashMap`].
+    pub fn into_inner(self) -> DashMap<TypeId, AnyValue, S> {
+        self.inner
+    }
+
+    /// Inserts a value, returning the previous value of the same type if there was one.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    pub fn insert<T: Any + Send + Sync>(&self, value: T) -> Option<T> {
+        self.inner
+            .insert(TypeId::of::<T>(), Box::new(value))
+            .map(|old| *old.downcast::<T>().expect("values are keyed by their type"))
+    }
+
+    /// Get an immutable reference to the value of type `T`.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    pub fn get<T: Any + Send + Sync>(&self) -> Option<MappedRef<'_, TypeId, T>> {
+        self.inner.get(&TypeId::of::<T>()).map(|r| {
+            r.map(|v| {
+                v.downcast_ref::<T>()
+                    .expect("values are keyed by their type")
+            })
+        })
+    }
+
+    /// Get a mutable reference to the value of type `T`.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    pub fn get_mut<T: Any + Send + Sync>(&self) -> Option<MappedRefMut<'_, TypeId, T>> {
+        self.inner.get_mut(&TypeId::of::<T>()).map(|r| {
+            r.map(|v| {
+                v.downcast_mut::<T>()
+                    .expect("values are keyed by their type")
+            })
+        })
+    }
+
+    /// Get a mutable reference to the value of type `T`, inserting the result of `f` if there is none.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    pub fn get_or_insert_with<T: Any + Send + Sync>(
+        &self,
+        f: impl FnOnce() -> T,
+    ) -> MappedRefMut<'_, TypeId, T> {
+        self.inner
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(f()))
+            .map(|v| {
+                v.downcast_mut::<T>()
+                    .expect("values are keyed by their type")
+            })
+    }
+
+    /// Removes the value of type `T` and returns it, if there was one.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    pub fn remove<T: Any + Send + Sync>(&self) -> Option<T> {
+        self.inner
+            .remove(&TypeId::of::<T>())
+            .map(|(_, v)| *v.downcast::<T>().expect("values are keyed by their type"))
+    }
+
+    /// Checks if the map holds a value of type `T`.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    pub fn contains<T: Any + Send + Sync>(&self) -> bool {
+        self.inner.contains_key(&TypeId::of::<T>())
+    }
+
+    /// Returns the number of values in the map.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Checks if the map is empty or not.
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Removes all values from the map.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    pub fn clear(&self) {
+        self.inner.clear()
+    }
+}
+
+impl<S: BuildHasher> fmt::Debug for DashAnyMap<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DashAnyMap")
+            .field("len", &self.len())
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,165 @@
+//! Detection of the most common deadlock: locking a shard from inside an iteration over the same map.
+//!
+//! With the `deadlock-audit` feature enabled, iterators record the shard they are holding in a
+//! thread-local list while they are alive, and blocking lock acquisitions check that list first.
+//! An iterator moved to another thread stays in the list of the thread which created it until it
+//! is dropped, so locks taken on the thread it was moved to are not checked against it.
+//! Without the feature, everything here compiles to nothing.
+
+use crate::lock::RawRwLock;
+use cfg_if::cfg_if;
+
+cfg_if! {
+    if #[cfg(feature = "deadlock-audit")] {
+        use core::cell::RefCell;
+        use core::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Arc;
+
+        std::thread_local! {
+            static HELD: RefCell<Vec<Arc<Marker>>> = RefCell::new(Vec::new());
+        }
+
+        /// An entry of a thread's list, shared with the [`Held`] which created it.
+        struct Marker {
+            lock: usize,
+            exclusive: bool,
+            released: AtomicBool,
+        }
+
+        /// Marks a shard as locked by an iterator on the current thread until dropped.
+        ///
+        /// Iterators are `Send`, so this may be dropped on another thread than the one whose list it
+        /// is in. It then can't remove its entry, but marks it as released, and the owning thread
+        /// skips and prunes released entries.
+        pub(crate) struct Held {
+            marker: Arc<Marker>,
+        }
+
+        impl Held {
+            pub(crate) fn new(lock: &RawRwLock, exclusive: bool) -> Self {
+                Self::register(lock as *const RawRwLock as usize, exclusive)
+            }
+
+            fn register(lock: usize, exclusive: bool) -> Self {
+                let marker = Arc::new(Marker {
+                    lock,
+                    exclusive,
+                    released: AtomicBool::new(false),
+                });
+                HELD.with(|held| held.borrow_mut().push(marker.clone()));
+                Self { marker }
+            }
+        }
+
+        impl Clone for Held {
+            fn clone(&self) -> Self {
+                Self::register(self.marker.lock, self.marker.exclusive)
+            }
+        }
+
+        impl Drop for Held {
+            fn drop(&mut self) {
+                self.marker.released.store(true, Ordering::Release);
+
+                // The list may already be gone if the thread is exiting.
+                let _ = HELD.try_with(|held| {
+                    let mut held = held.borrow_mut();
+                    if let Some(i) = held.iter().rposition(|entry| Arc::ptr_eq(entry, &self.marker)) {
+                        held.swap_remove(i);
+                    }
+                });
+            }
+        }
+
+        /// Panics if blocking on `lock` would wait for an iterator of the current thread.
+        #[inline]
+        pub(crate) fn check(lock: &RawRwLock, exclusive: bool) {
+            let lock = lock as *const RawRwLock as usize;
+            let conflict = HELD
+                .try_with(|held| {
+                    let mut held = held.borrow_mut();
+                    held.retain(|entry| !entry.released.load(Ordering::Acquire));
+                    held.iter()
+                        .any(|entry| entry.lock == lock && (exclusive || entry.exclusive))
+                })
+                .unwrap_or(false);
+
+            if conflict {
+                panic!(
+                    "dashmap: deadlock detected: this call locks a shard which is held by an \
+                     iterator over the same map on the current thread. Collect what you need \
+                     from the iterator before modifying the map, or drop the iterator first."
+                );
+            }
+        }
+    } else {
+        #[derive(Clone)]
+        pub(crate) struct Held;
+
+        impl Held {
+            #[inline(always)]
+            pub(crate) fn new(_lock: &RawRwLock, _exclusive: bool) -> Self {
+                Self
+            }
+        }
+
+        #[inline(always)]
+        pub(crate) fn check(_lock: &RawRwLock, _exclusive: bool) {}
+    }
+}
+
+#[cfg(all(test, feature = "deadlock-audit"))]
+mod tests {
+    use crate::DashMap;
+
+    #[test]
+    #[should_panic(expected = "deadlock detected")]
+    fn test_insert_while_iterating() {
+        let map = DashMap::new();
+        map.insert(1, 1);
+
+        for r in map.iter() {
+            map.insert(*r.key(), 2);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "deadlock detected")]
+    fn test_get_while_iterating_mutably() {
+        let map = DashMap::new();
+        map.insert(1, 1);
+
+        for r in map.iter_mut() {
+            let _ = map.get(r.key());
+        }
+    }
+
+    #[test]
+    fn test_reads_and_finished_iterations() {
+        let map = DashMap::new();
+        map.insert(1, 1);
+
+        for r in map.iter() {
+            assert_eq!(*map.get(r.key()).unwrap(), 1);
+        }
+
+        let count = map.iter_mut().count();
+        map.insert(2, count);
+        assert_eq!(*map.get(&2).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_iterator_dropped_on_another_thread() {
+        let map = DashMap::new();
+        map.insert(1, 1);
+
+        let mut iter = map.iter();
+        assert!(iter.next().is_some());
+        std::thread::scope(|s| {
+            s.spawn(move || drop(iter));
+        });
+
+        map.insert(1, 2);
+        assert_eq!(*map.get(&1).unwrap(), 2);
+    }
+}
@@ -0,0 +1,189 @@
+//! Batched modifications of a [`DashMap`]: operations for [`DashMap::apply_batch`]
+//! and the write-combining [`BufferedWriter`].
+
+use crate::metrics::{Kind, Timer};
+use crate::DashMap;
+use core::fmt;
+use core::hash::{BuildHasher, Hash};
+use core::mem;
+
+/// An operation which is applied as part of a batch.
+pub enum Op<K, V> {
+    /// Inserts a key and a value into the map, like [`DashMap::insert`](crate::DashMap::insert).
+    Insert(K, V),
+    /// Removes a key from the map, like [`DashMap::remove`](crate::DashMap::remove).
+    Remove(K),
+    /// Modifies the value of a key, like [`DashMap::alter`](crate::DashMap::alter).
+    ///
+    /// If the closure panics, the process is aborted. The closure is `Send`, so that batches can be
+    /// built on one thread and applied on another.
+    Alter(K, Box<dyn FnOnce(&K, V) -> V + Send>),
+}
+
+impl<K, V> Op<K, V> {
+    /// Returns the key the operation applies to.
+    pub fn key(&self) -> &K {
+        match self {
+            Op::Insert(k, _) | Op::Remove(k) | Op::Alter(k, _) => k,
+        }
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for Op<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Op::Insert(k, v) => f.debug_tuple("Insert").field(k).field(v).finish(),
+            Op::Remove(k) => f.debug_tuple("Remove").field(k).finish(),
+            Op::Alter(k, _) => f
+                .debug_tuple("Alter")
+                .field(k)
+                .field(&format_args!(".."))
+                .finish(),
+        }
+    }
+}
+
+/// The result of an [`Op`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OpResult<K, V> {
+    /// The old value associated with the key, if there was one.
+    Inserted(Option<V>),
+    /// The removed entry, if the key was present.
+    Removed(Option<(K, V)>),
+    /// Whether the key was present and its value was modified.
+    Altered(bool),
+}
+
+/// A write-combining front-end for a [`DashMap`], buffering inserts per shard
+/// and applying each full buffer under a single write lock.
+///
+/// The buffers belong to the writer, not to the map, so a writer is used by one thread at a time
+/// and its buffers need no synchronization. Threads writing into a shared map each use their own
+/// writer: cloning a writer creates one with empty buffers for the same map and threshold, so a
+/// writer can be set up once and cloned into every thread.
+///
+/// Buffered entries are not visible in the map until their shard is flushed, which happens when
+/// its buffer reaches the flush threshold, on [`flush`](Self::flush) and when the writer is dropped,
+/// including when a thread's clone goes out of scope. Entries of the same key buffered by one writer
+/// are inserted in the order they were buffered.
+///
+/// # Examples
+///
+/// ```
+/// use dashmap::batch::BufferedWriter;
+/// use dashmap::DashMap;
+/// use std::thread;
+///
+/// let map = DashMap::new();
+/// let writer = BufferedWriter::new(&map, 64);
+///
+/// thread::scope(|s| {
+///     for t in 0..4 {
+///         let mut writer = writer.clone();
+///         s.spawn(move || {
+///             for i in 0..250 {
+///                 writer.insert(t * 250 + i, i);
+///             }
+///             // Dropping the writer flushes the rest.
+///         });
+///     }
+/// });
+///
+/// assert_eq!(map.len(), 1000);
+/// ```
+pub struct BufferedWriter<'a, K: Eq + Hash, V, S: BuildHasher> {
+    map: &'a DashMap<K, V, S>,
+    buffers: Box<[Vec<(u64, K, V)>]>,
+    flush_threshold: usize,
+}
+
+impl<'a, K: Eq + Hash, V, S: BuildHasher> BufferedWriter<'a, K, V, S> {
+    /// Creates a writer which flushes the buffer of a shard once it holds `flush_threshold` entries.
+    /// A `flush_threshold` of 0 is treated as 1.
+    pub fn new(map: &'a DashMap<K, V, S>, flush_threshold: usize) -> Self {
+        Self {
+            map,
+            buffers: map.shards.iter().map(|_| Vec::new()).collect(),
+            flush_threshold: flush_threshold.max(1),
+        }
+    }
+
+    /// Buffers an insertion, flushing the buffer of the key's shard if it is full.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    pub fn insert(&mut self, key: K, value: V) {
+        let hash = self.map.hash_u64(&key);
+        let idx = self.map.determine_shard(hash as usize);
+
+        let buffer = &mut self.buffers[idx];
+        buffer.push((hash, key, value));
+
+        if buffer.len() >= self.flush_threshold {
+            self.flush_shard(idx);
+        }
+    }
+
+    /// Returns the number of buffered entries which are not yet in the map.
+    pub fn pending(&self) -> usize {
+        self.buffers.iter().map(Vec::len).sum()
+    }
+
+    /// Inserts all buffered entries into the map.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    pub fn flush(&mut self) {
+        for idx in 0..self.buffers.len() {
+            self.flush_shard(idx);
+        }
+    }
+
+    fn flush_shard(&mut self, idx: usize) {
+        if self.buffers[idx].is_empty() {
+            return;
+        }
+
+        // Keep the allocation of the buffer for the next round.
+        let mut entries = mem::take(&mut self.buffers[idx]);
+        let map = self.map;
+        let timer = Timer::start();
+        let mut shard = map.shards[idx].write();
+        map.metrics.record(idx, Kind::WriteWait, timer);
+
+        for (hash, key, value) in entries.drain(..) {
+            map.apply_op(
+                &mut shard,
+                map.notify(idx, hash),
+                hash,
+                Op::Insert(key, value),
+            );
+        }
+
+        map.sync_len(idx, shard.len());
+        drop(shard);
+
+        self.buffers[idx] = entries;
+    }
+}
+
+impl<'a, K: Eq + Hash, V, S: BuildHasher> Clone for BufferedWriter<'a, K, V, S> {
+    /// Creates a writer with empty buffers for the same map and flush threshold.
+    /// The entries buffered by `self` stay in `self`.
+    fn clone(&self) -> Self {
+        Self::new(self.map, self.flush_threshold)
+    }
+}
+
+impl<'a, K: Eq + Hash, V, S: BuildHasher> Drop for BufferedWriter<'a, K, V, S> {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+impl<'a, K: Eq + Hash, V, S: BuildHasher> fmt::Debug for BufferedWriter<'a, K, V, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BufferedWriter")
+            .field("pending", &self.pending())
+            .field("flush_threshold", &self.flush_threshold)
+            .finish()
+    }
+}
@@ -0,0 +1,239 @@
+//! Detection of shard lock acquisitions which block async worker threads.
+//!
+//! With the `blocking-detect` feature enabled, blocking lock acquisitions which had to wait are timed,
+//! and waits longer than the threshold of the installed [`BlockingDetector`] are reported if the thread
+//! is an async worker. Without the feature, everything here compiles to nothing.
+
+use crate::lock::RawRwLock;
+use cfg_if::cfg_if;
+
+cfg_if! {
+    if #[cfg(feature = "blocking-detect")] {
+        use core::fmt;
+        use core::time::Duration;
+        use std::backtrace::Backtrace;
+        use std::sync::RwLock;
+        use std::time::Instant;
+
+        static DETECTOR: RwLock<Option<BlockingDetector>> = RwLock::new(None);
+
+        /// Reports shard lock acquisitions which block async worker threads for too long.
+        ///
+        /// Async runtimes run many tasks on few threads, so a task waiting for a contended
+        /// shard lock stalls every other task of its thread. Once [installed](Self::install),
+        /// every blocking acquisition of a shard lock which waits for longer than the threshold
+        /// asks `is_async_worker` whether the current thread belongs to a runtime, and if it does,
+        /// reports the shard and a backtrace. By default the report is printed to stderr.
+        ///
+        /// Only acquisitions which had to wait are timed, so uncontended locking stays as fast
+        /// as without the feature.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// use dashmap::BlockingDetector;
+        /// use std::time::Duration;
+        ///
+        /// fn is_async_worker() -> bool {
+        ///     std::thread::current()
+        ///         .name()
+        ///         .map_or(false, |name| name.starts_with("tokio-runtime-worker"))
+        /// }
+        ///
+        /// BlockingDetector::new(is_async_worker)
+        ///     .threshold(Duration::from_millis(5))
+        ///     .install();
+        /// ```
+        #[derive(Clone, Copy)]
+        pub struct BlockingDetector {
+            threshold: Duration,
+            is_async_worker: fn() -> bool,
+            report: fn(&BlockedLock),
+        }
+
+        impl BlockingDetector {
+            /// Creates a detector for the threads `is_async_worker` returns `true` on,
+            /// with a threshold of one millisecond, printing its reports to stderr.
+            pub fn new(is_async_worker: fn() -> bool) -> Self {
+                Self {
+                    threshold: Duration::from_millis(1),
+                    is_async_worker,
+                    report: |blocked| eprintln!("warning: {}", blocked),
+                }
+            }
+
+            /// Sets how long an acquisition may wait before it is reported.
+            pub fn threshold(mut self, threshold: Duration) -> Self {
+                self.threshold = threshold;
+                self
+            }
+
+            /// Sets the function reports are passed to, for example to forward them to a logger.
+            pub fn report(mut self, report: fn(&BlockedLock)) -> Self {
+                self.report = report;
+                self
+            }
+
+            /// Enables the detector for every map, replacing any detector installed before.
+            pub fn install(self) {
+                *DETECTOR.write().unwrap_or_else(|e| e.into_inner()) = Some(self);
+            }
+
+            /// Disables the installed detector, if any.
+            pub fn uninstall() {
+                *DETECTOR.write().unwrap_or_else(|e| e.into_inner()) = None;
+            }
+        }
+
+        impl fmt::Debug for BlockingDetector {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_struct("BlockingDetector")
+                    .field("threshold", &self.threshold)
+                    .finish_non_exhaustive()
+            }
+        }
+
+        /// A shard lock acquisition on an async worker thread which waited for longer than the threshold.
+        #[derive(Debug)]
+        pub struct BlockedLock {
+            shard: Option<usize>,
+            exclusive: bool,
+            waited: Duration,
+            backtrace: Backtrace,
+        }
+
+        impl BlockedLock {
+            /// Returns the index of the shard, or `None` for maps created with `from_shards`.
+            pub fn shard(&self) -> Option<usize> {
+                self.shard
+            }
+
+            /// Returns `true` if the lock was acquired for writing.
+            pub fn exclusive(&self) -> bool {
+                self.exclusive
+            }
+
+            /// Returns how long the acquisition waited.
+            pub fn waited(&self) -> Duration {
+                self.waited
+            }
+
+            /// Returns the backtrace of the acquisition.
+            pub fn backtrace(&self) -> &Backtrace {
+                &self.backtrace
+            }
+        }
+
+        impl fmt::Display for BlockedLock {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                let kind = if self.exclusive { "write" } else { "read" };
+
+                write!(f, "async worker thread blocked for {:?} on the {} lock of ", self.waited, kind)?;
+
+                match self.shard {
+                    Some(shard) => write!(f, "shard {}", shard)?,
+                    None => write!(f, "a shard")?,
+                }
+
+                write!(f, "\n{}", self.backtrace)
+            }
+        }
+
+        /// The start of a blocking lock acquisition.
+        #[derive(Clone, Copy)]
+        pub(crate) struct Timer(Instant);
+
+        impl Timer {
+            #[inline]
+            pub(crate) fn start() -> Self {
+                Self(Instant::now())
+            }
+        }
+
+        /// Reports the acquisition of `lock` which started at `timer`, if it blocked an async worker for too long.
+        #[cold]
+        pub(crate) fn check(lock: &RawRwLock, exclusive: bool, timer: Timer) {
+            let waited = timer.0.elapsed();
+            let detector = match *DETECTOR.read().unwrap_or_else(|e| e.into_inner()) {
+                Some(detector) if waited >= detector.threshold => detector,
+                _ => return,
+            };
+
+            if (detector.is_async_worker)() {
+                (detector.report)(&BlockedLock {
+                    shard: lock.shard(),
+                    exclusive,
+                    waited,
+                    backtrace: Backtrace::force_capture(),
+                });
+            }
+        }
+    } else {
+        #[derive(Clone, Copy)]
+        pub(crate) struct Timer;
+
+        impl Timer {
+            #[inline(always)]
+            pub(crate) fn start() -> Self {
+                Self
+            }
+        }
+
+        #[inline(always)]
+        pub(crate) fn check(_lock: &RawRwLock, _exclusive: bool, _timer: Timer) {}
+    }
+}
+
+#[cfg(all(test, feature = "blocking-detect"))]
+mod tests {
+    use super::{BlockedLock, BlockingDetector};
+    use crate::DashMap;
+    use core::cell::Cell;
+    use core::time::Duration;
+    use std::sync::Mutex;
+
+    std::thread_local! {
+        static WORKER: Cell<bool> = Cell::new(false);
+    }
+
+    static REPORTS: Mutex<Vec<(Option<usize>, bool)>> = Mutex::new(Vec::new());
+
+    fn report(blocked: &BlockedLock) {
+        assert!(blocked.waited() >= Duration::from_millis(1));
+        assert!(blocked.to_string().contains("read lock"));
+        REPORTS
+            .lock()
+            .unwrap()
+            .push((blocked.shard(), blocked.exclusive()));
+    }
+
+    #[test]
+    fn test_blocking_detector() {
+        BlockingDetector::new(|| WORKER.with(Cell::get))
+            .report(report)
+            .install();
+
+        let map = DashMap::with_shard_amount(4);
+        map.insert(1, 1);
+        let shard = map.determine_shard(map.hash_u64(&1) as usize);
+
+        let mut guard = map.get_mut(&1).unwrap();
+
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                WORKER.with(|worker| worker.set(true));
+                assert_eq!(*map.get(&1).unwrap(), 2);
+            });
+
+            // Threads which aren't async workers are never reported.
+            s.spawn(|| assert_eq!(*map.get(&1).unwrap(), 2));
+
+            std::thread::sleep(Duration::from_millis(20));
+            *guard = 2;
+            drop(guard);
+        });
+
+        BlockingDetector::uninstall();
+        assert_eq!(*REPORTS.lock().unwrap(), [(Some(shard), false)]);
+    }
+}
@@ -0,0 +1,381 @@
+//! Traits abstracting over concurrent maps, so that code can be written against a [`DashMap`],
+//! a [`ReadOnlyView`] or a fake or instrumented map used in tests.
+
+use crate::iter::Iter;
+use crate::mapref::multiple::RefMulti;
+use crate::mapref::one::{Ref, RefMut};
+use crate::{DashMap, ReadOnlyView};
+use core::borrow::Borrow;
+use core::hash::{BuildHasher, Hash};
+use core::ops::DerefMut;
+
+/// A reference to an entry of a map, such as a [`Ref`] or the pairs yielded by [`ReadOnlyView::iter`].
+pub trait MapRef<K, V> {
+    /// Returns the key of the entry.
+    fn key(&self) -> &K;
+
+    /// Returns the value of the entry.
+    fn value(&self) -> &V;
+}
+
+/// The read operations of a concurrent map.
+///
+/// It is implemented by [`DashMap`] and [`ReadOnlyView`].
+///
+/// # Examples
+///
+/// ```
+/// use dashmap::{ConcurrentMap, DashMap, MapRef};
+///
+/// fn total<M: ConcurrentMap<&'static str, u32>>(map: &M) -> u32 {
+///     map.iter().map(|r| *r.value()).sum()
+/// }
+///
+/// let map = DashMap::new();
+/// map.insert("a", 1);
+/// map.insert("b", 2);
+/// assert_eq!(total(&map), 3);
+///
+/// let view = map.into_read_only();
+/// assert_eq!(total(&view), 3);
+/// ```
+pub trait ConcurrentMap<K, V> {
+    /// The reference returned by [`get`](Self::get).
+    type Ref<'a>: MapRef<K, V>
+    where
+        Self: 'a;
+
+    /// The reference yielded by [`iter`](Self::iter).
+    type IterRef<'a>: MapRef<K, V>
+    where
+        Self: 'a;
+
+    /// The iterator returned by [`iter`](Self::iter).
+    type Iter<'a>: Iterator<Item = Self::IterRef<'a>>
+    where
+        Self: 'a;
+
+    /// Returns a reference to the entry of a key.
+    fn get<Q>(&self, key: &Q) -> Option<Self::Ref<'_>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized;
+
+    /// Returns `true` if the map contains a key.
+    fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Returns the number of entries in the map.
+    fn len(&self) -> usize;
+
+    /// Returns `true` if the map contains no entries.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns an iterator over the entries of the map.
+    fn iter(&self) -> Self::Iter<'_>;
+}
+
+/// The write operations of a concurrent map, which can all be called through a shared reference.
+///
+/// It is implemented by [`DashMap`].
+///
+/// # Examples
+///
+/// ```
+/// use dashmap::{ConcurrentMapMut, DashMap};
+///
+/// fn hit<M: ConcurrentMapMut<String, u32>>(hits: &M, path: &str) {
+///     hits.upsert(path.to_owned(), || 1, |_, count| *count += 1);
+/// }
+///
+/// let hits = DashMap::new();
+/// hit(&hits, "/index");
+/// hit(&hits, "/index");
+/// assert_eq!(*hits.get("/index").unwrap(), 2);
+/// ```
+pub trait ConcurrentMapMut<K, V>: ConcurrentMap<K, V> {
+    /// The mutable reference returned by [`get_mut`](Self::get_mut) and [`upsert`](Self::upsert).
+    type RefMut<'a>: MapRef<K, V> + DerefMut<Target = V>
+    where
+        Self: 'a;
+
+    /// Returns a mutable reference to the entry of a key.
+    fn get_mut<Q>(&self, key: &Q) -> Option<Self::RefMut<'_>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized;
+
+    /// Inserts a key and a value, returning the old value if the key was present.
+    fn insert(&self, key: K, value: V) -> Option<V>;
+
+    /// Removes the entry of a key, returning it if it was present.
+    fn remove<Q>(&self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized;
+
+    /// Inserts the value produced by `on_insert` if the key is vacant, otherwise modifies the existing
+    /// value with `on_update`, like [`Entry::and_modify`](crate::Entry::and_modify) followed by
+    /// [`Entry::or_insert_with`](crate::Entry::or_insert_with).
+    fn upsert(
+        &self,
+        key: K,
+        on_insert: impl FnOnce() -> V,
+        on_update: impl FnOnce(&K, &mut V),
+    ) -> Self::RefMut<'_>;
+}
+
+impl<K: Eq + Hash, V> MapRef<K, V> for Ref<'_, K, V> {
+    fn key(&self) -> &K {
+        self.key()
+    }
+
+    fn value(&self) -> &V {
+        self.value()
+    }
+}
+
+impl<K: Eq + Hash, V> MapRef<K, V> for RefMut<'_, K, V> {
+    fn key(&self) -> &K {
+        self.key()
+    }
+
+    fn value(&self) -> &V {
+        self.value()
+    }
+}
+
+impl<K: Eq + Hash, V> MapRef<K, V> for RefMulti<'_, K, V> {
+    fn key(&self) -> &K {
+        self.key()
+    }
+
+    fn value(&self) -> &V {
+        self.value()
+    }
+}
+
+impl<K, V> MapRef<K, V> for (&K, &V) {
+    fn key(&self) -> &K {
+        self.0
+    }
+
+    fn value(&self) -> &V {
+        self.1
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher> ConcurrentMap<K, V> for DashMap<K, V, S> {
+    type Ref<'a> = Ref<'a, K, V> where Self: 'a;
+    type IterRef<'a> = RefMulti<'a, K, V> where Self: 'a;
+    type Iter<'a> = Iter<'a, K, V> where Self: 'a;
+
+    fn get<Q>(&self, key: &Q) -> Option<Ref<'_, K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        DashMap::get(self, key)
+    }
+
+    fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        DashMap::contains_key(self, key)
+    }
+
+    fn len(&self) -> usize {
+        DashMap::len(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        DashMap::is_empty(self)
+    }
+
+    fn iter(&self) -> Iter<'_, K, V> {
+        DashMap::iter(self)
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher> ConcurrentMapMut<K, V> for DashMap<K, V, S> {
+    type RefMut<'a> = RefMut<'a, K, V> where Self: 'a;
+
+    fn get_mut<Q>(&self, key: &Q) -> Option<RefMut<'_, K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        DashMap::get_mut(self, key)
+    }
+
+    fn insert(&self, key: K, value: V) -> Option<V> {
+        DashMap::insert(self, key, value)
+    }
+
+    fn remove<Q>(&self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        DashMap::remove(self, key)
+    }
+
+    fn upsert(
+        &self,
+        key: K,
+        on_insert: impl FnOnce() -> V,
+        on_update: impl FnOnce(&K, &mut V),
+    ) -> RefMut<'_, K, V> {
+        DashMap::upsert(self, key, on_insert, on_update)
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher> ConcurrentMap<K, V> for ReadOnlyView<K, V, S> {
+    type Ref<'a> = (&'a K, &'a V) where Self: 'a;
+    type IterRef<'a> = (&'a K, &'a V) where Self: 'a;
+    type Iter<'a> = Box<dyn Iterator<Item = (&'a K, &'a V)> + 'a> where Self: 'a;
+
+    fn get<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        ReadOnlyView::get_key_value(self, key)
+    }
+
+    fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        ReadOnlyView::contains_key(self, key)
+    }
+
+    fn len(&self) -> usize {
+        ReadOnlyView::len(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        ReadOnlyView::is_empty(self)
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        Box::new(ReadOnlyView::iter(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConcurrentMap, ConcurrentMapMut, MapRef};
+    use crate::DashMap;
+    use core::borrow::Borrow;
+    use core::hash::Hash;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// A map counting the writes made through it.
+    struct Counting {
+        map: DashMap<u32, u32>,
+        writes: AtomicUsize,
+    }
+
+    impl ConcurrentMap<u32, u32> for Counting {
+        type Ref<'a> = <DashMap<u32, u32> as ConcurrentMap<u32, u32>>::Ref<'a>;
+        type IterRef<'a> = <DashMap<u32, u32> as ConcurrentMap<u32, u32>>::IterRef<'a>;
+        type Iter<'a> = <DashMap<u32, u32> as ConcurrentMap<u32, u32>>::Iter<'a>;
+
+        fn get<Q>(&self, key: &Q) -> Option<Self::Ref<'_>>
+        where
+            u32: Borrow<Q>,
+            Q: Hash + Eq + ?Sized,
+        {
+            self.map.get(key)
+        }
+
+        fn len(&self) -> usize {
+            self.map.len()
+        }
+
+        fn iter(&self) -> Self::Iter<'_> {
+            self.map.iter()
+        }
+    }
+
+    impl ConcurrentMapMut<u32, u32> for Counting {
+        type RefMut<'a> = <DashMap<u32, u32> as ConcurrentMapMut<u32, u32>>::RefMut<'a>;
+
+        fn get_mut<Q>(&self, key: &Q) -> Option<Self::RefMut<'_>>
+        where
+            u32: Borrow<Q>,
+            Q: Hash + Eq + ?Sized,
+        {
+            self.writes.fetch_add(1, Ordering::Relaxed);
+            self.map.get_mut(key)
+        }
+
+        fn insert(&self, key: u32, value: u32) -> Option<u32> {
+            self.writes.fetch_add(1, Ordering::Relaxed);
+            self.map.insert(key, value)
+        }
+
+        fn remove<Q>(&self, key: &Q) -> Option<(u32, u32)>
+        where
+            u32: Borrow<Q>,
+            Q: Hash + Eq + ?Sized,
+        {
+            self.writes.fetch_add(1, Ordering::Relaxed);
+            self.map.remove(key)
+        }
+
+        fn upsert(
+            &self,
+            key: u32,
+            on_insert: impl FnOnce() -> u32,
+            on_update: impl FnOnce(&u32, &mut u32),
+        ) -> Self::RefMut<'_> {
+            self.writes.fetch_add(1, Ordering::Relaxed);
+            self.map.upsert(key, on_insert, on_update)
+        }
+    }
+
+    fn exercise<M: ConcurrentMapMut<u32, u32>>(map: &M) {
+        for i in 0..10 {
+            map.insert(i, i);
+        }
+
+        *map.get_mut(&3).unwrap() += 10;
+        map.upsert(4, || 0, |_, v| *v *= 2);
+        map.upsert(20, || 1, |_, v| *v *= 2);
+        assert_eq!(map.remove(&0), Some((0, 0)));
+
+        assert_eq!(map.len(), 10);
+        assert!(!map.contains_key(&0));
+        assert_eq!(*map.get(&3).unwrap().value(), 13);
+        assert_eq!(map.iter().map(|r| *r.value()).sum::<u32>(), 45 + 10 + 4 + 1);
+    }
+
+    #[test]
+    fn test_concurrent_map_backends() {
+        let map = DashMap::new();
+        exercise(&map);
+
+        let view = map.into_read_only();
+        assert_eq!(ConcurrentMap::get(&view, &20).map(|r| *r.value()), Some(1));
+        assert_eq!(ConcurrentMap::iter(&view).count(), 10);
+
+        let counting = Counting {
+            map: DashMap::new(),
+            writes: AtomicUsize::new(0),
+        };
+        exercise(&counting);
+        assert_eq!(counting.writes.load(Ordering::Relaxed), 14);
+    }
+}
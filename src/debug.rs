@@ -0,0 +1,56 @@
+use crate::lock::RawRwLock;
+use crate::{DashMap, HashMap};
+use core::fmt;
+use lock_api::RwLockReadGuard;
+
+/// Formats a map without blocking on its shard locks, returned by [`DashMap::debug_lossy`].
+///
+/// Shards which are write-locked, for example by a [`RefMut`](crate::mapref::one::RefMut) held while
+/// logging, are printed as a single `<locked>: <shard N>` entry instead of their contents.
+pub struct DebugLossy<'a, K, V, S> {
+    pub(crate) map: &'a DashMap<K, V, S>,
+}
+
+struct Locked(usize);
+
+impl fmt::Debug for Locked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<shard {}>", self.0)
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, S> fmt::Debug for DebugLossy<'_, K, V, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut pmap = f.debug_map();
+
+        for (idx, shard) in self.map.shards.iter().enumerate() {
+            match shard.try_read() {
+                Some(shard) => {
+                    pmap.entries(shard.iter().map(|(k, v)| (k, v)));
+                }
+                None => {
+                    pmap.entry(&format_args!("<locked>"), &Locked(idx));
+                }
+            }
+        }
+
+        pmap.finish()
+    }
+}
+
+/// A map whose shards are all read-locked, for formatting it, returned by [`DashMap::try_debug`].
+pub struct TryDebug<'a, K, V> {
+    pub(crate) shards: Vec<RwLockReadGuard<'a, RawRwLock, HashMap<K, V>>>,
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for TryDebug<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(
+                self.shards
+                    .iter()
+                    .flat_map(|shard| shard.iter().map(|(k, v)| (k, v))),
+            )
+            .finish()
+    }
+}
@@ -0,0 +1,291 @@
+use crate::lock::RwLockReadGuardDetached;
+use crate::mapref::multiple::RefMulti;
+use crate::mapref::one::{MappedRef, MappedRefMut};
+use crate::DashMap;
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::{BuildHasher, Hash};
+use std::collections::hash_map::{self, RandomState};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// A concurrent map with composite keys `(K1, K2)`, supporting lookups by `K1` alone.
+///
+/// Entries are grouped by their first key: every `K1` owns an inner map from `K2` to the values,
+/// and all entries of a `K1` live in the same shard. Every operation therefore takes a single shard
+/// lock, including [`remove_all_k1`](Self::remove_all_k1) and [`iter_k1`](Self::iter_k1),
+/// and removing the last entry of a group removes the group as well, so no empty groups are left behind.
+///
+/// # Examples
+///
+/// ```
+/// use dashmap::DashMap2;
+///
+/// let grades = DashMap2::new();
+/// grades.insert("alice", "math", 5);
+/// grades.insert("alice", "art", 4);
+/// grades.insert("bob", "math", 3);
+///
+/// assert_eq!(*grades.get("alice", "art").unwrap(), 4);
+/// assert_eq!(grades.iter_k1("alice").count(), 2);
+///
+/// grades.remove_all_k1("alice");
+/// assert_eq!(grades.len(), 1);
+/// ```
+pub struct DashMap2<K1, K2, V, S = RandomState> {
+    inner: DashMap<K1, HashMap<K2, V, S>, S>,
+}
+
+impl<K1: Eq + Hash, K2: Eq + Hash, V> DashMap2<K1, K2, V, RandomState> {
+    /// Creates a new DashMap2 with a capacity of 0.
+    pub fn new() -> Self {
+        Self::with_hasher(RandomState::new())
+    }
+}
+
+impl<K1: Eq + Hash, K2: Eq + Hash, V, S: Default + BuildHasher + Clone> Default
+    for DashMap2<K1, K2, V, S>
+{
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<K1: Eq + Hash, K2: Eq + Hash, V, S: BuildHasher + Clone> DashMap2<K1, K2, V, S> {
+    /// Creates a new DashMap2 with a capacity of 0 and the provided hasher,
+    /// which is used for both the outer map and the groups.
+    pub fn with_hasher(hasher: S) -> Self {
+        Self {
+            inner: DashMap::with_hasher(hasher),
+        }
+    }
+
+    /// Consumes the map, returning the underlying [`DashMap`] of groups.
+    pub fn into_inner(self) -> DashMap<K1, HashMap<K2, V, S>, S> {
+        self.inner
+    }
+
+    /// Inserts a value under a composite key. Returns the old value of the key if there was one.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    pub fn insert(&self, k1: K1, k2: K2, value: V) -> Option<V> {
+        self.inner
+            .entry(k1)
+            .or_insert_with(|| HashMap::with_hasher(self.inner.hasher().clone()))
+            .insert(k2, value)
+    }
+
+    /// Get an immutable reference to the value of a composite key.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    pub fn get<Q1, Q2>(&self, k1: &Q1, k2: &Q2) -> Option<MappedRef<'_, K1, V>>
+    where
+        K1: Borrow<Q1>,
+        K2: Borrow<Q2>,
+        Q1: Hash + Eq + ?Sized,
+        Q2: Hash + Eq + ?Sized,
+    {
+        self.inner.get(k1)?.try_map(|group| group.get(k2)).ok()
+    }
+
+    /// Get a mutable reference to the value of a composite key.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    pub fn get_mut<Q1, Q2>(&self, k1: &Q1, k2: &Q2) -> Option<MappedRefMut<'_, K1, V>>
+    where
+        K1: Borrow<Q1>,
+        K2: Borrow<Q2>,
+        Q1: Hash + Eq + ?Sized,
+        Q2: Hash + Eq + ?Sized,
+    {
+        self.inner
+            .get_mut(k1)?
+            .try_map(|group| group.get_mut(k2))
+            .ok()
+    }
+
+    /// Creates an iterator over the second keys and values of all entries of a first key,
+    /// in arbitrary order. The iterator is empty if the first key has no entries.
+    ///
+    /// The shard of the first key is read-locked until the iterator and all references it yielded are dropped.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap2;
+    ///
+    /// let grades = DashMap2::new();
+    /// grades.insert("alice", "math", 5);
+    /// grades.insert("alice", "art", 4);
+    ///
+    /// let total: u32 = grades.iter_k1("alice").map(|r| *r.value()).sum();
+    /// assert_eq!(total, 9);
+    /// assert_eq!(grades.iter_k1("bob").count(), 0);
+    /// ```
+    pub fn iter_k1<Q1>(&self, k1: &Q1) -> IterK1<'_, K2, V>
+    where
+        K1: Borrow<Q1>,
+        Q1: Hash + Eq + ?Sized,
+    {
+        let group = self.inner.get(k1).map(|r| {
+            let (guard, _, group) = r.into_parts();
+            (Arc::new(guard), group.iter())
+        });
+
+        IterK1 { group }
+    }
+
+    /// Checks if the map contains a composite key.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    pub fn contains_key<Q1, Q2>(&self, k1: &Q1, k2: &Q2) -> bool
+    where
+        K1: Borrow<Q1>,
+        K2: Borrow<Q2>,
+        Q1: Hash + Eq + ?Sized,
+        Q2: Hash + Eq + ?Sized,
+    {
+        self.get(k1, k2).is_some()
+    }
+
+    /// Removes the value of a composite key, returning it if it existed.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    pub fn remove<Q1, Q2>(&self, k1: &Q1, k2: &Q2) -> Option<V>
+    where
+        K1: Borrow<Q1>,
+        K2: Borrow<Q2>,
+        Q1: Hash + Eq + ?Sized,
+        Q2: Hash + Eq + ?Sized,
+    {
+        let mut removed = None;
+
+        self.inner.remove_if_mut(k1, |_, group| {
+            removed = group.remove(k2);
+            group.is_empty()
+        });
+
+        removed
+    }
+
+    /// Removes all entries of a first key, returning them if there were any.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    pub fn remove_all_k1<Q1>(&self, k1: &Q1) -> Option<HashMap<K2, V, S>>
+    where
+        K1: Borrow<Q1>,
+        Q1: Hash + Eq + ?Sized,
+    {
+        self.inner.remove(k1).map(|(_, group)| group)
+    }
+
+    /// Fetches the total number of values stored in the map.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    pub fn len(&self) -> usize {
+        self.inner.iter().map(|group| group.len()).sum()
+    }
+
+    /// Checks if the map is empty or not.
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Removes all values from the map.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    pub fn clear(&self) {
+        self.inner.clear()
+    }
+}
+
+/// Iterator over the entries of a first key of a [`DashMap2`], created by [`DashMap2::iter_k1`].
+///
+/// Yields the second key and the value of every entry.
+pub struct IterK1<'a, K2, V> {
+    group: Option<(Arc<RwLockReadGuardDetached<'a>>, hash_map::Iter<'a, K2, V>)>,
+}
+
+impl<'a, K2: Eq + Hash, V> Iterator for IterK1<'a, K2, V> {
+    type Item = RefMulti<'a, K2, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (guard, iter) = self.group.as_mut()?;
+        let (k2, v) = iter.next()?;
+
+        Some(RefMulti::new(Arc::clone(guard), k2, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.group {
+            Some((_, iter)) => iter.size_hint(),
+            None => (0, Some(0)),
+        }
+    }
+}
+
+impl<'a, K2: Eq + Hash, V> ExactSizeIterator for IterK1<'a, K2, V> {}
+
+impl<K1, K2, V, S> fmt::Debug for DashMap2<K1, K2, V, S>
+where
+    K1: Eq + Hash + fmt::Debug,
+    K2: fmt::Debug,
+    V: fmt::Debug,
+    S: BuildHasher,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut pmap = f.debug_map();
+
+        for group in self.inner.iter() {
+            for (k2, v) in group.value() {
+                pmap.entry(&(group.key(), k2), v);
+            }
+        }
+
+        pmap.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DashMap2;
+
+    #[test]
+    fn test_groups() {
+        let map = DashMap2::new();
+
+        for i in 0..10 {
+            for j in 0..10 {
+                map.insert(i, j, i * j);
+            }
+        }
+
+        assert_eq!(map.len(), 100);
+        assert_eq!(map.insert(3, 4, 0), Some(12));
+        *map.get_mut(&3, &4).unwrap() += 1;
+        assert_eq!(*map.get(&3, &4).unwrap(), 1);
+        assert!(map.get(&3, &10).is_none());
+
+        let mut row: Vec<(i32, i32)> = map.iter_k1(&3).map(|r| (*r.key(), *r.value())).collect();
+        row.sort_unstable();
+        assert_eq!(row[4], (4, 1));
+        assert_eq!(row.len(), 10);
+
+        // The shard stays read-locked while yielded references are alive.
+        let r = map.iter_k1(&3).next().unwrap();
+        assert!(map.inner.try_get_mut(&3).is_locked());
+        drop(r);
+
+        assert_eq!(map.remove_all_k1(&5).unwrap().len(), 10);
+        assert_eq!(map.iter_k1(&5).len(), 0);
+
+        for j in 0..10 {
+            assert!(map.remove(&7, &j).is_some());
+        }
+
+        assert_eq!(map.iter_k1(&7).count(), 0);
+        assert_eq!(map.into_inner().len(), 8);
+    }
+}
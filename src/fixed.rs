@@ -0,0 +1,301 @@
+use crate::lock::{RawRwLock, RwLock, RwLockReadGuardDetached, RwLockWriteGuardDetached};
+use crate::mapref::entry::{Entry, OccupiedEntry, VacantEntry};
+use crate::mapref::one::{Ref, RefMut};
+use crate::{util, HashMap};
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::{BuildHasher, Hash, Hasher};
+use crossbeam_utils::CachePadded;
+use hashbrown::hash_table;
+use lock_api::RawRwLock as _;
+use std::collections::hash_map::RandomState;
+
+/// A [`DashMap`](crate::DashMap) with a shard amount fixed at compile time.
+///
+/// The shards are stored inline in a fixed-size array instead of a boxed slice,
+/// which removes a pointer indirection and allows maps to be created in a `const` context,
+/// for example to place them in a `static`. `SHARDS` must be greater than 1 and a power of two,
+/// which is checked at compile time.
+///
+/// Only the core operations of `DashMap` are available.
+///
+/// # Examples
+///
+/// ```
+/// use dashmap::FixedDashMap;
+/// use std::collections::hash_map::DefaultHasher;
+/// use std::hash::BuildHasher;
+///
+/// struct DefaultState;
+///
+/// impl BuildHasher for DefaultState {
+///     type Hasher = DefaultHasher;
+///
+///     fn build_hasher(&self) -> DefaultHasher {
+///         DefaultHasher::new()
+///     }
+/// }
+///
+/// static SESSIONS: FixedDashMap<u32, &str, 8, DefaultState> = FixedDashMap::with_hasher(DefaultState);
+///
+/// SESSIONS.insert(1, "alice");
+/// assert_eq!(*SESSIONS.get(&1).unwrap(), "alice");
+/// ```
+pub struct FixedDashMap<K, V, const SHARDS: usize, S = RandomState> {
+    shards: [CachePadded<RwLock<HashMap<K, V>>>; SHARDS],
+    hasher: S,
+}
+
+impl<K, V, const SHARDS: usize, S> FixedDashMap<K, V, SHARDS, S> {
+    const SHIFT: usize = {
+        assert!(
+            SHARDS > 1 && SHARDS.is_power_of_two(),
+            "the shard amount must be greater than 1 and a power of two"
+        );
+
+        util::ptr_size_bits() - SHARDS.trailing_zeros() as usize
+    };
+
+    #[allow(clippy::declare_interior_mutable_const)]
+    const EMPTY_SHARD: CachePadded<RwLock<HashMap<K, V>>> =
+        CachePadded::new(RwLock::const_new(RawRwLock::INIT, HashMap::new()));
+
+    /// Creates a new FixedDashMap with a capacity of 0 and the provided hasher.
+    pub const fn with_hasher(hasher: S) -> Self {
+        let _ = Self::SHIFT;
+
+        Self {
+            shards: [Self::EMPTY_SHARD; SHARDS],
+            hasher,
+        }
+    }
+
+    /// Returns the amount of shards, `SHARDS`.
+    pub const fn shard_amount(&self) -> usize {
+        SHARDS
+    }
+
+    fn determine_shard(hash: u64) -> usize {
+        // Leave the high 7 bits for the HashBrown SIMD tag.
+        ((hash as usize) << 7) >> Self::SHIFT
+    }
+}
+
+impl<K, V, const SHARDS: usize> FixedDashMap<K, V, SHARDS, RandomState> {
+    /// Creates a new FixedDashMap with a capacity of 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::FixedDashMap;
+    ///
+    /// let reviews = FixedDashMap::<_, _, 16>::new();
+    /// reviews.insert("Veloren", "What a fantastic game!");
+    /// ```
+    pub fn new() -> Self {
+        Self::with_hasher(RandomState::new())
+    }
+}
+
+impl<K, V, const SHARDS: usize, S: Default> Default for FixedDashMap<K, V, SHARDS, S> {
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<'a, K: 'a + Eq + Hash, V: 'a, const SHARDS: usize, S: BuildHasher>
+    FixedDashMap<K, V, SHARDS, S>
+{
+    fn hash_u64<T: Hash>(&self, item: &T) -> u64 {
+        let mut hasher = self.hasher.build_hasher();
+
+        item.hash(&mut hasher);
+
+        hasher.finish()
+    }
+
+    /// Inserts a key and a value into the map. Returns the old value associated with the key if there was one.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    pub fn insert(&self, key: K, value: V) -> Option<V> {
+        match self.entry(key) {
+            Entry::Occupied(mut o) => Some(o.insert(value)),
+            Entry::Vacant(v) => {
+                v.insert(value);
+                None
+            }
+        }
+    }
+
+    /// Removes an entry from the map, returning the key and value if they existed in the map.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    pub fn remove<Q>(&self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.hash_u64(&key);
+
+        let mut shard = self.shards[Self::determine_shard(hash)].write();
+
+        match shard.find_entry(hash, |(k, _v)| key == k.borrow()) {
+            Ok(entry) => Some(entry.remove().0),
+            Err(_) => None,
+        }
+    }
+
+    /// Get an immutable reference to an entry in the map.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    pub fn get<Q>(&'a self, key: &Q) -> Option<Ref<'a, K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.hash_u64(&key);
+
+        let shard = self.shards[Self::determine_shard(hash)].read();
+        // SAFETY: The data will not outlive the guard, since we pass the guard to `Ref`.
+        let (guard, shard) = unsafe { RwLockReadGuardDetached::detach_from(shard) };
+
+        shard
+            .find(hash, |(k, _v)| key == k.borrow())
+            .map(|(k, v)| Ref::new(guard, k, v))
+    }
+
+    /// Get a mutable reference to an entry in the map.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    pub fn get_mut<Q>(&'a self, key: &Q) -> Option<RefMut<'a, K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.hash_u64(&key);
+
+        let shard = self.shards[Self::determine_shard(hash)].write();
+        // SAFETY: The data will not outlive the guard, since we pass the guard to `RefMut`.
+        let (guard, shard) = unsafe { RwLockWriteGuardDetached::detach_from(shard) };
+
+        shard
+            .find_mut(hash, |(k, _v)| key == k.borrow())
+            .map(|(k, v)| RefMut::new(guard, k, v))
+    }
+
+    /// Checks if the map contains a specific key.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Advanced entry API that tries to mimic `std::collections::HashMap`.
+    /// See the documentation on `dashmap::mapref::entry` for more details.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    pub fn entry(&'a self, key: K) -> Entry<'a, K, V> {
+        let hash = self.hash_u64(&key);
+
+        let shard = self.shards[Self::determine_shard(hash)].write();
+        // SAFETY: The data will not outlive the guard, since we pass the guard to `Entry`.
+        let (guard, shard) = unsafe { RwLockWriteGuardDetached::detach_from(shard) };
+
+        match shard.entry(
+            hash,
+            |(k, _v)| k == &key,
+            |(k, _v)| {
+                let mut hasher = self.hasher.build_hasher();
+                k.hash(&mut hasher);
+                hasher.finish()
+            },
+        ) {
+            hash_table::Entry::Occupied(entry) => {
+                Entry::Occupied(OccupiedEntry::new(guard, key, entry, None, None, None))
+            }
+            hash_table::Entry::Vacant(entry) => {
+                Entry::Vacant(VacantEntry::new(guard, key, entry, None, None, None, None))
+            }
+        }
+    }
+
+    /// Retain elements that whose predicates return true
+    /// and discard elements whose predicates return false.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    pub fn retain(&self, mut f: impl FnMut(&K, &mut V) -> bool) {
+        for shard in self.shards.iter() {
+            shard.write().retain(|(k, v)| f(k, v));
+        }
+    }
+
+    /// Removes all key-value pairs in the map.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    pub fn clear(&self) {
+        self.retain(|_, _| false)
+    }
+
+    /// Fetches the total number of key-value pairs stored in the map.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|s| s.read().len()).sum()
+    }
+
+    /// Checks if the map is empty or not.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<K: Eq + Hash + fmt::Debug, V: fmt::Debug, const SHARDS: usize, S: BuildHasher> fmt::Debug
+    for FixedDashMap<K, V, SHARDS, S>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut pmap = f.debug_map();
+
+        for shard in self.shards.iter() {
+            for (k, v) in shard.read().iter() {
+                pmap.entry(k, v);
+            }
+        }
+
+        pmap.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FixedDashMap;
+
+    #[test]
+    fn test_basic() {
+        let map = FixedDashMap::<_, _, 4>::new();
+
+        for i in 0..64 {
+            map.insert(i, i);
+        }
+
+        assert_eq!(map.len(), 64);
+        assert_eq!(map.shard_amount(), 4);
+
+        *map.get_mut(&3).unwrap() += 10;
+        assert_eq!(*map.get(&3).unwrap(), 13);
+
+        assert_eq!(map.remove(&3), Some((3, 13)));
+        assert!(!map.contains_key(&3));
+
+        map.retain(|k, _| k % 2 == 0);
+        assert_eq!(map.len(), 32);
+
+        map.clear();
+        assert!(map.is_empty());
+    }
+}
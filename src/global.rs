@@ -0,0 +1,89 @@
+//! Lazily initialized maps for use in `static`s.
+
+use crate::DashMap;
+use core::fmt;
+use core::hash::{BuildHasher, Hash};
+use core::ops::Deref;
+use once_cell::sync::OnceCell;
+use std::collections::hash_map::RandomState;
+
+/// A [`DashMap`] which is created on first use, so it can be placed in a `static`.
+///
+/// Creating a `Lazy` is a `const` operation and doesn't allocate. The map, its shards and its
+/// hasher are only created the first time the `Lazy` is dereferenced, after which it behaves
+/// exactly like the map it holds.
+///
+/// # Examples
+///
+/// ```
+/// use dashmap::global::Lazy;
+///
+/// static SESSIONS: Lazy<u32, &str> = Lazy::new();
+///
+/// assert!(!SESSIONS.is_initialized());
+/// SESSIONS.insert(1, "alice");
+/// assert!(SESSIONS.is_initialized());
+/// assert_eq!(*SESSIONS.get(&1).unwrap(), "alice");
+/// ```
+pub struct Lazy<K, V, S = RandomState> {
+    map: OnceCell<DashMap<K, V, S>>,
+    hasher: fn() -> S,
+}
+
+impl<K, V> Lazy<K, V, RandomState> {
+    /// Creates a new, uninitialized Lazy using the default hasher.
+    pub const fn new() -> Self {
+        Self::with_hasher(RandomState::new)
+    }
+}
+
+impl<K, V> Default for Lazy<K, V, RandomState> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V, S> Lazy<K, V, S> {
+    /// Creates a new, uninitialized Lazy whose hasher is created by `hasher` on first use.
+    pub const fn with_hasher(hasher: fn() -> S) -> Self {
+        Self {
+            map: OnceCell::new(),
+            hasher,
+        }
+    }
+
+    /// Returns the map if it has already been created.
+    pub fn get(this: &Self) -> Option<&DashMap<K, V, S>> {
+        this.map.get()
+    }
+
+    /// Checks if the map has already been created.
+    pub fn is_initialized(&self) -> bool {
+        self.map.get().is_some()
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher> Lazy<K, V, S> {
+    /// Returns the map, creating it if needed.
+    pub fn force(this: &Self) -> &DashMap<K, V, S> {
+        this.map
+            .get_or_init(|| DashMap::with_hasher((this.hasher)()))
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher> Deref for Lazy<K, V, S> {
+    type Target = DashMap<K, V, S>;
+
+    fn deref(&self) -> &DashMap<K, V, S> {
+        Lazy::force(self)
+    }
+}
+
+impl<K: Eq + Hash + fmt::Debug, V: fmt::Debug, S: BuildHasher> fmt::Debug for Lazy<K, V, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.map.get() {
+            Some(map) => map.fmt(f),
+            None => f.write_str("Lazy(<uninitialized>)"),
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use crate::concurrent::ConcurrentMap;
+use crate::iter::Iter;
+use crate::mapref::multiple::RefMulti;
+use crate::mapref::one::Ref;
+use crate::DashMap;
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::{BuildHasher, Hash};
+use core::ops::Deref;
+use std::collections::hash_map::RandomState;
+use std::sync::Arc;
+
+/// A cloneable handle to a shared [`DashMap`] which only allows reading it, created with [`DashMap::split_handles`].
+///
+/// # Examples
+///
+/// ```
+/// use dashmap::{DashMap, Reader};
+///
+/// fn report(sessions: &Reader<&'static str, u32>) -> usize {
+///     sessions.len()
+/// }
+///
+/// let (reader, writer) = DashMap::new().split_handles();
+/// writer.insert("alice", 1);
+/// assert_eq!(report(&reader), 1);
+/// assert_eq!(*reader.get("alice").unwrap(), 1);
+/// ```
+pub struct Reader<K, V, S = RandomState> {
+    map: Arc<DashMap<K, V, S>>,
+}
+
+/// A cloneable handle to a shared [`DashMap`] which allows reading and modifying it, created with
+/// [`DashMap::split_handles`]. It dereferences to the map.
+pub struct Writer<K, V, S = RandomState> {
+    map: Arc<DashMap<K, V, S>>,
+}
+
+impl<K, V, S> Clone for Reader<K, V, S> {
+    fn clone(&self) -> Self {
+        Self {
+            map: self.map.clone(),
+        }
+    }
+}
+
+impl<K, V, S> Clone for Writer<K, V, S> {
+    fn clone(&self) -> Self {
+        Self {
+            map: self.map.clone(),
+        }
+    }
+}
+
+impl<K, V, S> Writer<K, V, S> {
+    pub(crate) fn new(map: Arc<DashMap<K, V, S>>) -> Self {
+        Self { map }
+    }
+
+    /// Creates another read-only handle to the map.
+    pub fn reader(&self) -> Reader<K, V, S> {
+        Reader {
+            map: self.map.clone(),
+        }
+    }
+}
+
+impl<K, V, S> Deref for Writer<K, V, S> {
+    type Target = DashMap<K, V, S>;
+
+    fn deref(&self) -> &DashMap<K, V, S> {
+        &self.map
+    }
+}
+
+impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher> Reader<K, V, S> {
+    /// Get an immutable reference to an entry in the map.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    pub fn get<Q>(&'a self, key: &Q) -> Option<Ref<'a, K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.get(key)
+    }
+
+    /// Checks if the map contains a specific key.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.contains_key(key)
+    }
+
+    /// Returns the number of elements in the map.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the map contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Creates an iterator over the map yielding immutable references.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    pub fn iter(&'a self) -> Iter<'a, K, V> {
+        self.map.iter()
+    }
+
+    /// Calls a function on every entry of the map, see [`DashMap::visit_entries`].
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    pub fn visit_entries(&self, f: impl FnMut(&K, &V)) {
+        self.map.visit_entries(f);
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher> ConcurrentMap<K, V> for Reader<K, V, S> {
+    type Ref<'a> = Ref<'a, K, V> where Self: 'a;
+    type IterRef<'a> = RefMulti<'a, K, V> where Self: 'a;
+    type Iter<'a> = Iter<'a, K, V> where Self: 'a;
+
+    fn get<Q>(&self, key: &Q) -> Option<Ref<'_, K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        Reader::get(self, key)
+    }
+
+    fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        Reader::contains_key(self, key)
+    }
+
+    fn len(&self) -> usize {
+        Reader::len(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        Reader::is_empty(self)
+    }
+
+    fn iter(&self) -> Iter<'_, K, V> {
+        Reader::iter(self)
+    }
+}
+
+impl<K: Eq + Hash + fmt::Debug, V: fmt::Debug, S: BuildHasher> fmt::Debug for Reader<K, V, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.map.fmt(f)
+    }
+}
+
+impl<K: Eq + Hash + fmt::Debug, V: fmt::Debug, S: BuildHasher> fmt::Debug for Writer<K, V, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.map.fmt(f)
+    }
+}
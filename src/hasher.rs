@@ -0,0 +1,776 @@
+use core::fmt;
+use core::hash::{BuildHasher, Hash, Hasher};
+use std::collections::hash_map::RandomState;
+use std::sync::Arc;
+
+/// A type-erased [`BuildHasher`], which allows choosing the hasher of a map at runtime
+/// without making the hasher a type parameter of everything that stores the map.
+///
+/// `Box<dyn BuildHasher>` can not be used directly, since the hashers it builds have different types.
+///
+/// # Examples
+///
+/// ```
+/// use dashmap::{DashMap, DynBuildHasher};
+/// use std::collections::hash_map::RandomState;
+/// use std::hash::BuildHasherDefault;
+/// use std::collections::hash_map::DefaultHasher;
+///
+/// let deterministic = true;
+/// let hasher = if deterministic {
+///     DynBuildHasher::new(BuildHasherDefault::<DefaultHasher>::default())
+/// } else {
+///     DynBuildHasher::new(RandomState::new())
+/// };
+/// let map: DashMap<&str, u32, DynBuildHasher> = DashMap::with_hasher(hasher);
+/// map.insert("a", 1);
+/// assert_eq!(*map.get("a").unwrap(), 1);
+/// ```
+#[derive(Clone)]
+pub struct DynBuildHasher {
+    inner: Arc<dyn BuildDynHasher>,
+}
+
+impl DynBuildHasher {
+    /// Erases the type of a [`BuildHasher`].
+    pub fn new<S>(hasher: S) -> Self
+    where
+        S: BuildHasher + Send + Sync + 'static,
+        S::Hasher: 'static,
+    {
+        Self {
+            inner: Arc::new(hasher),
+        }
+    }
+}
+
+impl BuildHasher for DynBuildHasher {
+    type Hasher = Box<dyn Hasher>;
+
+    fn build_hasher(&self) -> Self::Hasher {
+        self.inner.build_dyn_hasher()
+    }
+}
+
+impl fmt::Debug for DynBuildHasher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DynBuildHasher").finish_non_exhaustive()
+    }
+}
+
+trait BuildDynHasher: Send + Sync {
+    fn build_dyn_hasher(&self) -> Box<dyn Hasher>;
+}
+
+impl<S> BuildDynHasher for S
+where
+    S: BuildHasher + Send + Sync,
+    S::Hasher: 'static,
+{
+    fn build_dyn_hasher(&self) -> Box<dyn Hasher> {
+        Box::new(self.build_hasher())
+    }
+}
+
+/// A [`BuildHasher`] for integer keys, which skips mixing and lays the key out in the bits
+/// DashMap and hashbrown read from the hash.
+///
+/// DashMap takes the shard index from the bits below the top 7 of a hash, hashbrown takes the
+/// SIMD tag from the top 7 bits and the bucket from the low bits. A plain identity hash would put
+/// every small key into the first shard with the same tag. Instead, the lowest bits of the key pick
+/// the shard, and the remaining bits are used as is for the bucket and copied into the tag, so
+/// consecutive keys go round-robin over the shards and fill consecutive buckets of each shard.
+///
+/// This only suits keys which are roughly uniformly distributed integers, such as ids from a
+/// counter. It offers no protection against keys which are chosen to collide, and keys of other
+/// types fall back to a fast but weak hash. The shard amount must be that of the map using it;
+/// [`DashMap::with_integer_keys`](crate::DashMap::with_integer_keys) takes care of that.
+///
+/// # Examples
+///
+/// ```
+/// use dashmap::{DashMap, IdentityState};
+///
+/// let map: DashMap<u64, &str, IdentityState> =
+///     DashMap::with_hasher_and_shard_amount(IdentityState::new(16), 16);
+/// map.insert(7, "seven");
+/// assert_eq!(*map.get(&7).unwrap(), "seven");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IdentityState {
+    shard_bits: u32,
+}
+
+impl IdentityState {
+    /// Creates a hasher state for a map with the given number of shards.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shard_amount` is not a power of two.
+    pub fn new(shard_amount: usize) -> Self {
+        assert!(
+            shard_amount.is_power_of_two(),
+            "shard_amount must be a power of two"
+        );
+
+        Self {
+            shard_bits: shard_amount.trailing_zeros(),
+        }
+    }
+}
+
+impl BuildHasher for IdentityState {
+    type Hasher = IdentityHasher;
+
+    fn build_hasher(&self) -> IdentityHasher {
+        IdentityHasher {
+            shard_bits: self.shard_bits,
+            value: 0,
+            written: false,
+        }
+    }
+}
+
+/// Combines a word into a hash, for the hashers which take the bits of a key mostly as they are.
+///
+/// A single multiplication spreads the low bits of the word into the high bits,
+/// which pick the shard and the hashbrown tag.
+#[inline]
+pub(crate) fn mix(hash: u64, word: u64) -> u64 {
+    (hash.rotate_left(5) ^ word).wrapping_mul(0x51_7c_c1_b7_27_22_0a_95)
+}
+
+/// Splits bytes into little-endian words for `add`, padding the last one and combining it with the length.
+#[inline]
+pub(crate) fn write_words(bytes: &[u8], mut add: impl FnMut(u64)) {
+    let mut chunks = bytes.chunks_exact(8);
+
+    for chunk in &mut chunks {
+        let mut word = [0; 8];
+        word.copy_from_slice(chunk);
+        add(u64::from_le_bytes(word));
+    }
+
+    let mut word = [0; 8];
+    word[..chunks.remainder().len()].copy_from_slice(chunks.remainder());
+    add(u64::from_le_bytes(word) ^ bytes.len() as u64);
+}
+
+/// The [`Hasher`] built by [`IdentityState`].
+#[derive(Clone, Copy, Debug)]
+pub struct IdentityHasher {
+    shard_bits: u32,
+    value: u64,
+    written: bool,
+}
+
+impl IdentityHasher {
+    #[inline]
+    fn add(&mut self, word: u64) {
+        // A single integer is kept as is. Anything hashed as several words, such as tuples or
+        // strings, is combined like in `PreHashedHasher`.
+        self.value = if self.written {
+            mix(self.value, word)
+        } else {
+            word
+        };
+        self.written = true;
+    }
+}
+
+impl Hasher for IdentityHasher {
+    #[inline]
+    fn finish(&self) -> u64 {
+        // Hashes are truncated to `usize` for the shard index and hashbrown's tag on 32-bit targets.
+        let bits = crate::util::ptr_size_bits() as u32;
+        let rest = self.value >> self.shard_bits;
+        let shard = self.value & ((1 << self.shard_bits) - 1);
+
+        rest ^ (shard << (bits - 7 - self.shard_bits)) ^ (rest << (bits - 7))
+    }
+
+    #[inline]
+    fn write_u8(&mut self, n: u8) {
+        self.add(n as u64);
+    }
+
+    #[inline]
+    fn write_u16(&mut self, n: u16) {
+        self.add(n as u64);
+    }
+
+    #[inline]
+    fn write_u32(&mut self, n: u32) {
+        self.add(n as u64);
+    }
+
+    #[inline]
+    fn write_u64(&mut self, n: u64) {
+        self.add(n);
+    }
+
+    #[inline]
+    fn write_u128(&mut self, n: u128) {
+        self.add(n as u64 ^ (n >> 64) as u64);
+    }
+
+    #[inline]
+    fn write_usize(&mut self, n: usize) {
+        self.add(n as u64);
+    }
+
+    #[inline]
+    fn write_i8(&mut self, n: i8) {
+        self.add(n as u64);
+    }
+
+    #[inline]
+    fn write_i16(&mut self, n: i16) {
+        self.add(n as u64);
+    }
+
+    #[inline]
+    fn write_i32(&mut self, n: i32) {
+        self.add(n as u64);
+    }
+
+    #[inline]
+    fn write_i64(&mut self, n: i64) {
+        self.add(n as u64);
+    }
+
+    #[inline]
+    fn write_isize(&mut self, n: isize) {
+        self.add(n as u64);
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        write_words(bytes, |word| self.add(word));
+    }
+}
+
+/// The upper half of the `u128` written by [`ShardAffinity::hash_shard_hint`], which marks it as a hint.
+const HINT_MARKER: u64 = 0x5348_4152_445f_4849;
+
+/// A key which can choose the shard it is stored in, for example to keep the keys of a tenant together.
+///
+/// In a map using [`AffinityState`], keys with the same [`shard_hint`](Self::shard_hint) are stored
+/// in the same shard, and a hint of `h` selects shard `h % shard_amount`. Keys without a hint are
+/// distributed by their hash as usual. The hint is read from the hash, so implementations must call
+/// [`hash_shard_hint`](Self::hash_shard_hint) at the start of their [`Hash`] implementation, and so
+/// must the types the key is looked up by through [`Borrow`](core::borrow::Borrow).
+///
+/// The hint only places keys. A key which doesn't follow this, or other hashes which happen to look
+/// like a hint, may be stored in an unexpected shard, but the map still works correctly.
+///
+/// # Examples
+///
+/// ```
+/// use dashmap::{DashMap, ShardAffinity};
+/// use std::hash::{Hash, Hasher};
+///
+/// #[derive(PartialEq, Eq)]
+/// struct Key {
+///     tenant: u64,
+///     name: String,
+/// }
+///
+/// impl ShardAffinity for Key {
+///     fn shard_hint(&self) -> Option<u64> {
+///         Some(self.tenant)
+///     }
+/// }
+///
+/// impl Hash for Key {
+///     fn hash<H: Hasher>(&self, state: &mut H) {
+///         self.hash_shard_hint(state);
+///         self.tenant.hash(state);
+///         self.name.hash(state);
+///     }
+/// }
+///
+/// let map = DashMap::with_shard_affinity();
+/// map.insert(Key { tenant: 3, name: "alice".to_owned() }, 1);
+/// map.insert(Key { tenant: 3, name: "bob".to_owned() }, 2);
+/// assert_eq!(*map.get(&Key { tenant: 3, name: "bob".to_owned() }).unwrap(), 2);
+/// ```
+pub trait ShardAffinity: Hash + Eq {
+    /// Returns the hint selecting the shard of the key, or `None` to select it by the hash.
+    fn shard_hint(&self) -> Option<u64>;
+
+    /// Writes the shard hint to a hasher. Must be the first write of the key's [`Hash`] implementation.
+    fn hash_shard_hint<H: Hasher>(&self, state: &mut H) {
+        let hint = match self.shard_hint() {
+            Some(hint) => ((HINT_MARKER as u128) << 64) | hint as u128,
+            None => 0,
+        };
+
+        state.write_u128(hint);
+    }
+}
+
+/// A [`BuildHasher`] for maps keyed by [`ShardAffinity`] keys, which places keys by their shard hint.
+///
+/// Hashes are computed by the wrapped hasher, after which the bits DashMap takes the shard index from
+/// are replaced by the hint, if the key has one. The bucket and tag bits are left alone, so the keys of a
+/// shard still spread over its buckets. The shard amount must be that of the map using it;
+/// [`DashMap::with_shard_affinity`](crate::DashMap::with_shard_affinity) takes care of that.
+#[derive(Clone, Debug)]
+pub struct AffinityState<S = RandomState> {
+    inner: S,
+    shard_bits: u32,
+}
+
+impl AffinityState {
+    /// Creates a hasher state for a map with the given number of shards, hashing with a [`RandomState`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shard_amount` is not a power of two.
+    pub fn new(shard_amount: usize) -> Self {
+        Self::with_hasher(shard_amount, RandomState::new())
+    }
+}
+
+impl<S> AffinityState<S> {
+    /// Creates a hasher state for a map with the given number of shards, hashing with the provided hasher.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shard_amount` is not a power of two.
+    pub fn with_hasher(shard_amount: usize, hasher: S) -> Self {
+        assert!(
+            shard_amount.is_power_of_two(),
+            "shard_amount must be a power of two"
+        );
+
+        Self {
+            inner: hasher,
+            shard_bits: shard_amount.trailing_zeros(),
+        }
+    }
+}
+
+impl<S: BuildHasher> BuildHasher for AffinityState<S> {
+    type Hasher = AffinityHasher<S::Hasher>;
+
+    fn build_hasher(&self) -> Self::Hasher {
+        AffinityHasher {
+            inner: self.inner.build_hasher(),
+            shard_bits: self.shard_bits,
+            hint: None,
+            written: false,
+        }
+    }
+}
+
+/// The [`Hasher`] built by [`AffinityState`].
+#[derive(Clone, Debug)]
+pub struct AffinityHasher<H> {
+    inner: H,
+    shard_bits: u32,
+    hint: Option<u64>,
+    written: bool,
+}
+
+impl<H> AffinityHasher<H> {
+    #[inline]
+    fn written(&mut self) {
+        self.written = true;
+    }
+}
+
+impl<H: Hasher> Hasher for AffinityHasher<H> {
+    #[inline]
+    fn finish(&self) -> u64 {
+        let hash = self.inner.finish();
+
+        match self.hint {
+            Some(hint) => {
+                // Hashes are truncated to `usize` for the shard index on 32-bit targets.
+                let bits = crate::util::ptr_size_bits() as u32;
+                let mask = (1 << self.shard_bits) - 1;
+                let shift = bits - 7 - self.shard_bits;
+
+                (hash & !(mask << shift)) | ((hint & mask) << shift)
+            }
+            None => hash,
+        }
+    }
+
+    #[inline]
+    fn write_u128(&mut self, n: u128) {
+        if !self.written && (n >> 64) as u64 == HINT_MARKER {
+            self.hint = Some(n as u64);
+        }
+
+        self.written();
+        self.inner.write_u128(n);
+    }
+
+    #[inline]
+    fn write_u8(&mut self, n: u8) {
+        self.written();
+        self.inner.write_u8(n);
+    }
+
+    #[inline]
+    fn write_u16(&mut self, n: u16) {
+        self.written();
+        self.inner.write_u16(n);
+    }
+
+    #[inline]
+    fn write_u32(&mut self, n: u32) {
+        self.written();
+        self.inner.write_u32(n);
+    }
+
+    #[inline]
+    fn write_u64(&mut self, n: u64) {
+        self.written();
+        self.inner.write_u64(n);
+    }
+
+    #[inline]
+    fn write_usize(&mut self, n: usize) {
+        self.written();
+        self.inner.write_usize(n);
+    }
+
+    #[inline]
+    fn write_i8(&mut self, n: i8) {
+        self.written();
+        self.inner.write_i8(n);
+    }
+
+    #[inline]
+    fn write_i16(&mut self, n: i16) {
+        self.written();
+        self.inner.write_i16(n);
+    }
+
+    #[inline]
+    fn write_i32(&mut self, n: i32) {
+        self.written();
+        self.inner.write_i32(n);
+    }
+
+    #[inline]
+    fn write_i64(&mut self, n: i64) {
+        self.written();
+        self.inner.write_i64(n);
+    }
+
+    #[inline]
+    fn write_i128(&mut self, n: i128) {
+        self.written();
+        self.inner.write_i128(n);
+    }
+
+    #[inline]
+    fn write_isize(&mut self, n: isize) {
+        self.written();
+        self.inner.write_isize(n);
+    }
+
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        self.written();
+        self.inner.write(bytes);
+    }
+}
+
+/// A [`BuildHasher`] with an explicit 128-bit seed, hashing with SipHash-1-3 like [`RandomState`].
+///
+/// Maps created with the same seed and shard amount place keys in the same shards in every process,
+/// which lets several processes agree on the shard of a key, for example to split export jobs by shard.
+/// Integers are hashed in native byte order, so placement agrees between machines of the same
+/// endianness and pointer width. The seed should be kept secret if the keys come from untrusted input.
+///
+/// # Examples
+///
+/// ```
+/// use dashmap::DashMap;
+///
+/// let a: DashMap<&str, u32, _> = DashMap::with_hasher_seeded_and_shard_amount(42, 16);
+/// let b: DashMap<&str, u32, _> = DashMap::with_hasher_seeded_and_shard_amount(a.hasher_seed(), 16);
+/// assert_eq!(a.shard_id("alice"), b.shard_id("alice"));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SeededState {
+    k0: u64,
+    k1: u64,
+}
+
+impl SeededState {
+    /// Creates a hasher state with the given seed.
+    pub fn new(seed: u128) -> Self {
+        Self {
+            k0: seed as u64,
+            k1: (seed >> 64) as u64,
+        }
+    }
+
+    /// Returns the seed of the hasher state.
+    pub fn seed(&self) -> u128 {
+        u128::from(self.k1) << 64 | u128::from(self.k0)
+    }
+}
+
+impl BuildHasher for SeededState {
+    type Hasher = SeededHasher;
+
+    fn build_hasher(&self) -> SeededHasher {
+        SeededHasher(Sip::new(self.k0, self.k1))
+    }
+}
+
+/// The [`Hasher`] built by [`SeededState`].
+#[derive(Clone, Debug)]
+pub struct SeededHasher(Sip<1, 3>);
+
+impl Hasher for SeededHasher {
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.0.finish()
+    }
+
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.write(bytes);
+    }
+}
+
+/// SipHash with `C` compression and `D` finalization rounds, reading the input as little-endian words.
+#[derive(Clone, Debug)]
+struct Sip<const C: usize, const D: usize> {
+    v: [u64; 4],
+    tail: u64,
+    ntail: usize,
+    length: usize,
+}
+
+impl<const C: usize, const D: usize> Sip<C, D> {
+    fn new(k0: u64, k1: u64) -> Self {
+        Self {
+            v: [
+                k0 ^ 0x736f_6d65_7073_6575,
+                k1 ^ 0x646f_7261_6e64_6f6d,
+                k0 ^ 0x6c79_6765_6e65_7261,
+                k1 ^ 0x7465_6462_7974_6573,
+            ],
+            tail: 0,
+            ntail: 0,
+            length: 0,
+        }
+    }
+
+    #[inline]
+    fn round(v: &mut [u64; 4]) {
+        v[0] = v[0].wrapping_add(v[1]);
+        v[1] = v[1].rotate_left(13) ^ v[0];
+        v[0] = v[0].rotate_left(32);
+        v[2] = v[2].wrapping_add(v[3]);
+        v[3] = v[3].rotate_left(16) ^ v[2];
+        v[0] = v[0].wrapping_add(v[3]);
+        v[3] = v[3].rotate_left(21) ^ v[0];
+        v[2] = v[2].wrapping_add(v[1]);
+        v[1] = v[1].rotate_left(17) ^ v[2];
+        v[2] = v[2].rotate_left(32);
+    }
+
+    #[inline]
+    fn compress(&mut self, word: u64) {
+        self.v[3] ^= word;
+        for _ in 0..C {
+            Self::round(&mut self.v);
+        }
+        self.v[0] ^= word;
+    }
+
+    fn write(&mut self, mut bytes: &[u8]) {
+        self.length = self.length.wrapping_add(bytes.len());
+
+        if self.ntail != 0 {
+            let fill = (8 - self.ntail).min(bytes.len());
+            for (i, &byte) in bytes[..fill].iter().enumerate() {
+                self.tail |= u64::from(byte) << (8 * (self.ntail + i));
+            }
+            self.ntail += fill;
+            bytes = &bytes[fill..];
+
+            if self.ntail < 8 {
+                return;
+            }
+
+            self.compress(self.tail);
+            self.tail = 0;
+            self.ntail = 0;
+        }
+
+        let mut chunks = bytes.chunks_exact(8);
+
+        for chunk in &mut chunks {
+            let mut word = [0; 8];
+            word.copy_from_slice(chunk);
+            self.compress(u64::from_le_bytes(word));
+        }
+
+        for (i, &byte) in chunks.remainder().iter().enumerate() {
+            self.tail |= u64::from(byte) << (8 * i);
+        }
+        self.ntail = chunks.remainder().len();
+    }
+
+    fn finish(&self) -> u64 {
+        let mut v = self.v;
+        let last = (self.length as u64 & 0xff) << 56 | self.tail;
+
+        v[3] ^= last;
+        for _ in 0..C {
+            Self::round(&mut v);
+        }
+        v[0] ^= last;
+
+        v[2] ^= 0xff;
+        for _ in 0..D {
+            Self::round(&mut v);
+        }
+
+        v[0] ^ v[1] ^ v[2] ^ v[3]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AffinityState, IdentityState, SeededState, ShardAffinity, Sip};
+    use crate::DashMap;
+    use std::collections::HashSet;
+    use std::hash::{Hash, Hasher};
+
+    #[test]
+    fn test_identity_hash_layout() {
+        let map: DashMap<u64, u64, IdentityState> = DashMap::with_integer_keys_and_shard_amount(8);
+
+        // Keys of the same shard get distinct tags.
+        let tags: HashSet<u64> = (0..128u64).map(|i| map.hash_u64(&(i * 8)) >> 57).collect();
+        assert_eq!(tags.len(), 128);
+
+        for i in 0..8000 {
+            map.insert(i, i);
+        }
+
+        for shard in map.shards() {
+            assert_eq!(shard.read().len(), 1000);
+        }
+
+        assert!((0..8000).all(|i| *map.get(&i).unwrap() == i));
+    }
+
+    struct Key {
+        tenant: Option<u64>,
+        id: u64,
+    }
+
+    impl PartialEq for Key {
+        fn eq(&self, other: &Self) -> bool {
+            (self.tenant, self.id) == (other.tenant, other.id)
+        }
+    }
+
+    impl Eq for Key {}
+
+    impl ShardAffinity for Key {
+        fn shard_hint(&self) -> Option<u64> {
+            self.tenant
+        }
+    }
+
+    impl Hash for Key {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            self.hash_shard_hint(state);
+            self.tenant.hash(state);
+            self.id.hash(state);
+        }
+    }
+
+    #[test]
+    fn test_shard_affinity() {
+        let map: DashMap<Key, u64, AffinityState> =
+            DashMap::with_shard_affinity_and_shard_amount(8);
+        let shard_of = |key: &Key| map.determine_shard(map.hash_u64(key) as usize);
+
+        for id in 0..1000 {
+            map.insert(
+                Key {
+                    tenant: Some(id % 3),
+                    id,
+                },
+                id,
+            );
+            map.insert(Key { tenant: None, id }, id);
+        }
+
+        for id in 0..1000 {
+            assert_eq!(
+                shard_of(&Key {
+                    tenant: Some(id % 3),
+                    id
+                }),
+                (id % 3) as usize
+            );
+            assert_eq!(
+                *map.get(&Key {
+                    tenant: Some(id % 3),
+                    id
+                })
+                .unwrap(),
+                id
+            );
+            assert_eq!(*map.get(&Key { tenant: None, id }).unwrap(), id);
+        }
+
+        // Keys without a hint still go to every shard.
+        let shards: HashSet<usize> = (0..1000)
+            .map(|id| shard_of(&Key { tenant: None, id }))
+            .collect();
+        assert_eq!(shards.len(), 8);
+        assert_eq!(map.len(), 2000);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_sip_matches_std() {
+        use std::hash::{BuildHasher, SipHasher};
+
+        let input: Vec<u8> = (0..64).collect();
+
+        for len in 0..input.len() {
+            let mut expected = SipHasher::new_with_keys(1, 2);
+            expected.write(&input[..len]);
+
+            // Split writes must hash like a single one.
+            let mut sip = Sip::<2, 4>::new(1, 2);
+            let (a, b) = input[..len].split_at(len / 3);
+            sip.write(a);
+            sip.write(b);
+
+            assert_eq!(sip.finish(), expected.finish());
+        }
+
+        let hash = |state: SeededState, key: &str| {
+            let mut hasher = state.build_hasher();
+            key.hash(&mut hasher);
+            hasher.finish()
+        };
+
+        let state = SeededState::new(0x0123_4567_89ab_cdef_fedc_ba98_7654_3210);
+        assert_eq!(state.seed(), 0x0123_4567_89ab_cdef_fedc_ba98_7654_3210);
+        assert_eq!(
+            hash(state, "key"),
+            hash(SeededState::new(state.seed()), "key")
+        );
+        assert_ne!(hash(state, "key"), hash(SeededState::new(1), "key"));
+    }
+}
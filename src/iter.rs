@@ -2,9 +2,12 @@ use crossbeam_utils::CachePadded;
 use hashbrown::hash_table;
 
 use super::mapref::multiple::{RefMulti, RefMutMulti};
+use super::mapref::one::Ref;
+use crate::audit;
 use crate::lock::{RwLock, RwLockReadGuardDetached, RwLockWriteGuardDetached};
 use crate::{DashMap, HashMap};
-use core::hash::Hash;
+use core::fmt;
+use core::hash::{BuildHasher, Hash, Hasher};
 use std::sync::Arc;
 
 /// Iterator over a DashMap yielding key value pairs.
@@ -77,6 +80,7 @@ type GuardIterMut<'a, K, V> = (
 pub struct Iter<'a, K, V> {
     shards: std::slice::Iter<'a, CachePadded<RwLock<HashMap<K, V>>>>,
     current: Option<GuardIter<'a, K, V>>,
+    held: Option<audit::Held>,
 }
 
 impl<'i, K: Clone + Hash + Eq, V: Clone> Clone for Iter<'i, K, V> {
@@ -84,6 +88,7 @@ impl<'i, K: Clone + Hash + Eq, V: Clone> Clone for Iter<'i, K, V> {
         Iter {
             shards: self.shards.clone(),
             current: self.current.clone(),
+            held: self.held.clone(),
         }
     }
 }
@@ -93,6 +98,7 @@ impl<'a, K: Eq + Hash + 'a, V: 'a> Iter<'a, K, V> {
         Self {
             shards: map.shards.iter(),
             current: None,
+            held: None,
         }
     }
 }
@@ -109,7 +115,10 @@ impl<'a, K: Eq + Hash + 'a, V: 'a> Iterator for Iter<'a, K, V> {
                 }
             }
 
-            let guard = self.shards.next()?.read();
+            let shard = self.shards.next()?;
+            let guard = shard.read();
+            // SAFETY: the raw lock is only used for its address.
+            self.held = Some(audit::Held::new(unsafe { shard.raw() }, false));
             // SAFETY: we keep the guard alive with the shard iterator,
             // and with any refs produced by the iterator
             let (guard, shard) = unsafe { RwLockReadGuardDetached::detach_from(guard) };
@@ -121,6 +130,261 @@ impl<'a, K: Eq + Hash + 'a, V: 'a> Iterator for Iter<'a, K, V> {
     }
 }
 
+/// Iterator over a DashMap yielding immutable references, skipping the shards which are locked for writing.
+///
+/// # Examples
+///
+/// ```
+/// use dashmap::DashMap;
+///
+/// let map = DashMap::new();
+/// map.insert("hello", "world");
+///
+/// let mut iter = map.iter_available();
+/// assert_eq!(iter.by_ref().count(), 1);
+/// assert_eq!(iter.skipped(), 0);
+/// ```
+pub struct AvailableIter<'a, K, V> {
+    shards: std::slice::Iter<'a, CachePadded<RwLock<HashMap<K, V>>>>,
+    current: Option<GuardIter<'a, K, V>>,
+    held: Option<audit::Held>,
+    skipped: usize,
+}
+
+impl<'a, K: Eq + Hash + 'a, V: 'a> AvailableIter<'a, K, V> {
+    pub(crate) fn new<S>(map: &'a DashMap<K, V, S>) -> Self {
+        Self {
+            shards: map.shards.iter(),
+            current: None,
+            held: None,
+            skipped: 0,
+        }
+    }
+
+    /// Returns the number of shards skipped so far because they were locked.
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+}
+
+impl<'a, K: Eq + Hash + 'a, V: 'a> Iterator for AvailableIter<'a, K, V> {
+    type Item = RefMulti<'a, K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(current) = self.current.as_mut() {
+                if let Some((k, v)) = current.1.next() {
+                    let guard = current.0.clone();
+                    return Some(RefMulti::new(guard, k, v));
+                }
+            }
+
+            let shard = self.shards.next()?;
+            let guard = match shard.try_read() {
+                Some(guard) => guard,
+                None => {
+                    self.current = None;
+                    self.held = None;
+                    self.skipped += 1;
+                    continue;
+                }
+            };
+            // SAFETY: the raw lock is only used for its address.
+            self.held = Some(audit::Held::new(unsafe { shard.raw() }, false));
+            // SAFETY: we keep the guard alive with the shard iterator,
+            // and with any refs produced by the iterator
+            let (guard, shard) = unsafe { RwLockReadGuardDetached::detach_from(guard) };
+
+            let iter = shard.iter();
+
+            self.current = Some((Arc::new(guard), iter));
+        }
+    }
+}
+
+type GuardSortedIter<'a, K, V> = (
+    Arc<RwLockReadGuardDetached<'a>>,
+    std::vec::IntoIter<(u64, &'a (K, V))>,
+);
+
+/// Iterator over a DashMap yielding immutable references in a reproducible order.
+///
+/// Entries are ordered by the hash of their key, so keys with the same hash keep the order of
+/// the table; see [`DashMap::iter_deterministic`].
+///
+/// # Examples
+///
+/// ```
+/// use dashmap::DashMap;
+/// use std::hash::BuildHasherDefault;
+/// use std::collections::hash_map::DefaultHasher;
+///
+/// let map: DashMap<u32, u32, BuildHasherDefault<DefaultHasher>> = DashMap::default();
+/// map.insert(1, 2);
+/// assert_eq!(map.iter_deterministic().count(), 1);
+/// ```
+pub struct DeterministicIter<'a, K, V, S> {
+    shards: std::slice::Iter<'a, CachePadded<RwLock<HashMap<K, V>>>>,
+    hasher: &'a S,
+    current: Option<GuardSortedIter<'a, K, V>>,
+    held: Option<audit::Held>,
+}
+
+impl<'a, K: Eq + Hash + 'a, V: 'a, S: BuildHasher> DeterministicIter<'a, K, V, S> {
+    pub(crate) fn new(map: &'a DashMap<K, V, S>) -> Self {
+        Self {
+            shards: map.shards.iter(),
+            hasher: &map.hasher,
+            current: None,
+            held: None,
+        }
+    }
+}
+
+impl<'a, K: Eq + Hash + 'a, V: 'a, S: BuildHasher> Iterator for DeterministicIter<'a, K, V, S> {
+    type Item = RefMulti<'a, K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(current) = self.current.as_mut() {
+                if let Some((_hash, (k, v))) = current.1.next() {
+                    let guard = current.0.clone();
+                    return Some(RefMulti::new(guard, k, v));
+                }
+            }
+
+            let shard = self.shards.next()?;
+            let guard = shard.read();
+            // SAFETY: the raw lock is only used for its address.
+            self.held = Some(audit::Held::new(unsafe { shard.raw() }, false));
+            // SAFETY: we keep the guard alive with the shard iterator,
+            // and with any refs produced by the iterator
+            let (guard, shard) = unsafe { RwLockReadGuardDetached::detach_from(guard) };
+
+            // The bucket order depends on the insertion history and capacity of the table,
+            // while the hashes only depend on the keys and the hasher.
+            let mut entries: Vec<_> = shard
+                .iter()
+                .map(|entry| {
+                    let mut hasher = self.hasher.build_hasher();
+                    entry.0.hash(&mut hasher);
+                    (hasher.finish(), entry)
+                })
+                .collect();
+            entries.sort_unstable_by_key(|(hash, _entry)| *hash);
+
+            self.current = Some((Arc::new(guard), entries.into_iter()));
+        }
+    }
+}
+
+/// Iterator over a DashMap of shared values yielding clones of the keys and values.
+///
+/// Each shard is only locked while its entries are cloned, so no lock is held between calls to `next`.
+/// See [`DashMap::iter_shared`].
+pub struct SharedIter<'a, K, V> {
+    shards: std::slice::Iter<'a, CachePadded<RwLock<HashMap<K, Arc<V>>>>>,
+    current: std::vec::IntoIter<(K, Arc<V>)>,
+}
+
+impl<'a, K: Clone, V> SharedIter<'a, K, V> {
+    pub(crate) fn new<S>(map: &'a DashMap<K, Arc<V>, S>) -> Self {
+        Self {
+            shards: map.shards.iter(),
+            current: Vec::new().into_iter(),
+        }
+    }
+}
+
+impl<'a, K: Clone, V> Iterator for SharedIter<'a, K, V> {
+    type Item = (K, Arc<V>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(entry) = self.current.next() {
+                return Some(entry);
+            }
+
+            let shard = self.shards.next()?.read();
+            self.current = shard
+                .iter()
+                .map(|(k, v)| (k.clone(), Arc::clone(v)))
+                .collect::<Vec<_>>()
+                .into_iter();
+        }
+    }
+}
+
+/// Iterator over a DashMap yielding clones of the keys and values.
+///
+/// Each shard is only locked while its entries are cloned, so no lock is held between calls to `next`
+/// and at most one shard's entries are buffered at a time. See [`DashMap::iter_cloned`].
+pub struct ClonedIter<'a, K, V> {
+    shards: std::slice::Iter<'a, CachePadded<RwLock<HashMap<K, V>>>>,
+    current: std::vec::IntoIter<(K, V)>,
+}
+
+impl<'a, K: Clone, V: Clone> ClonedIter<'a, K, V> {
+    pub(crate) fn new<S>(map: &'a DashMap<K, V, S>) -> Self {
+        Self {
+            shards: map.shards.iter(),
+            current: Vec::new().into_iter(),
+        }
+    }
+}
+
+impl<'a, K: Clone, V: Clone> Iterator for ClonedIter<'a, K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(entry) = self.current.next() {
+                return Some(entry);
+            }
+
+            let shard = self.shards.next()?.read();
+            self.current = shard.iter().cloned().collect::<Vec<_>>().into_iter();
+        }
+    }
+}
+
+/// Iterator over the entries of a single locked shard, yielding plain references.
+///
+/// See [`DashMap::map_reduce`].
+pub struct ShardIter<'a, K, V> {
+    inner: hash_table::Iter<'a, (K, V)>,
+}
+
+impl<'a, K, V> ShardIter<'a, K, V> {
+    pub(crate) fn new(shard: &'a HashMap<K, V>) -> Self {
+        Self {
+            inner: shard.iter(),
+        }
+    }
+}
+
+impl<'a, K, V> Clone for ShardIter<'a, K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for ShardIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(k, v)| (k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for ShardIter<'a, K, V> {}
+
 /// Iterator over a DashMap yielding mutable references.
 ///
 /// # Examples
@@ -136,6 +400,7 @@ impl<'a, K: Eq + Hash + 'a, V: 'a> Iterator for Iter<'a, K, V> {
 pub struct IterMut<'a, K, V> {
     shards: std::slice::Iter<'a, CachePadded<RwLock<HashMap<K, V>>>>,
     current: Option<GuardIterMut<'a, K, V>>,
+    held: Option<audit::Held>,
 }
 
 impl<'a, K: Eq + Hash + 'a, V: 'a> IterMut<'a, K, V> {
@@ -143,6 +408,7 @@ impl<'a, K: Eq + Hash + 'a, V: 'a> IterMut<'a, K, V> {
         Self {
             shards: map.shards.iter(),
             current: None,
+            held: None,
         }
     }
 }
@@ -159,7 +425,10 @@ impl<'a, K: Eq + Hash + 'a, V: 'a> Iterator for IterMut<'a, K, V> {
                 }
             }
 
-            let guard = self.shards.next()?.write();
+            let shard = self.shards.next()?;
+            let guard = shard.write();
+            // SAFETY: the raw lock is only used for its address.
+            self.held = Some(audit::Held::new(unsafe { shard.raw() }, true));
 
             // SAFETY: we keep the guard alive with the shard iterator,
             // and with any refs produced by the iterator
@@ -172,10 +441,265 @@ impl<'a, K: Eq + Hash + 'a, V: 'a> Iterator for IterMut<'a, K, V> {
     }
 }
 
+/// Iterator over a DashMap yielding chunks of mutable references, each chunk from a single shard.
+///
+/// The shard is only locked while references of a chunk are alive; see [`DashMap::iter_mut_chunks`].
+pub struct IterMutChunks<'a, K, V, S> {
+    map: &'a DashMap<K, V, S>,
+    chunk_size: usize,
+    shard: usize,
+    hashes: std::vec::IntoIter<u64>,
+}
+
+impl<'a, K, V, S> IterMutChunks<'a, K, V, S> {
+    pub(crate) fn new(map: &'a DashMap<K, V, S>, chunk_size: usize) -> Self {
+        Self {
+            map,
+            chunk_size,
+            shard: 0,
+            hashes: Vec::new().into_iter(),
+        }
+    }
+}
+
+impl<'a, K: Eq + Hash + 'a, V: 'a, S: BuildHasher> Iterator for IterMutChunks<'a, K, V, S> {
+    type Item = Vec<RefMutMulti<'a, K, V>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.hashes.len() == 0 {
+                if self.shard == self.map.shards.len() {
+                    return None;
+                }
+
+                self.hashes = self.map.shard_hashes(self.shard).into_iter();
+                self.shard += 1;
+                continue;
+            }
+
+            let guard = self.map.shards[self.shard - 1].write();
+
+            // SAFETY: we keep the guard alive with the refs produced by the iterator
+            let (guard, shard) = unsafe { RwLockWriteGuardDetached::detach_from(guard) };
+            let guard = Arc::new(guard);
+            let shard: *mut HashMap<K, V> = shard;
+
+            let mut chunk = Vec::new();
+
+            for hash in self.hashes.by_ref().take(self.chunk_size) {
+                // SAFETY: the hashes are distinct, so every entry is only borrowed once.
+                for (k, v) in self.map.iter_hash_exact_mut(unsafe { &mut *shard }, hash) {
+                    chunk.push(RefMutMulti::new(Arc::clone(&guard), &*k, v));
+                }
+            }
+
+            if !chunk.is_empty() {
+                return Some(chunk);
+            }
+        }
+    }
+}
+
+/// A difference between two maps, yielded by [`DiffIter`].
+pub enum Diff<'a, 'b, K, V> {
+    /// The key is only in the left map.
+    OnlyLeft(RefMulti<'a, K, V>),
+    /// The key is only in the right map.
+    OnlyRight(RefMulti<'b, K, V>),
+    /// The key is in both maps, with different values.
+    Changed(RefMulti<'a, K, V>, Ref<'b, K, V>),
+}
+
+impl<'a, 'b, K: Eq + Hash + fmt::Debug, V: fmt::Debug> fmt::Debug for Diff<'a, 'b, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Diff::OnlyLeft(l) => f
+                .debug_tuple("OnlyLeft")
+                .field(l.pair().0)
+                .field(l.pair().1)
+                .finish(),
+            Diff::OnlyRight(r) => f
+                .debug_tuple("OnlyRight")
+                .field(r.pair().0)
+                .field(r.pair().1)
+                .finish(),
+            Diff::Changed(l, r) => f
+                .debug_tuple("Changed")
+                .field(l.key())
+                .field(l.value())
+                .field(r.value())
+                .finish(),
+        }
+    }
+}
+
+/// Iterator over the differences between two maps. See [`DashMap::diff`].
+pub struct DiffIter<'a, 'b, K, V, S, S2, F> {
+    left_map: &'a DashMap<K, V, S>,
+    right_map: &'b DashMap<K, V, S2>,
+    left: Option<Iter<'a, K, V>>,
+    right: Option<Iter<'b, K, V>>,
+    eq: F,
+}
+
+impl<'a, 'b, K: Eq + Hash, V, S, S2, F> DiffIter<'a, 'b, K, V, S, S2, F> {
+    pub(crate) fn new(left: &'a DashMap<K, V, S>, right: &'b DashMap<K, V, S2>, eq: F) -> Self {
+        Self {
+            left_map: left,
+            right_map: right,
+            left: Some(Iter::new(left)),
+            right: Some(Iter::new(right)),
+            eq,
+        }
+    }
+}
+
+impl<'a, 'b, K, V, S, S2, F> Iterator for DiffIter<'a, 'b, K, V, S, S2, F>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+    S2: BuildHasher,
+    F: FnMut(&V, &V) -> bool,
+{
+    type Item = Diff<'a, 'b, K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(left) = self.left.as_mut() {
+            for l in left.by_ref() {
+                match self.right_map.get(l.key()) {
+                    None => return Some(Diff::OnlyLeft(l)),
+                    Some(r) if !(self.eq)(l.value(), r.value()) => {
+                        return Some(Diff::Changed(l, r))
+                    }
+                    Some(_) => {}
+                }
+            }
+
+            // Release the last shard before looking keys up in this map again.
+            self.left = None;
+        }
+
+        if let Some(right) = self.right.as_mut() {
+            for r in right.by_ref() {
+                if !self.left_map.contains_key(r.key()) {
+                    return Some(Diff::OnlyRight(r));
+                }
+            }
+
+            self.right = None;
+        }
+
+        None
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::DashMap;
 
+    #[test]
+    fn iter_cloned_holds_no_locks() {
+        let map: DashMap<u32, u32> = DashMap::with_shard_amount(4);
+
+        for i in 0..100 {
+            map.insert(i, i);
+        }
+
+        let mut seen = 0;
+
+        for (k, v) in map.iter_cloned() {
+            assert_eq!(k, v);
+            assert!(map.try_get_mut(&k).is_present());
+            map.remove(&k);
+            seen += 1;
+        }
+
+        assert_eq!(seen, 100);
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn diff_reports_every_difference() {
+        use super::Diff;
+
+        let left: DashMap<u32, u32> = (0..100).map(|i| (i, i)).collect();
+        let right: DashMap<u32, u32> = (50..150).map(|i| (i, i % 10)).collect();
+
+        let (mut only_left, mut only_right, mut changed) = (0, 0, 0);
+
+        for diff in left.diff(&right) {
+            match diff {
+                Diff::OnlyLeft(l) => {
+                    assert!(*l.key() < 50);
+                    only_left += 1;
+                }
+                Diff::OnlyRight(r) => {
+                    assert!(*r.key() >= 100);
+                    only_right += 1;
+                }
+                Diff::Changed(l, r) => {
+                    assert_ne!(*l.value(), *r.value());
+                    changed += 1;
+                }
+            }
+        }
+
+        assert_eq!((only_left, only_right, changed), (50, 50, 50));
+        assert_eq!(left.diff(&left.clone()).count(), 0);
+    }
+
+    #[test]
+    fn iter_mut_chunks_yields_every_entry_once() {
+        let map: DashMap<u32, u32> = DashMap::with_shard_amount(4);
+
+        for i in 0..1000 {
+            map.insert(i, 0);
+        }
+
+        let mut chunks = 0;
+
+        for chunk in map.iter_mut_chunks(10) {
+            assert!(!chunk.is_empty() && chunk.len() <= 10);
+            chunks += 1;
+
+            for mut r in chunk {
+                *r += 1;
+            }
+
+            map.insert(1000 + chunks, 1);
+        }
+
+        assert!(chunks >= 100);
+        assert!(map.iter().filter(|r| *r.key() < 1000).all(|r| *r == 1));
+    }
+
+    #[test]
+    fn iter_deterministic_ignores_history() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::BuildHasherDefault;
+
+        type Map = DashMap<u32, u32, BuildHasherDefault<DefaultHasher>>;
+
+        let a = Map::with_hasher_and_shard_amount(Default::default(), 4);
+        let b = Map::with_capacity_and_hasher_and_shard_amount(1024, Default::default(), 4);
+
+        for i in 0..256 {
+            a.insert(i, i);
+        }
+
+        for i in (0..512).rev() {
+            b.insert(i, i);
+        }
+
+        b.retain(|k, _| *k < 256);
+
+        let a: Vec<u32> = a.iter_deterministic().map(|r| *r.key()).collect();
+        let b: Vec<u32> = b.iter_deterministic().map(|r| *r.key()).collect();
+
+        assert_eq!(a.len(), 256);
+        assert_eq!(a, b);
+    }
+
     #[test]
     fn iter_mut_manual_count() {
         let map = DashMap::new();
@@ -36,3 +36,21 @@ impl<'a, K: Eq + Hash + 'a> Iterator for Iter<'a, K> {
         self.inner.next().map(RefMulti::new)
     }
 }
+
+pub struct ClonedIter<'a, K> {
+    inner: crate::iter::ClonedIter<'a, K, ()>,
+}
+
+impl<'a, K: Clone> ClonedIter<'a, K> {
+    pub(crate) fn new(inner: crate::iter::ClonedIter<'a, K, ()>) -> Self {
+        Self { inner }
+    }
+}
+
+impl<'a, K: Clone> Iterator for ClonedIter<'a, K> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(k, _)| k)
+    }
+}
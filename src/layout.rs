@@ -0,0 +1,138 @@
+use crate::util;
+use hashbrown::HashTable;
+
+/// The shard count and per-shard capacities of a [`DashMap`](crate::DashMap).
+///
+/// A layout can be captured from a warmed-up map with [`DashMap::export_layout`](crate::DashMap::export_layout)
+/// and used to pre-allocate a new map identically with [`DashMap::with_layout`](crate::DashMap::with_layout).
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ShardLayout {
+    capacities: Vec<usize>,
+}
+
+impl ShardLayout {
+    /// Creates a layout from the capacity of each shard.
+    ///
+    /// The number of capacities is the shard amount, which should be greater than 1 and a power of two.
+    /// Constructing a map from a layout which does not satisfy this will panic.
+    pub fn new(capacities: Vec<usize>) -> Self {
+        Self { capacities }
+    }
+
+    /// Returns the number of shards in this layout.
+    pub fn shard_amount(&self) -> usize {
+        self.capacities.len()
+    }
+
+    /// Returns the capacity of each shard.
+    pub fn capacities(&self) -> &[usize] {
+        &self.capacities
+    }
+
+    /// Returns the sum of all shard capacities.
+    pub fn total_capacity(&self) -> usize {
+        self.capacities.iter().sum()
+    }
+}
+
+/// The occupancy of every shard of a [`DashMap`](crate::DashMap), returned by
+/// [`DashMap::occupancy_report`](crate::DashMap::occupancy_report).
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OccupancyReport {
+    shards: Vec<ShardOccupancy>,
+}
+
+impl OccupancyReport {
+    pub(crate) fn new(shards: Vec<ShardOccupancy>) -> Self {
+        Self { shards }
+    }
+
+    /// Returns the occupancy of each shard.
+    pub fn shards(&self) -> &[ShardOccupancy] {
+        &self.shards
+    }
+
+    /// Returns the occupancy of all shards combined.
+    pub fn total(&self) -> ShardOccupancy {
+        let mut total = ShardOccupancy::default();
+
+        for shard in &self.shards {
+            total.len += shard.len;
+            total.capacity += shard.capacity;
+            total.estimated_tombstones += shard.estimated_tombstones;
+            total.bytes += shard.bytes;
+        }
+
+        total
+    }
+}
+
+/// The occupancy of a single shard.
+///
+/// Removed entries may leave tombstones behind, which take up capacity until the shard is
+/// rehashed, either when it grows or when it is shrunk with [`shrink_to_fit`](crate::DashMap::shrink_to_fit).
+/// `capacity` is what is left for entries after the tombstones, so a shard with many tombstones
+/// grows earlier than its entry count suggests.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ShardOccupancy {
+    /// The number of entries.
+    pub len: usize,
+    /// The number of entries the shard can hold without reallocating.
+    pub capacity: usize,
+    /// The estimated number of tombstones, derived from the allocation size of the shard.
+    pub estimated_tombstones: usize,
+    /// The number of bytes allocated by the shard's table.
+    pub bytes: usize,
+}
+
+impl ShardOccupancy {
+    pub(crate) fn of<T>(shard: &HashTable<T>) -> Self {
+        let bytes = shard.allocation_size();
+        let estimated_tombstones = util::estimate_buckets::<T>(bytes).map_or(0, |buckets| {
+            util::buckets_to_capacity(buckets).saturating_sub(shard.capacity())
+        });
+
+        Self {
+            len: shard.len(),
+            capacity: shard.capacity(),
+            estimated_tombstones,
+            bytes,
+        }
+    }
+}
+
+/// When the shards of a [`DashMap`](crate::DashMap) give memory back after entries were removed.
+///
+/// Set with [`DashMap::with_shrink_policy`](crate::DashMap::with_shrink_policy). Shrinking one shard
+/// only takes the lock of that shard, which is already held for the removal.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum ShrinkPolicy {
+    /// Never shrink automatically, only when [`shrink_to_fit`](crate::DashMap::shrink_to_fit) is called.
+    #[default]
+    Never,
+    /// Shrink a shard to fit its entries once they fill less than the given fraction of its capacity.
+    ///
+    /// After shrinking, a shard is mostly full again, so a ratio well below 1, such as 0.25,
+    /// keeps a shard which hovers around a size from being shrunk and grown repeatedly.
+    OnFallBelow(f64),
+}
+
+impl ShrinkPolicy {
+    pub(crate) fn should_shrink(self, len: usize, capacity: usize) -> bool {
+        match self {
+            ShrinkPolicy::Never => false,
+            ShrinkPolicy::OnFallBelow(ratio) => (len as f64) < ratio * capacity as f64,
+        }
+    }
+}
+
+/// Applies the shrink policy of a map to one of its shards.
+///
+/// Entries hold this instead of the map, since they don't know its hasher.
+pub(crate) trait ShrinkShard<K, V> {
+    /// Shrinks a shard which has just had entries removed, if the shrink policy says so.
+    fn shrink_after_removal(&self, shard: &mut HashTable<(K, V)>);
+}
@@ -1,19 +1,44 @@
 #![doc = include_str!("../README.md")]
 #![allow(clippy::type_complexity)]
 
+mod any;
 #[cfg(feature = "arbitrary")]
 mod arbitrary;
+mod audit;
+pub mod batch;
+mod blocking;
+mod concurrent;
+mod debug;
+mod dual;
+mod fixed;
+pub mod global;
+mod handles;
+mod hasher;
 pub mod iter;
 pub mod iter_set;
+mod layout;
 mod lock;
 pub mod mapref;
+pub mod memo;
+mod metrics;
+#[cfg(feature = "ordered")]
+pub mod ordered;
+mod prehashed;
+mod quota;
 mod read_only;
+mod scope;
 #[cfg(feature = "serde")]
 mod serde;
 mod set;
 pub mod setref;
+pub mod sync;
+#[cfg(test)]
+mod test_util;
+mod tracker;
 pub mod try_result;
 mod util;
+mod version;
+mod watch;
 
 #[cfg(feature = "rayon")]
 pub mod rayon {
@@ -28,24 +53,67 @@ use crate::lock::RwLock;
 #[cfg(feature = "raw-api")]
 pub use crate::lock::{RawRwLock, RwLock};
 
+#[cfg(feature = "serde")]
+pub use crate::serde::{serde_pairs, DashMapSeed, DashSetSeed, ShardGroup, ShardGroupSeed};
+pub use any::DashAnyMap;
+use batch::{Op, OpResult};
+#[cfg(feature = "blocking-detect")]
+pub use blocking::{BlockedLock, BlockingDetector};
 use cfg_if::cfg_if;
+pub use concurrent::{ConcurrentMap, ConcurrentMapMut, MapRef};
+use core::alloc::Layout;
+use core::any::Any;
 use core::borrow::Borrow;
+use core::cmp;
 use core::fmt;
+use core::future::Future;
 use core::hash::{BuildHasher, Hash, Hasher};
 use core::iter::FromIterator;
+use core::mem;
 use core::ops::{BitAnd, BitOr, Shl, Shr, Sub};
+use core::sync::atomic::{AtomicUsize, Ordering};
+use core::time::Duration;
 use crossbeam_utils::CachePadded;
+pub use debug::{DebugLossy, TryDebug};
+pub use dual::{DashMap2, IterK1};
+pub use fixed::FixedDashMap;
+pub use handles::{Reader, Writer};
 use hashbrown::hash_table;
-use iter::{Iter, IterMut, OwningIter};
+pub use hasher::{
+    AffinityHasher, AffinityState, DynBuildHasher, IdentityHasher, IdentityState, SeededHasher,
+    SeededState, ShardAffinity,
+};
+use iter::{
+    AvailableIter, ClonedIter, DeterministicIter, DiffIter, Iter, IterMut, IterMutChunks,
+    OwningIter, ShardIter, SharedIter,
+};
+use layout::ShrinkShard;
+pub use layout::{OccupancyReport, ShardLayout, ShardOccupancy, ShrinkPolicy};
 use lock::{RwLockReadGuardDetached, RwLockWriteGuardDetached};
-pub use mapref::entry::{Entry, OccupiedEntry, VacantEntry};
-use mapref::multiple::RefMulti;
-use mapref::one::{Ref, RefMut};
+pub use mapref::entry::{Entry, OccupiedEntry, OwnedEntry, VacantEntry};
+use mapref::entry::{EntryRef, OccupiedEntryRef, VacantEntryRef};
+use mapref::multiple::{RefMulti, RefMutMulti};
+use mapref::one::{EntrySnapshot, MappedRef, Ref, RefMut};
+#[cfg(feature = "metrics")]
+pub use metrics::{HistogramSnapshot, MetricsSnapshot, ShardMetricsSnapshot};
+use metrics::{Kind, Metrics, Timer};
 use once_cell::sync::OnceCell;
+pub use prehashed::{Hashed, HashedString, PreHashedHasher, PreHashedKey, PreHashedState};
+pub use quota::{QuotaExceeded, QuotaMap};
 pub use read_only::ReadOnlyView;
+pub use scope::ReadScope;
 pub use set::DashSet;
+use std::borrow::Cow;
 use std::collections::hash_map::RandomState;
-use try_result::TryResult;
+use std::collections::BTreeMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+use std::time::Instant;
+pub use tracker::MemoryTracker;
+use try_result::{TryResult, TryResultDetailed};
+pub use version::Versioned;
+use watch::ShardWatchers;
+pub use watch::WatchKey;
 
 pub(crate) type HashMap<K, V> = hash_table::HashTable<(K, V)>;
 
@@ -53,9 +121,270 @@ pub(crate) type HashMap<K, V> = hash_table::HashTable<(K, V)>;
 // util [`std::collections::TryReserveError`] stabilises.
 // We cannot easily create `std::collections` error type from `hashbrown` error type
 // without access to `TryReserveError::kind` method.
+/// The error type for [`DashMap::try_reserve`].
+#[non_exhaustive]
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TryReserveError {
+    kind: TryReserveErrorKind,
+}
+
+impl TryReserveError {
+    /// Returns details about the allocation that caused the error.
+    pub fn kind(&self) -> TryReserveErrorKind {
+        self.kind.clone()
+    }
+}
+
+impl From<hashbrown::TryReserveError> for TryReserveError {
+    fn from(error: hashbrown::TryReserveError) -> Self {
+        let kind = match error {
+            hashbrown::TryReserveError::CapacityOverflow => TryReserveErrorKind::CapacityOverflow,
+            hashbrown::TryReserveError::AllocError { layout } => {
+                TryReserveErrorKind::AllocError { layout }
+            }
+        };
+
+        Self { kind }
+    }
+}
+
+impl fmt::Display for TryReserveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("memory allocation failed")?;
+
+        match self.kind {
+            TryReserveErrorKind::CapacityOverflow => {
+                f.write_str(" because the computed capacity exceeded the collection's maximum")
+            }
+            TryReserveErrorKind::AllocError { .. } => {
+                f.write_str(" because the memory allocator returned an error")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TryReserveError {}
+
+/// Details of the allocation that caused a [`TryReserveError`].
 #[non_exhaustive]
 #[derive(Clone, PartialEq, Eq, Debug)]
-pub struct TryReserveError {}
+pub enum TryReserveErrorKind {
+    /// The required capacity exceeded the maximum of a shard, usually because of an overflow.
+    CapacityOverflow,
+    /// The memory allocator returned an error.
+    AllocError {
+        /// The layout of the allocation request that failed.
+        layout: Layout,
+    },
+}
+
+/// The error type for constructors given an invalid shard amount,
+/// such as [`DashMap::try_with_capacity_and_hasher_and_shard_amount`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ShardAmountError {
+    shard_amount: usize,
+}
+
+impl ShardAmountError {
+    fn check(shard_amount: usize) -> Result<(), Self> {
+        if shard_amount > 1 && shard_amount.is_power_of_two() {
+            Ok(())
+        } else {
+            Err(Self { shard_amount })
+        }
+    }
+
+    /// Returns the shard amount which was rejected.
+    pub fn shard_amount(&self) -> usize {
+        self.shard_amount
+    }
+}
+
+impl fmt::Display for ShardAmountError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "shard amount must be greater than 1 and a power of two, got {}",
+            self.shard_amount
+        )
+    }
+}
+
+impl std::error::Error for ShardAmountError {}
+
+/// The error type for [`DashMap::for_each_mut_resilient`],
+/// recording a panic that occurred while visiting a shard.
+pub struct ShardError {
+    shard: usize,
+    payload: Box<dyn Any + Send + 'static>,
+}
+
+impl ShardError {
+    /// Returns the index of the shard whose iteration panicked.
+    pub fn shard(&self) -> usize {
+        self.shard
+    }
+
+    /// Consumes the error, returning the panic payload.
+    /// It can be passed to [`std::panic::resume_unwind`] to continue unwinding.
+    pub fn into_panic(self) -> Box<dyn Any + Send + 'static> {
+        self.payload
+    }
+}
+
+impl fmt::Debug for ShardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShardError")
+            .field("shard", &self.shard)
+            .finish_non_exhaustive()
+    }
+}
+
+/// How [`DashMap::alter_all_with_priority`] competes with other users of the map for the shard locks.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Priority {
+    /// Block on every shard lock in turn, like [`DashMap::alter_all`].
+    #[default]
+    Foreground,
+    /// Only take shard locks which are free, deferring contended shards and retrying them with backoff.
+    Background,
+}
+
+/// The progress of [`DashMap::clear_incremental`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ClearProgress {
+    cleared: usize,
+    remaining: usize,
+}
+
+impl ClearProgress {
+    /// Returns the number of entries removed by this call.
+    pub fn cleared(&self) -> usize {
+        self.cleared
+    }
+
+    /// Returns the number of entries which were left in the map.
+    /// Entries inserted concurrently are included, so this may not decrease between calls.
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    /// Returns `true` if the map was empty when this call finished.
+    pub fn is_done(&self) -> bool {
+        self.remaining == 0
+    }
+}
+
+/// What a call to [`DashMap::retain_counted`] did.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RetainStats {
+    examined: usize,
+    removed: usize,
+    duration: Duration,
+}
+
+impl RetainStats {
+    /// Returns the number of entries the predicate was called for.
+    pub fn examined(&self) -> usize {
+        self.examined
+    }
+
+    /// Returns the number of entries which were removed.
+    pub fn removed(&self) -> usize {
+        self.removed
+    }
+
+    /// Returns the number of entries which were kept.
+    pub fn kept(&self) -> usize {
+        self.examined - self.removed
+    }
+
+    /// Returns how long the call took, including waiting for the shard locks.
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+}
+
+/// What a call to [`DashMap::retain_async`] or [`DashMap::clear_async`] did.
+///
+/// A slice is the work done between two yields to the executor. It ends when its time budget is
+/// used up, or when the next shard is locked and has to be retried later. `clear_async` can end a
+/// slice in the middle of a shard, but `retain_async` only ends them between shards, so one of its
+/// slices lasts at least as long as retaining the shard it started in.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SliceProgress {
+    examined: usize,
+    removed: usize,
+    slices: usize,
+    duration: Duration,
+}
+
+impl SliceProgress {
+    /// Returns the number of entries the predicate was called for, or which were cleared.
+    pub fn examined(&self) -> usize {
+        self.examined
+    }
+
+    /// Returns the number of entries which were removed.
+    pub fn removed(&self) -> usize {
+        self.removed
+    }
+
+    /// Returns the number of entries which were kept.
+    pub fn kept(&self) -> usize {
+        self.examined - self.removed
+    }
+
+    /// Returns the number of time slices the work was split into, which is one more than the
+    /// number of times the future yielded, including the retries of locked shards.
+    pub fn slices(&self) -> usize {
+        self.slices
+    }
+
+    /// Returns how long the call took, including the time spent yielding to other tasks.
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+}
+
+/// What the gate of [`DashMap::entry_if`] sees before a vacant entry is handed out.
+///
+/// The shard lengths are exact, since the shard is locked while the gate runs. The map length is
+/// read from the per-shard counters without locking the other shards, so it is only a snapshot.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct EntryGateInfo {
+    len: usize,
+    shard_len: usize,
+    shard_capacity: usize,
+}
+
+impl EntryGateInfo {
+    /// Returns the number of entries in the map, not counting the new one.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the map has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of entries in the shard the key belongs to, not counting the new one.
+    pub fn shard_len(&self) -> usize {
+        self.shard_len
+    }
+
+    /// Returns how many entries the shard the key belongs to can hold without reallocating.
+    pub fn shard_capacity(&self) -> usize {
+        self.shard_capacity
+    }
+}
+
+/// An opaque identifier of the shard a key is stored in, returned by [`DashMap::shard_id`].
+///
+/// Identifiers are only meaningful for the map that produced them.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct ShardId(usize);
 
 fn default_shard_amount() -> usize {
     static DEFAULT_SHARD_AMOUNT: OnceCell<usize> = OnceCell::new();
@@ -68,6 +397,12 @@ fn ncb(shard_amount: usize) -> usize {
     shard_amount.trailing_zeros() as usize
 }
 
+fn empty_lens(shard_amount: usize) -> Box<[CachePadded<AtomicUsize>]> {
+    (0..shard_amount)
+        .map(|_| CachePadded::new(AtomicUsize::new(0)))
+        .collect()
+}
+
 /// DashMap is an implementation of a concurrent associative array/hashmap in Rust.
 ///
 /// DashMap tries to implement an easy to use API similar to `std::collections::HashMap`
@@ -83,18 +418,64 @@ pub struct DashMap<K, V, S = RandomState> {
     shift: usize,
     shards: Box<[CachePadded<RwLock<HashMap<K, V>>>]>,
     hasher: S,
+    tracker: Option<Arc<dyn MemoryTracker<K, V>>>,
+    watchers: OnceCell<Box<[ShardWatchers]>>,
+    lens: Box<[CachePadded<AtomicUsize>]>,
+    metrics: Metrics,
+    shrink_policy: ShrinkPolicy,
 }
 
 impl<K: Eq + Hash + Clone, V: Clone, S: Clone> Clone for DashMap<K, V, S> {
     fn clone(&self) -> Self {
-        fn clone_rwlock<T: Clone>(lock: &CachePadded<RwLock<T>>) -> CachePadded<RwLock<T>> {
-            CachePadded::new(RwLock::new(lock.read().clone()))
+        let shards: Box<[_]> = self
+            .shards
+            .iter()
+            .enumerate()
+            .map(|(idx, lock)| CachePadded::new(lock::shard_lock(idx, lock.read().clone())))
+            .collect();
+
+        // The clone shares the tracker, so it has to account for everything it just allocated.
+        if let Some(tracker) = self.tracker.as_deref() {
+            for shard in shards.iter() {
+                let shard = shard.read();
+                tracker::resized(Some(tracker), 0, shard.allocation_size());
+                for (k, v) in shard.iter() {
+                    tracker::entry_added(Some(tracker), k, v);
+                }
+            }
         }
 
+        let lens = shards
+            .iter()
+            .map(|shard| CachePadded::new(AtomicUsize::new(shard.read().len())))
+            .collect();
+
         Self {
             shift: self.shift,
-            shards: self.shards.iter().map(clone_rwlock).collect(),
+            shards,
             hasher: self.hasher.clone(),
+            tracker: self.tracker.clone(),
+            watchers: OnceCell::new(),
+            metrics: Metrics::new(self.shards.len()),
+            lens,
+            shrink_policy: self.shrink_policy,
+        }
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher> ShrinkShard<K, V> for DashMap<K, V, S> {
+    fn shrink_after_removal(&self, shard: &mut HashMap<K, V>) {
+        if self
+            .shrink_policy
+            .should_shrink(shard.len(), shard.capacity())
+        {
+            let before = shard.allocation_size();
+            shard.shrink_to_fit(|(k, _v)| {
+                let mut hasher = self.hasher.build_hasher();
+                k.hash(&mut hasher);
+                hasher.finish()
+            });
+            tracker::resized(self.tracker.as_deref(), before, shard.allocation_size());
         }
     }
 }
@@ -102,7 +483,7 @@ impl<K: Eq + Hash + Clone, V: Clone, S: Clone> Clone for DashMap<K, V, S> {
 impl<K, V, S> Default for DashMap<K, V, S>
 where
     K: Eq + Hash,
-    S: Default + BuildHasher + Clone,
+    S: Default + BuildHasher,
 {
     fn default() -> Self {
         Self::with_hasher(Default::default())
@@ -124,6 +505,24 @@ impl<'a, K: 'a + Eq + Hash, V: 'a> DashMap<K, V, RandomState> {
         DashMap::with_hasher(RandomState::default())
     }
 
+    /// Creates a new DashMap with the specified shard amount, whose shards are created by `init`
+    /// from their index.
+    ///
+    /// Requires the `raw-api` feature to be enabled.
+    ///
+    /// See [`with_hasher_and_shard_amount_and_init`](Self::with_hasher_and_shard_amount_and_init).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shard_amount` is not greater than 1 and a power of two.
+    #[cfg(feature = "raw-api")]
+    pub fn with_shard_amount_and_init(
+        shard_amount: usize,
+        init: impl Fn(usize) -> HashMap<K, V>,
+    ) -> Self {
+        Self::with_hasher_and_shard_amount_and_init(RandomState::default(), shard_amount, init)
+    }
+
     /// Creates a new DashMap with a specified starting capacity.
     ///
     /// # Examples
@@ -178,14 +577,93 @@ impl<'a, K: 'a + Eq + Hash, V: 'a> DashMap<K, V, RandomState> {
             shard_amount,
         )
     }
+
+    /// Creates a new DashMap with the shard amount and per-shard capacities of a [`ShardLayout`].
+    ///
+    /// If the layout's shard amount is not greater than 1 and a power of two, the function will panic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let warm = DashMap::with_shard_amount(16);
+    /// (0..1000).for_each(|i| { warm.insert(i, i); });
+    ///
+    /// let fresh: DashMap<i32, i32> = DashMap::with_layout(&warm.export_layout());
+    /// assert_eq!(fresh.capacity(), warm.capacity());
+    /// ```
+    pub fn with_layout(layout: &ShardLayout) -> Self {
+        Self::with_layout_and_hasher(layout, RandomState::default())
+    }
+
+    /// Creates a new DashMap which reports changes in its memory usage to a [`MemoryTracker`].
+    ///
+    /// Inserted, replaced and removed entries are measured with [`MemoryTracker::entry_size`],
+    /// and growing or shrinking a shard's allocation is reported as well.
+    /// Values modified in place through references or closures are not measured again, except by
+    /// [`apply_batch`](Self::apply_batch) and [`update_batch`](Self::update_batch), and dropping or
+    /// consuming the map is not reported.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::{DashMap, MemoryTracker};
+    /// use std::sync::atomic::{AtomicIsize, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// #[derive(Default)]
+    /// struct Budget(AtomicIsize);
+    ///
+    /// impl MemoryTracker<u32, String> for Budget {
+    ///     fn entry_size(&self, _key: &u32, value: &String) -> usize {
+    ///         value.capacity()
+    ///     }
+    ///
+    ///     fn on_change(&self, delta: isize) {
+    ///         self.0.fetch_add(delta, Ordering::Relaxed);
+    ///     }
+    /// }
+    ///
+    /// let budget = Arc::new(Budget::default());
+    /// let map = DashMap::with_memory_tracker(budget.clone());
+    /// map.insert(1, String::with_capacity(100));
+    /// assert!(budget.0.load(Ordering::Relaxed) >= 100);
+    /// map.remove(&1);
+    /// map.shrink_to_fit();
+    /// assert_eq!(budget.0.load(Ordering::Relaxed), 0);
+    /// ```
+    pub fn with_memory_tracker(tracker: Arc<dyn MemoryTracker<K, V>>) -> Self {
+        Self::with_hasher_and_memory_tracker(RandomState::default(), tracker)
+    }
 }
 
-impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher + Clone> DashMap<K, V, S> {
+impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher> DashMap<K, V, S> {
     /// Wraps this `DashMap` into a read-only view. This view allows to obtain raw references to the stored values.
     pub fn into_read_only(self) -> ReadOnlyView<K, V, S> {
         ReadOnlyView::new(self)
     }
 
+    /// Moves the map behind an `Arc`, returning a [`Reader`] handle which only allows reading it
+    /// and a [`Writer`] handle which allows everything. Both handles can be cloned, and more readers
+    /// can be created from a writer, so read-only access can be handed out at the type level.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    /// use std::thread;
+    ///
+    /// let (reader, writer) = DashMap::new().split_handles();
+    ///
+    /// thread::spawn(move || writer.insert("config", 1)).join().unwrap();
+    /// assert!(reader.contains_key("config"));
+    /// ```
+    pub fn split_handles(self) -> (Reader<K, V, S>, Writer<K, V, S>) {
+        let writer = Writer::new(Arc::new(self));
+        (writer.reader(), writer)
+    }
+
     /// Creates a new DashMap with a capacity of 0 and the provided hasher.
     ///
     /// # Examples
@@ -272,86 +750,373 @@ impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher + Clone> DashMap<K, V, S> {
         let cps = capacity / shard_amount;
 
         let shards = (0..shard_amount)
-            .map(|_| CachePadded::new(RwLock::new(HashMap::with_capacity(cps))))
+            .map(|idx| CachePadded::new(lock::shard_lock(idx, HashMap::with_capacity(cps))))
             .collect();
 
         Self {
             shift,
             shards,
             hasher,
+            tracker: None,
+            watchers: OnceCell::new(),
+            lens: empty_lens(shard_amount),
+            metrics: Metrics::new(shard_amount),
+            shrink_policy: ShrinkPolicy::Never,
         }
     }
 
-    /// Hash a given item to produce a usize.
-    /// Uses the provided or default HashBuilder.
-    pub fn hash_usize<T: Hash>(&self, item: &T) -> usize {
-        self.hash_u64(item) as usize
+    /// Creates a new DashMap with a specified starting capacity, hasher and shard_amount.
+    ///
+    /// Unlike [`with_capacity_and_hasher_and_shard_amount`](Self::with_capacity_and_hasher_and_shard_amount),
+    /// this returns an error instead of panicking if the shard amount is not greater than 1 and a power of two.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    /// use std::collections::hash_map::RandomState;
+    ///
+    /// let map = DashMap::<u32, u32>::try_with_capacity_and_hasher_and_shard_amount(0, RandomState::new(), 12);
+    /// assert_eq!(map.unwrap_err().shard_amount(), 12);
+    /// ```
+    pub fn try_with_capacity_and_hasher_and_shard_amount(
+        capacity: usize,
+        hasher: S,
+        shard_amount: usize,
+    ) -> Result<Self, ShardAmountError> {
+        ShardAmountError::check(shard_amount)?;
+
+        Ok(Self::with_capacity_and_hasher_and_shard_amount(
+            capacity,
+            hasher,
+            shard_amount,
+        ))
     }
 
-    fn hash_u64<T: Hash>(&self, item: &T) -> u64 {
-        let mut hasher = self.hasher.build_hasher();
+    /// Creates a new DashMap with the provided hasher and the shard amount and per-shard capacities of a [`ShardLayout`].
+    ///
+    /// If the layout's shard amount is not greater than 1 and a power of two, the function will panic.
+    pub fn with_layout_and_hasher(layout: &ShardLayout, hasher: S) -> Self {
+        let shard_amount = layout.shard_amount();
 
-        item.hash(&mut hasher);
+        assert!(shard_amount > 1);
+        assert!(shard_amount.is_power_of_two());
 
-        hasher.finish()
+        let shift = util::ptr_size_bits() - ncb(shard_amount);
+
+        let shards = layout
+            .capacities()
+            .iter()
+            .enumerate()
+            .map(|(idx, &cap)| CachePadded::new(lock::shard_lock(idx, HashMap::with_capacity(cap))))
+            .collect();
+
+        Self {
+            shift,
+            shards,
+            hasher,
+            tracker: None,
+            watchers: OnceCell::new(),
+            lens: empty_lens(shard_amount),
+            metrics: Metrics::new(shard_amount),
+            shrink_policy: ShrinkPolicy::Never,
+        }
     }
 
-    cfg_if! {
-        if #[cfg(feature = "raw-api")] {
-            /// Allows you to peek at the inner shards that store your data.
-            /// You should probably not use this unless you know what you are doing.
-            ///
-            /// Requires the `raw-api` feature to be enabled.
-            ///
-            /// # Examples
-            ///
-            /// ```
-            /// use dashmap::DashMap;
-            ///
-            /// let map = DashMap::<(), ()>::new();
-            /// println!("Amount of shards: {}", map.shards().len());
-            /// ```
-            pub fn shards(&self) -> &[CachePadded<RwLock<HashMap<K, V>>>] {
-                &self.shards
-            }
+    /// Creates a new DashMap with the provided hasher and the shard amount and per-shard capacities of a [`ShardLayout`].
+    ///
+    /// Unlike [`with_layout_and_hasher`](Self::with_layout_and_hasher), this returns an error
+    /// instead of panicking if the layout's shard amount is not greater than 1 and a power of two,
+    /// which is useful for layouts loaded from a file.
+    pub fn try_with_layout_and_hasher(
+        layout: &ShardLayout,
+        hasher: S,
+    ) -> Result<Self, ShardAmountError> {
+        ShardAmountError::check(layout.shard_amount())?;
 
-            /// Provides mutable access to the inner shards that store your data.
-            /// You should probably not use this unless you know what you are doing.
-            ///
-            /// Requires the `raw-api` feature to be enabled.
-            ///
-            /// # Examples
-            ///
-            /// ```
-            /// use dashmap::DashMap;
-            /// use std::hash::{Hash, Hasher, BuildHasher};
-            ///
-            /// let mut map = DashMap::<i32, &'static str>::new();
-            /// let shard_ind = map.determine_map(&42);
-            /// let mut factory = map.hasher().clone();
-            /// let hasher = |tuple: &(i32, &'static str)| {
-            ///     let mut hasher = factory.build_hasher();
-            ///     tuple.0.hash(&mut hasher);
-            ///     hasher.finish()
-            /// };
-            /// let data = (42, "forty two");
-            /// let hash = hasher(&data);
-            /// map.shards_mut()[shard_ind].get_mut().insert_unique(hash, data, hasher);
-            /// assert_eq!(*map.get(&42).unwrap(), "forty two");
-            /// ```
-            pub fn shards_mut(&mut self) -> &mut [CachePadded<RwLock<HashMap<K, V>>>] {
-                &mut self.shards
-            }
+        Ok(Self::with_layout_and_hasher(layout, hasher))
+    }
 
-            /// Consumes this `DashMap` and returns the inner shards.
-            /// You should probably not use this unless you know what you are doing.
-            ///
-            /// Requires the `raw-api` feature to be enabled.
+    /// Creates a new DashMap with the provided hasher which reports changes in its memory usage to a [`MemoryTracker`].
+    ///
+    /// See [`DashMap::with_memory_tracker`] for what is reported.
+    pub fn with_hasher_and_memory_tracker(
+        hasher: S,
+        tracker: Arc<dyn MemoryTracker<K, V>>,
+    ) -> Self {
+        let mut map = Self::with_hasher(hasher);
+        map.tracker = Some(tracker);
+        map
+    }
+
+    pub(crate) fn tracker(&self) -> Option<&dyn MemoryTracker<K, V>> {
+        self.tracker.as_deref()
+    }
+
+    /// The waker registries of the shards, allocated when the first future starts waiting.
+    pub(crate) fn watchers(&self) -> &[ShardWatchers] {
+        self.watchers.get_or_init(|| {
+            (0..self.shards.len())
+                .map(|_| ShardWatchers::default())
+                .collect()
+        })
+    }
+
+    /// The counter of the entries in the given shard, kept up to date under its write lock.
+    pub(crate) fn len_counter(&self, idx: usize) -> Option<&AtomicUsize> {
+        Some(&self.lens[idx])
+    }
+
+    /// Returns what entries use to apply the shrink policy after removing themselves, if it ever shrinks.
+    pub(crate) fn shrinker(&self) -> Option<&dyn ShrinkShard<K, V>> {
+        match self.shrink_policy {
+            ShrinkPolicy::Never => None,
+            ShrinkPolicy::OnFallBelow(_) => Some(self),
+        }
+    }
+
+    /// Resets the counter of a shard to its length, either under its lock or with exclusive access.
+    fn sync_len(&self, idx: usize, len: usize) {
+        self.lens[idx].store(len, Ordering::Relaxed);
+    }
+
+    /// Returns what is needed to wake the watchers of a key inserted into the given shard, if anyone ever waited.
+    pub(crate) fn notify(&self, idx: usize, hash: u64) -> watch::Notify<'_> {
+        self.watchers.get().map(|watchers| (&watchers[idx], hash))
+    }
+
+    /// Captures the shard amount and the current capacity of each shard,
+    /// so that a map with identical sizing can later be created with [`DashMap::with_layout`].
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    pub fn export_layout(&self) -> ShardLayout {
+        ShardLayout::new(self.shards.iter().map(|s| s.read().capacity()).collect())
+    }
+
+    /// Reports the number of entries, the capacity, the estimated tombstones and the allocated
+    /// bytes of every shard, for capacity planning and alerting.
+    ///
+    /// The shards are locked one at a time, so the report is not a consistent snapshot of the whole map.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let map = DashMap::with_shard_amount(4);
+    /// for i in 0..100 {
+    ///     map.insert(i, i);
+    /// }
+    ///
+    /// let report = map.occupancy_report();
+    /// assert_eq!(report.shards().len(), 4);
+    /// assert_eq!(report.total().len, 100);
+    /// assert!(report.total().capacity >= 100);
+    /// ```
+    pub fn occupancy_report(&self) -> OccupancyReport {
+        OccupancyReport::new(
+            self.shards
+                .iter()
+                .map(|shard| ShardOccupancy::of(&*shard.read()))
+                .collect(),
+        )
+    }
+
+    /// Sets when shards give memory back after entries were removed from them.
+    ///
+    /// The policy is evaluated for a shard after [`remove`](Self::remove), [`remove_if`](Self::remove_if),
+    /// [`remove_if_mut`](Self::remove_if_mut) and [`remove_keys`](Self::remove_keys) removed an entry from it,
+    /// and after [`retain`](Self::retain) and [`clear`](Self::clear) went over it, while its lock is held anyway.
+    /// Entries removed through the entry API don't cause a shrink.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::{DashMap, ShrinkPolicy};
+    ///
+    /// let map = DashMap::new().with_shrink_policy(ShrinkPolicy::OnFallBelow(0.25));
+    /// for i in 0..10_000 {
+    ///     map.insert(i, i);
+    /// }
+    ///
+    /// map.retain(|k, _| *k < 10);
+    /// assert!(map.capacity() < 1000);
+    /// ```
+    pub fn with_shrink_policy(mut self, policy: ShrinkPolicy) -> Self {
+        self.shrink_policy = policy;
+        self
+    }
+
+    /// Returns the shrink policy of the map, set with [`with_shrink_policy`](Self::with_shrink_policy).
+    pub fn shrink_policy(&self) -> ShrinkPolicy {
+        self.shrink_policy
+    }
+
+    /// Hash a given item to produce a usize.
+    /// Uses the provided or default HashBuilder.
+    pub fn hash_usize<T: Hash>(&self, item: &T) -> usize {
+        self.hash_u64(item) as usize
+    }
+
+    fn hash_u64<T: Hash>(&self, item: &T) -> u64 {
+        let mut hasher = self.hasher.build_hasher();
+
+        item.hash(&mut hasher);
+
+        hasher.finish()
+    }
+
+    cfg_if! {
+        if #[cfg(feature = "raw-api")] {
+            /// Allows you to peek at the inner shards that store your data.
+            /// You should probably not use this unless you know what you are doing.
+            ///
+            /// Requires the `raw-api` feature to be enabled.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// use dashmap::DashMap;
+            ///
+            /// let map = DashMap::<(), ()>::new();
+            /// println!("Amount of shards: {}", map.shards().len());
+            /// ```
+            ///
+            /// Entries inserted or removed through the shards are not reflected by [`len`](Self::len)
+            /// until [`len_exact`](Self::len_exact) is called.
+            pub fn shards(&self) -> &[CachePadded<RwLock<HashMap<K, V>>>] {
+                &self.shards
+            }
+
+            /// Provides mutable access to the inner shards that store your data.
+            /// You should probably not use this unless you know what you are doing.
+            ///
+            /// Requires the `raw-api` feature to be enabled.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// use dashmap::DashMap;
+            /// use std::hash::{Hash, Hasher, BuildHasher};
+            ///
+            /// let mut map = DashMap::<i32, &'static str>::new();
+            /// let shard_ind = map.determine_map(&42);
+            /// let mut factory = map.hasher().clone();
+            /// let hasher = |tuple: &(i32, &'static str)| {
+            ///     let mut hasher = factory.build_hasher();
+            ///     tuple.0.hash(&mut hasher);
+            ///     hasher.finish()
+            /// };
+            /// let data = (42, "forty two");
+            /// let hash = hasher(&data);
+            /// map.shards_mut()[shard_ind].get_mut().insert_unique(hash, data, hasher);
+            /// assert_eq!(*map.get(&42).unwrap(), "forty two");
+            /// ```
+            ///
+            /// Entries inserted or removed through the shards are not reflected by [`len`](Self::len)
+            /// until [`len_exact`](Self::len_exact) is called.
+            pub fn shards_mut(&mut self) -> &mut [CachePadded<RwLock<HashMap<K, V>>>] {
+                &mut self.shards
+            }
+
+            /// Consumes this `DashMap` and returns the inner shards.
+            /// You should probably not use this unless you know what you are doing.
+            ///
+            /// Requires the `raw-api` feature to be enabled.
             ///
             /// See [`DashMap::shards()`] and [`DashMap::shards_mut()`] for more information.
             pub fn into_shards(self) -> Box<[CachePadded<RwLock<HashMap<K, V>>>]> {
                 self.shards
             }
+
+            /// Creates a `DashMap` from shards, for example ones returned by [`DashMap::into_shards()`].
+            /// You should probably not use this unless you know what you are doing.
+            ///
+            /// Every entry must be in the shard [`determine_map`](Self::determine_map) chooses for its key
+            /// with the given hasher, and be stored with the hash of its key; entries elsewhere can't be found.
+            ///
+            /// Requires the `raw-api` feature to be enabled.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// use dashmap::DashMap;
+            /// use std::collections::hash_map::RandomState;
+            ///
+            /// let hasher = RandomState::new();
+            /// let map = DashMap::with_hasher_and_shard_amount(hasher.clone(), 8);
+            /// map.insert("Rufus", "dog");
+            ///
+            /// let map = DashMap::from_shards(map.into_shards(), hasher);
+            /// assert_eq!(map.len(), 1);
+            /// assert_eq!(*map.get("Rufus").unwrap(), "dog");
+            /// ```
+            ///
+            /// # Panics
+            ///
+            /// Panics if the amount of shards is not greater than 1 and a power of two.
+            pub fn from_shards(shards: Box<[CachePadded<RwLock<HashMap<K, V>>>]>, hasher: S) -> Self {
+                let shard_amount = shards.len();
+
+                assert!(shard_amount > 1);
+                assert!(shard_amount.is_power_of_two());
+
+                let lens = shards
+                    .iter()
+                    .map(|shard| CachePadded::new(AtomicUsize::new(shard.read().len())))
+                    .collect();
+
+                Self {
+                    shift: util::ptr_size_bits() - ncb(shard_amount),
+                    shards,
+                    hasher,
+                    tracker: None,
+                    watchers: OnceCell::new(),
+                    lens,
+                    metrics: Metrics::new(shard_amount),
+                    shrink_policy: ShrinkPolicy::Never,
+                }
+            }
+
+            /// Creates a new DashMap with the specified shard amount, whose shards are created by `init`
+            /// from their index, for example to give shards known to be busier a larger capacity.
+            ///
+            /// The same rules as for [`from_shards`](Self::from_shards) apply to entries the shards are
+            /// created with.
+            ///
+            /// Requires the `raw-api` feature to be enabled.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// use dashmap::DashMap;
+            /// use hashbrown::HashTable;
+            /// use std::collections::hash_map::RandomState;
+            ///
+            /// let map: DashMap<u32, u32> = DashMap::with_hasher_and_shard_amount_and_init(
+            ///     RandomState::new(),
+            ///     4,
+            ///     |idx| HashTable::with_capacity(if idx == 0 { 1024 } else { 16 }),
+            /// );
+            /// assert!(map.shards()[0].read().capacity() >= 1024);
+            /// ```
+            ///
+            /// # Panics
+            ///
+            /// Panics if `shard_amount` is not greater than 1 and a power of two.
+            pub fn with_hasher_and_shard_amount_and_init(
+                hasher: S,
+                shard_amount: usize,
+                init: impl Fn(usize) -> HashMap<K, V>,
+            ) -> Self {
+                let shards = (0..shard_amount)
+                    .map(|idx| CachePadded::new(lock::shard_lock(idx, init(idx))))
+                    .collect();
+
+                Self::from_shards(shards, hasher)
+            }
         } else {
             #[allow(dead_code)]
             pub(crate) fn shards(&self) -> &[CachePadded<RwLock<HashMap<K, V>>>] {
@@ -459,6 +1224,46 @@ impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher + Clone> DashMap<K, V, S> {
         }
     }
 
+    /// Returns an identifier of the shard a certain key is stored in.
+    ///
+    /// Keys with the same identifier share a lock, which can be used to group work by shard.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let map: DashMap<&str, u32> = DashMap::new();
+    /// assert_eq!(map.shard_id("apple"), map.shard_id("apple"));
+    /// ```
+    pub fn shard_id<Q>(&self, key: &Q) -> ShardId
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.hash_usize(&key);
+        ShardId(self.determine_shard(hash))
+    }
+
+    /// Returns `true` if the two keys are stored in the same shard.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let map: DashMap<&str, u32> = DashMap::with_shard_amount(2);
+    /// assert!(map.same_shard("apple", "apple"));
+    /// ```
+    pub fn same_shard<Q1, Q2>(&self, a: &Q1, b: &Q2) -> bool
+    where
+        K: Borrow<Q1> + Borrow<Q2>,
+        Q1: Hash + Eq + ?Sized,
+        Q2: Hash + Eq + ?Sized,
+    {
+        self.shard_id(a) == self.shard_id(b)
+    }
+
     /// Returns a reference to the map's [`BuildHasher`].
     ///
     /// # Examples
@@ -493,6 +1298,51 @@ impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher + Clone> DashMap<K, V, S> {
         self._insert(key, value)
     }
 
+    /// Inserts a value under a possibly borrowed key, converting the key into an owned one
+    /// only if it isn't in the map yet. Returns the old value associated with the key if there was one.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    /// use std::borrow::Cow;
+    ///
+    /// let hits: DashMap<String, u32> = DashMap::new();
+    /// assert_eq!(hits.insert_with_cow(Cow::Borrowed("/index.html"), 1), None);
+    /// // The key is already present, so this doesn't allocate a new `String`.
+    /// assert_eq!(hits.insert_with_cow(Cow::Borrowed("/index.html"), 2), Some(1));
+    /// ```
+    pub fn insert_with_cow<Q>(&self, key: Cow<'_, Q>, value: V) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ToOwned<Owned = K> + ?Sized,
+    {
+        self._insert_with_cow(key, value)
+    }
+
+    /// Inserts a key and the value returned by a function into the map.
+    /// Returns the old value associated with the key if there was one.
+    ///
+    /// The function is only called once the slot for the key has been located,
+    /// which lets large values be constructed directly into the map instead of being moved through the call.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let map = DashMap::new();
+    /// map.insert_with("buffer", || [0u8; 4096]);
+    /// assert_eq!(map.get("buffer").unwrap().len(), 4096);
+    /// ```
+    pub fn insert_with(&self, key: K, value: impl FnOnce() -> V) -> Option<V> {
+        self._insert_with(key, value)
+    }
+
     /// Removes an entry from the map, returning the key and value if they existed in the map.
     ///
     /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
@@ -543,32 +1393,37 @@ impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher + Clone> DashMap<K, V, S> {
         self._remove_if(key, f)
     }
 
-    pub fn remove_if_mut<Q>(&self, key: &Q, f: impl FnOnce(&K, &mut V) -> bool) -> Option<(K, V)>
-    where
-        K: Borrow<Q>,
-        Q: Hash + Eq + ?Sized,
-    {
-        self._remove_if_mut(key, f)
-    }
-
-    /// Creates an iterator over a DashMap yielding immutable references.
+    /// Like [`remove_if`](Self::remove_if), but doesn't wait if the shard is locked.
     ///
-    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    /// Returns [`TryResult::Present`] with the removed entry, [`TryResult::Absent`] if the key is missing
+    /// or the conditional function returned false, and [`TryResult::Locked`] if the shard is locked.
     ///
     /// # Examples
     ///
     /// ```
     /// use dashmap::DashMap;
     ///
-    /// let words = DashMap::new();
-    /// words.insert("hello", "world");
-    /// assert_eq!(words.iter().count(), 1);
+    /// let soccer_team = DashMap::new();
+    /// soccer_team.insert("Sam", "Forward");
+    ///
+    /// let captain = soccer_team.get("Sam");
+    /// assert!(soccer_team.try_remove_if("Sam", |_, _| true).is_locked());
+    /// drop(captain);
+    ///
+    /// assert!(soccer_team.try_remove_if("Sam", |_, position| position == &"Goalie").is_absent());
+    /// assert_eq!(soccer_team.try_remove_if("Sam", |_, _| true).unwrap(), ("Sam", "Forward"));
     /// ```
-    pub fn iter(&'a self) -> Iter<'a, K, V> {
-        self._iter()
+    pub fn try_remove_if<Q>(&self, key: &Q, f: impl FnOnce(&K, &V) -> bool) -> TryResult<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self._try_remove_if(key, f)
     }
 
-    /// Iterator over a DashMap yielding mutable references.
+    /// Removes an entry from the map, returning the key and value if they existed in the map.
+    ///
+    /// This is the same as [`remove`](Self::remove), under the name used by `std::collections::HashMap`.
     ///
     /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
     ///
@@ -577,293 +1432,527 @@ impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher + Clone> DashMap<K, V, S> {
     /// ```
     /// use dashmap::DashMap;
     ///
-    /// let map = DashMap::new();
-    /// map.insert("Johnny", 21);
-    /// map.iter_mut().for_each(|mut r| *r += 1);
-    /// assert_eq!(*map.get("Johnny").unwrap(), 22);
+    /// let soccer_team = DashMap::new();
+    /// soccer_team.insert("Jack", "Goalie");
+    /// assert_eq!(soccer_team.remove_entry("Jack"), Some(("Jack", "Goalie")));
+    /// assert_eq!(soccer_team.remove_entry("Jack"), None);
     /// ```
-    pub fn iter_mut(&'a self) -> IterMut<'a, K, V> {
-        self._iter_mut()
+    pub fn remove_entry<Q>(&self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self._remove(key)
     }
 
-    /// Get an immutable reference to an entry in the map
+    /// Removes an entry from the map, returning the key and value
+    /// if the entry existed and the provided conditional function returned true.
     ///
-    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    /// Unlike [`remove_if`](Self::remove_if), the function gets a mutable reference to the value,
+    /// so it can update it or flush state out of it before deciding on the removal.
     ///
-    /// # Examples
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
     ///
     /// ```
     /// use dashmap::DashMap;
     ///
-    /// let youtubers = DashMap::new();
-    /// youtubers.insert("Bosnian Bill", 457000);
-    /// assert_eq!(*youtubers.get("Bosnian Bill").unwrap(), 457000);
+    /// let buffers = DashMap::new();
+    /// buffers.insert("log", vec![1, 2, 3]);
+    /// let mut flushed = Vec::new();
+    /// let removed = buffers.remove_if_mut("log", |_, buffer| {
+    ///     flushed.append(buffer);
+    ///     false
+    /// });
+    /// assert!(removed.is_none());
+    /// assert_eq!(flushed, [1, 2, 3]);
+    /// assert!(buffers.get("log").unwrap().is_empty());
     /// ```
-    pub fn get<Q>(&'a self, key: &Q) -> Option<Ref<'a, K, V>>
+    pub fn remove_if_mut<Q>(&self, key: &Q, f: impl FnOnce(&K, &mut V) -> bool) -> Option<(K, V)>
     where
         K: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
-        self._get(key)
+        self._remove_if_mut(key, f)
     }
 
-    /// Get a mutable reference to an entry in the map
+    /// Returns a [`Debug`](fmt::Debug) view of the map which never blocks, printing shards
+    /// which are write-locked as `<locked>` instead of their entries.
     ///
-    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    /// The shards are read one at a time, so the output is not a consistent snapshot.
+    ///
+    /// **Locking behaviour:** Never blocks, so it is safe to use while holding any sort of reference into the map.
     ///
     /// # Examples
     ///
     /// ```
     /// use dashmap::DashMap;
     ///
-    /// let class = DashMap::new();
-    /// class.insert("Albin", 15);
-    /// *class.get_mut("Albin").unwrap() -= 1;
-    /// assert_eq!(*class.get("Albin").unwrap(), 14);
+    /// let map = DashMap::with_shard_amount(2);
+    /// map.insert("a", 1);
+    ///
+    /// let guard = map.get_mut("a").unwrap();
+    /// let printed = format!("{:?}", map.debug_lossy());
+    /// assert!(printed.contains("<locked>"));
+    /// drop(guard);
+    ///
+    /// assert_eq!(format!("{:?}", map.debug_lossy()), r#"{"a": 1}"#);
     /// ```
-    pub fn get_mut<Q>(&'a self, key: &Q) -> Option<RefMut<'a, K, V>>
-    where
-        K: Borrow<Q>,
-        Q: Hash + Eq + ?Sized,
-    {
-        self._get_mut(key)
+    pub fn debug_lossy(&self) -> DebugLossy<'_, K, V, S> {
+        DebugLossy { map: self }
     }
 
-    /// Get an immutable reference to an entry in the map, if the shard is not locked.
-    /// If the shard is locked, the function will return [TryResult::Locked].
+    /// Read-locks every shard of the map without blocking, returning a consistent [`Debug`](fmt::Debug)
+    /// view of it, or `None` if any shard is write-locked.
+    ///
+    /// The locks are held until the returned view is dropped.
+    ///
+    /// **Locking behaviour:** Never blocks. Modifying the map while the view is alive deadlocks.
     ///
     /// # Examples
     ///
     /// ```
     /// use dashmap::DashMap;
-    /// use dashmap::try_result::TryResult;
     ///
     /// let map = DashMap::new();
-    /// map.insert("Johnny", 21);
+    /// map.insert("a", 1);
+    /// assert_eq!(format!("{:?}", map.try_debug().unwrap()), r#"{"a": 1}"#);
     ///
-    /// assert_eq!(*map.try_get("Johnny").unwrap(), 21);
+    /// let _guard = map.get_mut("a").unwrap();
+    /// assert!(map.try_debug().is_none());
+    /// ```
+    pub fn try_debug(&self) -> Option<TryDebug<'_, K, V>> {
+        let shards = self
+            .shards
+            .iter()
+            .map(|shard| shard.try_read())
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(TryDebug { shards })
+    }
+
+    /// Creates an iterator over a DashMap yielding immutable references.
     ///
-    /// let _result1_locking = map.get_mut("Johnny");
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    ///
+    /// # Examples
     ///
-    /// let result2 = map.try_get("Johnny");
-    /// assert!(result2.is_locked());
     /// ```
-    pub fn try_get<Q>(&'a self, key: &Q) -> TryResult<Ref<'a, K, V>>
-    where
-        K: Borrow<Q>,
-        Q: Hash + Eq + ?Sized,
-    {
-        self._try_get(key)
+    /// use dashmap::DashMap;
+    ///
+    /// let words = DashMap::new();
+    /// words.insert("hello", "world");
+    /// assert_eq!(words.iter().count(), 1);
+    /// ```
+    pub fn iter(&'a self) -> Iter<'a, K, V> {
+        self._iter()
     }
 
-    /// Get a mutable reference to an entry in the map, if the shard is not locked.
-    /// If the shard is locked, the function will return [TryResult::Locked].
+    /// Creates an iterator over a DashMap yielding immutable references,
+    /// which skips the shards it can't read-lock immediately instead of waiting for them.
+    ///
+    /// The result is best-effort: the entries of shards which are locked for writing when the iterator
+    /// reaches them are left out, and [`AvailableIter::skipped`](iter::AvailableIter::skipped) tells how many
+    /// shards that were. This suits monitoring and estimates which must never block behind a long writer.
+    ///
+    /// **Locking behaviour:** Never waits for a lock, so it can't deadlock.
     ///
     /// # Examples
     ///
     /// ```
     /// use dashmap::DashMap;
-    /// use dashmap::try_result::TryResult;
     ///
-    /// let map = DashMap::new();
-    /// map.insert("Johnny", 21);
+    /// let map: DashMap<u32, u32> = (0..100).map(|i| (i, i)).collect();
     ///
-    /// *map.try_get_mut("Johnny").unwrap() += 1;
-    /// assert_eq!(*map.get("Johnny").unwrap(), 22);
+    /// let writing = map.get_mut(&0);
+    /// let visible = map.iter_available().count();
+    /// assert!(visible < 100);
     ///
-    /// let _result1_locking = map.get("Johnny");
+    /// drop(writing);
+    /// assert_eq!(map.iter_available().count(), 100);
+    /// ```
+    pub fn iter_available(&'a self) -> AvailableIter<'a, K, V> {
+        AvailableIter::new(self)
+    }
+
+    /// Creates an iterator over a DashMap yielding immutable references in a reproducible order.
+    ///
+    /// Two maps with the same contents, the same shard amount and equally seeded hashers
+    /// are iterated in the same order, regardless of the order the entries were inserted in
+    /// or the capacity of the maps. This is useful for golden-file tests; note that
+    /// [`RandomState`] is seeded differently for every map, so use a fixed-seed hasher.
+    ///
+    /// Entries are sorted by hash one shard at a time, so this is slower than [`iter`](Self::iter).
+    ///
+    /// The order is only reproducible up to hash collisions: distinct keys with the same 64-bit hash
+    /// are yielded in table order, which does depend on the insertion history. With a hasher which can
+    /// map different keys to the same hash, collect the entries and sort them by key instead.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    ///
+    /// # Examples
     ///
-    /// let result2 = map.try_get_mut("Johnny");
-    /// assert!(result2.is_locked());
     /// ```
-    pub fn try_get_mut<Q>(&'a self, key: &Q) -> TryResult<RefMut<'a, K, V>>
+    /// use dashmap::DashMap;
+    /// use std::collections::hash_map::DefaultHasher;
+    /// use std::hash::BuildHasherDefault;
+    ///
+    /// let a: DashMap<u32, u32, BuildHasherDefault<DefaultHasher>> = DashMap::default();
+    /// let b: DashMap<u32, u32, BuildHasherDefault<DefaultHasher>> = DashMap::default();
+    /// for i in 0..100 {
+    ///     a.insert(i, i);
+    ///     b.insert(99 - i, 99 - i);
+    /// }
+    /// let a: Vec<u32> = a.iter_deterministic().map(|r| *r.key()).collect();
+    /// let b: Vec<u32> = b.iter_deterministic().map(|r| *r.key()).collect();
+    /// assert_eq!(a, b);
+    /// ```
+    pub fn iter_deterministic(&'a self) -> DeterministicIter<'a, K, V, S> {
+        self._iter_deterministic()
+    }
+
+    /// Creates an iterator over clones of the keys and values of the map.
+    ///
+    /// It holds no lock between items, so it can be kept across `.await` points in async code
+    /// where the guards of [`iter`](Self::iter) must not be held. Only the entries of one shard are
+    /// cloned at a time, which bounds the memory used to the size of the largest shard.
+    ///
+    /// **Locking behaviour:** Each shard is read-locked only while its entries are cloned,
+    /// so the map can be modified while iterating. May deadlock if called or advanced
+    /// when holding a mutable reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let stock = DashMap::new();
+    /// stock.insert("apples", 3);
+    /// stock.insert("pears", 0);
+    ///
+    /// for (fruit, count) in stock.iter_cloned() {
+    ///     if count == 0 {
+    ///         stock.remove(fruit);
+    ///     }
+    /// }
+    /// assert_eq!(stock.len(), 1);
+    /// ```
+    pub fn iter_cloned(&'a self) -> ClonedIter<'a, K, V>
     where
-        K: Borrow<Q>,
-        Q: Hash + Eq + ?Sized,
+        K: Clone,
+        V: Clone,
     {
-        self._try_get_mut(key)
+        ClonedIter::new(self)
     }
 
-    /// Remove excess capacity to reduce memory usage.
+    /// Iterator over a DashMap yielding mutable references.
     ///
     /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
     /// # Examples
     ///
     /// ```
     /// use dashmap::DashMap;
-    /// use dashmap::try_result::TryResult;
     ///
     /// let map = DashMap::new();
     /// map.insert("Johnny", 21);
-    /// assert!(map.capacity() > 0);
-    /// map.remove("Johnny");
-    /// map.shrink_to_fit();
-    /// assert_eq!(map.capacity(), 0);
+    /// map.iter_mut().for_each(|mut r| *r += 1);
+    /// assert_eq!(*map.get("Johnny").unwrap(), 22);
     /// ```
-    pub fn shrink_to_fit(&self) {
-        self._shrink_to_fit();
+    pub fn iter_mut(&'a self) -> IterMut<'a, K, V> {
+        self._iter_mut()
     }
 
-    /// Retain elements that whose predicates return true
-    /// and discard elements whose predicates return false.
+    /// Iterator over a DashMap yielding chunks of at most about `chunk_size` mutable references
+    /// from the same shard, releasing the write lock of the shard between chunks.
     ///
-    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    /// Long mutations of the whole map hold a shard for the time of a single chunk only, so readers
+    /// and writers of the shard can interleave. Every entry present for the whole iteration is yielded
+    /// exactly once. Entries inserted while the map is iterated may or may not be yielded, and entries
+    /// removed in the meantime are skipped. Keys whose hashes collide are always yielded in the same
+    /// chunk, so a chunk can be larger than `chunk_size`. A `chunk_size` of 0 is treated as 1.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map,
+    /// including a chunk previously yielded by the same iterator.
     ///
     /// # Examples
     ///
     /// ```
     /// use dashmap::DashMap;
     ///
-    /// let people = DashMap::new();
-    /// people.insert("Albin", 15);
-    /// people.insert("Jones", 22);
-    /// people.insert("Charlie", 27);
-    /// people.retain(|_, v| *v > 20);
-    /// assert_eq!(people.len(), 2);
+    /// let map: DashMap<u32, u32> = (0..100).map(|i| (i, i)).collect();
+    ///
+    /// for chunk in map.iter_mut_chunks(16) {
+    ///     for mut r in chunk {
+    ///         *r += 1;
+    ///     }
+    /// }
+    ///
+    /// assert_eq!(*map.get(&7).unwrap(), 8);
     /// ```
-    pub fn retain(&self, f: impl FnMut(&K, &mut V) -> bool) {
-        self._retain(f);
+    pub fn iter_mut_chunks(&'a self, chunk_size: usize) -> IterMutChunks<'a, K, V, S> {
+        self._iter_mut_chunks(chunk_size)
     }
 
-    /// Fetches the total number of key-value pairs stored in the map.
+    /// Compares the map to another one, yielding the keys which are only in one of the maps
+    /// and the keys whose values differ.
     ///
-    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    /// Both maps are iterated shard by shard, looking every key up in the other map. As with
+    /// [`iter`](Self::iter), entries modified during the comparison may or may not be reported.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into either map,
+    /// or if `other` is the same map.
     ///
     /// # Examples
     ///
     /// ```
+    /// use dashmap::iter::Diff;
     /// use dashmap::DashMap;
     ///
-    /// let people = DashMap::new();
-    /// people.insert("Albin", 15);
-    /// people.insert("Jones", 22);
-    /// people.insert("Charlie", 27);
-    /// assert_eq!(people.len(), 3);
+    /// let old = DashMap::new();
+    /// old.insert("a", 1);
+    /// old.insert("b", 2);
+    ///
+    /// let new = DashMap::new();
+    /// new.insert("b", 3);
+    /// new.insert("c", 4);
+    ///
+    /// let mut changes: Vec<_> = old
+    ///     .diff(&new)
+    ///     .map(|d| match d {
+    ///         Diff::OnlyLeft(l) => (*l.key(), Some(*l.value()), None),
+    ///         Diff::OnlyRight(r) => (*r.key(), None, Some(*r.value())),
+    ///         Diff::Changed(l, r) => (*l.key(), Some(*l.value()), Some(*r.value())),
+    ///     })
+    ///     .collect();
+    /// changes.sort();
+    ///
+    /// assert_eq!(changes, [("a", Some(1), None), ("b", Some(2), Some(3)), ("c", None, Some(4))]);
     /// ```
-    pub fn len(&self) -> usize {
-        self._len()
+    pub fn diff<'b, S2: BuildHasher>(
+        &'a self,
+        other: &'b DashMap<K, V, S2>,
+    ) -> DiffIter<'a, 'b, K, V, S, S2, fn(&V, &V) -> bool>
+    where
+        V: PartialEq,
+    {
+        self._diff_by(other, V::eq)
     }
 
-    /// Checks if the map is empty or not.
+    /// Compares the map to another one like [`diff`](Self::diff), using a closure to check
+    /// if the values of a key are equal.
     ///
-    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into either map,
+    /// or if `other` is the same map.
     ///
     /// # Examples
     ///
     /// ```
     /// use dashmap::DashMap;
     ///
-    /// let map = DashMap::<(), ()>::new();
-    /// assert!(map.is_empty());
+    /// let old = DashMap::new();
+    /// old.insert("a", 1.0);
+    ///
+    /// let new = DashMap::new();
+    /// new.insert("a", 1.0001);
+    ///
+    /// assert_eq!(old.diff_by(&new, |a: &f64, b: &f64| (a - b).abs() < 0.01).count(), 0);
     /// ```
-    pub fn is_empty(&self) -> bool {
-        self._is_empty()
+    pub fn diff_by<'b, S2: BuildHasher, F: FnMut(&V, &V) -> bool>(
+        &'a self,
+        other: &'b DashMap<K, V, S2>,
+        eq: F,
+    ) -> DiffIter<'a, 'b, K, V, S, S2, F> {
+        self._diff_by(other, eq)
     }
 
-    /// Removes all key-value pairs in the map.
+    /// Returns a reference to the first entry found to satisfy a predicate, stopping the scan there.
     ///
-    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    /// The shards are scanned in order, each under its read lock, and the returned reference
+    /// keeps the lock of its shard. Which entry is found first if several match is unspecified.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
     ///
     /// # Examples
     ///
     /// ```
     /// use dashmap::DashMap;
     ///
-    /// let stats = DashMap::new();
-    /// stats.insert("Goals", 4);
-    /// assert!(!stats.is_empty());
-    /// stats.clear();
-    /// assert!(stats.is_empty());
+    /// let ages = DashMap::new();
+    /// ages.insert("Albin", 15);
+    /// ages.insert("Jones", 22);
+    /// assert_eq!(*ages.find_any(|_, age| *age > 20).unwrap().key(), "Jones");
+    /// assert!(ages.find_any(|_, age| *age > 30).is_none());
     /// ```
-    pub fn clear(&self) {
-        self._clear();
+    pub fn find_any(&'a self, pred: impl FnMut(&K, &V) -> bool) -> Option<Ref<'a, K, V>> {
+        self._find_any(pred)
     }
 
-    /// Returns how many key-value pairs the map can store without reallocating.
+    /// Checks if any entry satisfies a predicate, stopping the scan at the first match.
     ///
     /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
-    pub fn capacity(&self) -> usize {
-        self._capacity()
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let ages = DashMap::new();
+    /// ages.insert("Albin", 15);
+    /// assert!(ages.any_entry(|_, age| *age < 18));
+    /// assert!(!ages.any_entry(|name, _| name.is_empty()));
+    /// ```
+    pub fn any_entry(&self, mut pred: impl FnMut(&K, &V) -> bool) -> bool {
+        self.shards
+            .iter()
+            .any(|shard| shard.read().iter().any(|(k, v)| pred(k, v)))
     }
 
-    /// Modify a specific value according to a function.
+    /// Aggregates the map one shard at a time.
     ///
-    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    /// `map_fn` is called once per shard, under its read lock, with an iterator
+    /// over the entries of that shard. The per-shard results are then combined with `reduce_fn`.
+    /// Compared to folding over [`iter`](Self::iter), this avoids creating a guard for every entry.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
     ///
     /// # Examples
     ///
     /// ```
     /// use dashmap::DashMap;
     ///
-    /// let stats = DashMap::new();
-    /// stats.insert("Goals", 4);
-    /// stats.alter("Goals", |_, v| v * 2);
-    /// assert_eq!(*stats.get("Goals").unwrap(), 8);
+    /// let scores = DashMap::new();
+    /// scores.insert("Alice", 3);
+    /// scores.insert("Bob", 5);
+    /// let total = scores.map_reduce(|shard| shard.map(|(_, v)| v).sum::<i32>(), |a, b| a + b);
+    /// assert_eq!(total, 8);
     /// ```
+    pub fn map_reduce<T>(
+        &self,
+        map_fn: impl FnMut(ShardIter<'_, K, V>) -> T,
+        reduce_fn: impl FnMut(T, T) -> T,
+    ) -> T {
+        self._map_reduce(map_fn, reduce_fn)
+    }
+
+    /// Calls a function on every entry of the map, catching panics per shard.
     ///
-    /// # Panics
+    /// If the function panics, the remaining entries of that shard are skipped,
+    /// but iteration continues with the next shard. Every shard that panicked is reported in the error.
     ///
-    /// If the given closure panics, then `alter` will abort the process
-    pub fn alter<Q>(&self, key: &Q, f: impl FnOnce(&K, V) -> V)
-    where
-        K: Borrow<Q>,
-        Q: Hash + Eq + ?Sized,
-    {
-        self._alter(key, f);
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let map = DashMap::new();
+    /// map.insert("Johnny", 21);
+    /// map.insert("Jimmy", 0);
+    /// let result = map.for_each_mut_resilient(|_, v| {
+    ///     assert!(*v > 0);
+    ///     *v += 1;
+    /// });
+    /// assert_eq!(result.unwrap_err().len(), 1);
+    /// assert_eq!(*map.get("Jimmy").unwrap(), 0);
+    /// ```
+    pub fn for_each_mut_resilient(&self, f: impl FnMut(&K, &mut V)) -> Result<(), Vec<ShardError>> {
+        self._for_each_mut_resilient(f)
     }
 
-    /// Modify every value in the map according to a function.
+    /// Calls a function on every key of the map.
     ///
-    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    /// Unlike [`iter`](Self::iter), no guard is created per entry and nothing is allocated, which makes it
+    /// suited for lightweight scans such as building a bloom filter. Each shard is read-locked while its
+    /// keys are visited, so the keys are not a consistent snapshot if the map is modified concurrently.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map,
+    /// or if `f` modifies the map.
     ///
     /// # Examples
     ///
     /// ```
     /// use dashmap::DashMap;
     ///
-    /// let stats = DashMap::new();
-    /// stats.insert("Wins", 4);
-    /// stats.insert("Losses", 2);
-    /// stats.alter_all(|_, v| v + 1);
-    /// assert_eq!(*stats.get("Wins").unwrap(), 5);
-    /// assert_eq!(*stats.get("Losses").unwrap(), 3);
+    /// let map: DashMap<u64, ()> = (0..64).map(|i| (i, ())).collect();
+    /// let mut filter = 0u64;
+    /// map.visit_keys(|k| filter |= 1 << (k % 64));
+    /// assert_eq!(filter, u64::MAX);
     /// ```
+    pub fn visit_keys(&self, mut f: impl FnMut(&K)) {
+        self._visit_entries(|k, _v| f(k));
+    }
+
+    /// Calls a function on every entry of the map.
     ///
-    /// # Panics
+    /// Like [`visit_keys`](Self::visit_keys), this creates no guards and allocates nothing.
     ///
-    /// If the given closure panics, then `alter_all` will abort the process
-    pub fn alter_all(&self, f: impl FnMut(&K, V) -> V) {
-        self._alter_all(f);
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map,
+    /// or if `f` modifies the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let map = DashMap::new();
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    ///
+    /// let mut total = 0;
+    /// map.visit_entries(|_, v| total += v);
+    /// assert_eq!(total, 3);
+    /// ```
+    pub fn visit_entries(&self, f: impl FnMut(&K, &V)) {
+        self._visit_entries(f);
     }
 
-    /// Scoped access into an item of the map according to a function.
+    /// Collects clones of all keys in the map into a `Vec`.
     ///
-    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    /// Each shard is read-locked only while its keys are cloned,
+    /// so the result is not a consistent snapshot if the map is modified concurrently.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
     ///
     /// # Examples
     ///
     /// ```
     /// use dashmap::DashMap;
     ///
-    /// let warehouse = DashMap::new();
-    /// warehouse.insert(4267, ("Banana", 100));
-    /// warehouse.insert(2359, ("Pear", 120));
-    /// let fruit = warehouse.view(&4267, |_k, v| *v);
-    /// assert_eq!(fruit, Some(("Banana", 100)));
+    /// let map = DashMap::new();
+    /// map.insert("hello".to_string(), 1);
+    /// assert_eq!(map.keys_cloned(), vec!["hello".to_string()]);
     /// ```
+    pub fn keys_cloned(&self) -> Vec<K>
+    where
+        K: Clone,
+    {
+        self._keys_cloned()
+    }
+
+    /// Collects copies of all keys in the map into a `Vec`.
     ///
-    /// # Panics
+    /// See [`DashMap::keys_cloned`] for the consistency guarantees.
     ///
-    /// If the given closure panics, then `view` will abort the process
-    pub fn view<Q, R>(&self, key: &Q, f: impl FnOnce(&K, &V) -> R) -> Option<R>
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let map = DashMap::new();
+    /// map.insert(7, "seven");
+    /// assert_eq!(map.keys_copied(), vec![7]);
+    /// ```
+    pub fn keys_copied(&self) -> Vec<K>
     where
-        K: Borrow<Q>,
-        Q: Hash + Eq + ?Sized,
+        K: Copy,
     {
-        self._view(key, f)
+        self._keys_cloned()
     }
 
-    /// Checks if the map contains a specific key.
+    /// Get an immutable reference to an entry in the map
     ///
     /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
     ///
@@ -872,656 +1961,4697 @@ impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher + Clone> DashMap<K, V, S> {
     /// ```
     /// use dashmap::DashMap;
     ///
-    /// let team_sizes = DashMap::new();
-    /// team_sizes.insert("Dakota Cherries", 23);
-    /// assert!(team_sizes.contains_key("Dakota Cherries"));
+    /// let youtubers = DashMap::new();
+    /// youtubers.insert("Bosnian Bill", 457000);
+    /// assert_eq!(*youtubers.get("Bosnian Bill").unwrap(), 457000);
     /// ```
-    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    pub fn get<Q>(&'a self, key: &Q) -> Option<Ref<'a, K, V>>
     where
         K: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
-        self._contains_key(key)
+        self._get(key)
     }
 
-    /// Advanced entry API that tries to mimic `std::collections::HashMap`.
-    /// See the documentation on `dashmap::mapref::entry` for more details.
+    /// Read-locks every shard of the map, returning a handle whose lookups return plain references
+    /// which stay valid while the handle lives.
     ///
-    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
-    pub fn entry(&'a self, key: K) -> Entry<'a, K, V> {
-        self._entry(key)
+    /// This mirrors the `pin()` guards of epoch-based concurrent maps, so code written against them
+    /// can use a DashMap without changing its call sites. The map can't be modified until the handle
+    /// is dropped, so it should not be held for long.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    /// Modifying the map while the handle is alive deadlocks as well.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let map = DashMap::new();
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    ///
+    /// let pinned = map.pin();
+    /// let (a, b) = (pinned.get("a").unwrap(), pinned.get("b").unwrap());
+    /// assert_eq!(a + b, 3);
+    /// ```
+    pub fn pin(&self) -> sync::Pinned<'_, K, V, S> {
+        sync::Pinned::new(self)
     }
 
-    /// Advanced entry API that tries to mimic `std::collections::HashMap`.
-    /// See the documentation on `dashmap::mapref::entry` for more details.
+    /// Write-locks every shard of the map, returning a read-only view of it which can be read
+    /// without any locking, until the guard is dropped and the map thaws.
     ///
-    /// Returns None if the shard is currently locked.
-    pub fn try_entry(&'a self, key: K) -> Option<Entry<'a, K, V>> {
-        self._try_entry(key)
+    /// Unlike [`into_read_only`](Self::into_read_only), this doesn't need ownership of the map.
+    /// Unlike [`pin`](Self::pin), no other thread can read the map while it is frozen.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    /// Accessing the map other than through the guard while it is alive deadlocks as well.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let map = DashMap::new();
+    /// map.insert("a", 1);
+    ///
+    /// let frozen = map.freeze();
+    /// assert_eq!(frozen.get("a"), Some(&1));
+    /// drop(frozen);
+    ///
+    /// map.insert("b", 2);
+    /// ```
+    pub fn freeze(&self) -> sync::FrozenGuard<'_, K, V, S> {
+        sync::FrozenGuard::new(self)
     }
 
-    /// Advanced entry API that tries to mimic `std::collections::HashMap::try_reserve`.
-    /// Tries to reserve capacity for at least `shard * additional`
-    /// and may reserve more space to avoid frequent reallocations.
+    /// Runs a batch of lookups through a [`ReadScope`], which locks every shard it reads from only once.
     ///
-    /// # Errors
+    /// The read locks are released when `f` returns.
     ///
-    /// If the capacity overflows, or the allocator reports a failure, then an error is returned.
-    // TODO: return std::collections::TryReserveError once std::collections::TryReserveErrorKind stabilises.
-    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
-        for shard in self.shards.iter() {
-            shard
-                .write()
-                .try_reserve(additional, |(k, _v)| {
-                    let mut hasher = self.hasher.build_hasher();
-                    k.hash(&mut hasher);
-                    hasher.finish()
-                })
-                .map_err(|_| TryReserveError {})?;
-        }
-        Ok(())
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    /// Modifying the map from inside `f` deadlocks as well.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let prices = DashMap::new();
+    /// prices.insert("apple", 3);
+    /// prices.insert("pear", 4);
+    ///
+    /// let total: u32 = prices.read_scope(|tx| {
+    ///     ["apple", "pear", "apple", "plum"]
+    ///         .iter()
+    ///         .filter_map(|fruit| tx.get(fruit).copied())
+    ///         .sum()
+    /// });
+    /// assert_eq!(total, 10);
+    /// ```
+    pub fn read_scope<R>(&self, f: impl FnOnce(&mut ReadScope<'_, K, V, S>) -> R) -> R {
+        f(&mut ReadScope::new(self))
     }
-}
 
-impl<'a, K: 'a + Eq + Hash, V: 'a, S: 'a + BuildHasher + Clone> DashMap<K, V, S> {
-    fn _insert(&self, key: K, value: V) -> Option<V> {
-        match self.entry(key) {
-            Entry::Occupied(mut o) => Some(o.insert(value)),
-            Entry::Vacant(v) => {
-                v.insert(value);
-                None
-            }
-        }
+    /// Get an owned copy of an entry in the map, cloned while its shard is read-locked.
+    ///
+    /// The lock is released before returning, so the snapshot can be kept for as long as needed.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let scores = DashMap::new();
+    /// scores.insert("alice".to_owned(), 12);
+    ///
+    /// let snapshot = scores.get_snapshot("alice").unwrap();
+    /// scores.insert("alice".to_owned(), 13);
+    /// assert_eq!(snapshot.pair(), (&"alice".to_owned(), &12));
+    /// ```
+    pub fn get_snapshot<Q>(&self, key: &Q) -> Option<EntrySnapshot<K, V>>
+    where
+        K: Borrow<Q> + Clone,
+        V: Clone,
+        Q: Hash + Eq + ?Sized,
+    {
+        self._get_snapshot(key)
     }
 
-    fn _remove<Q>(&self, key: &Q) -> Option<(K, V)>
+    /// Get a mutable reference to an entry in the map
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let class = DashMap::new();
+    /// class.insert("Albin", 15);
+    /// *class.get_mut("Albin").unwrap() -= 1;
+    /// assert_eq!(*class.get("Albin").unwrap(), 14);
+    /// ```
+    pub fn get_mut<Q>(&'a self, key: &Q) -> Option<RefMut<'a, K, V>>
     where
         K: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
-        let hash = self.hash_u64(&key);
+        self._get_mut(key)
+    }
 
-        let idx = self.determine_shard(hash as usize);
+    /// Get mutable references to several entries which are stored in the same shard, under a single write lock.
+    ///
+    /// Returns `None` if the keys are not all in the same shard, if any of them is missing, or if a key
+    /// is given more than once. Use [`same_shard`](Self::same_shard) or [`shard_id`](Self::shard_id) to
+    /// find keys which are co-located.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let balances: DashMap<u32, i64> = (0..64).map(|id| (id, 100)).collect();
+    /// let other = (1..64).find(|id| balances.same_shard(&0, id)).unwrap_or(0);
+    ///
+    /// if other != 0 {
+    ///     let mut accounts = balances.get_mut_same_shard(&[&0, &other]).unwrap();
+    ///     *accounts[0] -= 30;
+    ///     *accounts[1] += 30;
+    ///     drop(accounts);
+    ///
+    ///     assert_eq!(*balances.get(&other).unwrap(), 130);
+    /// }
+    /// ```
+    pub fn get_mut_same_shard<Q>(&'a self, keys: &[&Q]) -> Option<Vec<RefMutMulti<'a, K, V>>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self._get_mut_same_shard(keys)
+    }
 
-        let mut shard = self.shards[idx].write();
+    /// Get an immutable reference to an entry in the map, if the shard is not locked.
+    /// If the shard is locked, the function will return [TryResult::Locked].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    /// use dashmap::try_result::TryResult;
+    ///
+    /// let map = DashMap::new();
+    /// map.insert("Johnny", 21);
+    ///
+    /// assert_eq!(*map.try_get("Johnny").unwrap(), 21);
+    ///
+    /// let _result1_locking = map.get_mut("Johnny");
+    ///
+    /// let result2 = map.try_get("Johnny");
+    /// assert!(result2.is_locked());
+    /// ```
+    pub fn try_get<Q>(&'a self, key: &Q) -> TryResult<Ref<'a, K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self._try_get(key)
+    }
 
-        if let Ok(entry) = shard.find_entry(hash, |(k, _v)| key == k.borrow()) {
-            let ((k, v), _) = entry.remove();
-            Some((k, v))
-        } else {
-            None
-        }
+    /// Get an immutable reference to an entry in the map, if the shard is not locked.
+    /// If the shard is locked, the function will return [TryResultDetailed::WouldBlock]
+    /// with a best-effort snapshot of the current lock holders.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    /// use dashmap::try_result::TryResultDetailed;
+    ///
+    /// let map = DashMap::new();
+    /// map.insert("Johnny", 21);
+    ///
+    /// assert_eq!(*map.try_get_detailed("Johnny").unwrap(), 21);
+    ///
+    /// let _result1_locking = map.get_mut("Johnny");
+    ///
+    /// let result2 = map.try_get_detailed("Johnny");
+    /// assert!(matches!(result2, TryResultDetailed::WouldBlock { writer: true, .. }));
+    /// ```
+    pub fn try_get_detailed<Q>(&'a self, key: &Q) -> TryResultDetailed<Ref<'a, K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self._try_get_detailed(key)
+    }
+
+    /// Get a mutable reference to an entry in the map, if the shard is not locked.
+    /// If the shard is locked, the function will return [TryResultDetailed::WouldBlock]
+    /// with a best-effort snapshot of the current lock holders.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    /// use dashmap::try_result::TryResultDetailed;
+    ///
+    /// let map = DashMap::new();
+    /// map.insert("Johnny", 21);
+    ///
+    /// *map.try_get_mut_detailed("Johnny").unwrap() += 1;
+    /// assert_eq!(*map.get("Johnny").unwrap(), 22);
+    ///
+    /// let _result1_locking = map.get("Johnny");
+    ///
+    /// let result2 = map.try_get_mut_detailed("Johnny");
+    /// assert!(matches!(result2, TryResultDetailed::WouldBlock { readers: 1, writer: false }));
+    /// ```
+    pub fn try_get_mut_detailed<Q>(&'a self, key: &Q) -> TryResultDetailed<RefMut<'a, K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self._try_get_mut_detailed(key)
+    }
+
+    /// Returns a future which resolves to a reference to the entry of a key once the key is present in the map.
+    ///
+    /// The future holds no locks while it is pending and is woken by the insertion of the key.
+    /// Dropping it before it resolves is safe and unregisters its waker, so it can be used in `select!`
+    /// or with timeouts. Insertions made directly into the shards with the `raw-api` feature don't wake it.
+    ///
+    /// **Locking behaviour:** The future never blocks. If the shard is locked when it is polled,
+    /// it asks to be polled again instead. The resolved reference holds a read lock on the shard,
+    /// so drop it before awaiting anything else.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// async fn wait_for_config(map: &DashMap<&str, u32>) -> u32 {
+    ///     *map.watch_key("config").await
+    /// }
+    /// ```
+    pub fn watch_key<'q, Q>(&'a self, key: &'q Q) -> WatchKey<'a, 'q, K, V, S, Q>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        WatchKey::new(self, key)
+    }
+
+    /// Returns a reference to the value of a key, inserting the output of the future returned by `f`
+    /// if the key is vacant.
+    ///
+    /// No lock is held while the future runs. While one caller initializes a key, others calling this
+    /// method for the same key wait for its value instead of running their own initializers. If the
+    /// initializing future is dropped or panics, one of the waiting callers runs its initializer instead.
+    /// A value inserted by other means while the future runs is kept, and the produced one is dropped.
+    ///
+    /// **Locking behaviour:** The future never blocks. If the shard is locked when it is polled,
+    /// it asks to be polled again instead. The resolved reference holds a read lock on the shard,
+    /// so drop it before awaiting anything else.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// async fn fetch_user(id: u32) -> String {
+    ///     format!("user {}", id)
+    /// }
+    ///
+    /// async fn user(cache: &DashMap<u32, String>, id: u32) -> String {
+    ///     cache
+    ///         .get_or_insert_with_async(id, || fetch_user(id))
+    ///         .await
+    ///         .clone()
+    /// }
+    /// ```
+    pub async fn get_or_insert_with_async<F, Fut>(&'a self, key: K, f: F) -> Ref<'a, K, V>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = V>,
+    {
+        self._get_or_insert_with_async(key, f).await
+    }
+
+    /// Get a mutable reference to an entry in the map, if the shard is not locked.
+    /// If the shard is locked, the function will return [TryResult::Locked].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    /// use dashmap::try_result::TryResult;
+    ///
+    /// let map = DashMap::new();
+    /// map.insert("Johnny", 21);
+    ///
+    /// *map.try_get_mut("Johnny").unwrap() += 1;
+    /// assert_eq!(*map.get("Johnny").unwrap(), 22);
+    ///
+    /// let _result1_locking = map.get("Johnny");
+    ///
+    /// let result2 = map.try_get_mut("Johnny");
+    /// assert!(result2.is_locked());
+    /// ```
+    pub fn try_get_mut<Q>(&'a self, key: &Q) -> TryResult<RefMut<'a, K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self._try_get_mut(key)
     }
 
-    fn _remove_if<Q>(&self, key: &Q, f: impl FnOnce(&K, &V) -> bool) -> Option<(K, V)>
-    where
-        K: Borrow<Q>,
-        Q: Hash + Eq + ?Sized,
-    {
-        let hash = self.hash_u64(&key);
+    /// Remove excess capacity to reduce memory usage.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    /// use dashmap::try_result::TryResult;
+    ///
+    /// let map = DashMap::new();
+    /// map.insert("Johnny", 21);
+    /// assert!(map.capacity() > 0);
+    /// map.remove("Johnny");
+    /// map.shrink_to_fit();
+    /// assert_eq!(map.capacity(), 0);
+    /// ```
+    pub fn shrink_to_fit(&self) {
+        self._shrink_to_fit();
+    }
+
+    /// Retain elements that whose predicates return true
+    /// and discard elements whose predicates return false.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let people = DashMap::new();
+    /// people.insert("Albin", 15);
+    /// people.insert("Jones", 22);
+    /// people.insert("Charlie", 27);
+    /// people.retain(|_, v| *v > 20);
+    /// assert_eq!(people.len(), 2);
+    /// ```
+    pub fn retain(&self, f: impl FnMut(&K, &mut V) -> bool) {
+        self._retain(f);
+    }
+
+    /// Like [`retain`](Self::retain), but reports how many entries were examined and removed
+    /// and how long it took, for logging the work of cleanup jobs.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let sessions: DashMap<u32, u32> = (0..100).map(|i| (i, i)).collect();
+    /// let stats = sessions.retain_counted(|_, age| *age < 30);
+    /// assert_eq!((stats.examined(), stats.removed(), stats.kept()), (100, 70, 30));
+    /// ```
+    pub fn retain_counted(&self, f: impl FnMut(&K, &mut V) -> bool) -> RetainStats {
+        let start = Instant::now();
+        let (examined, removed) = self._retain(f);
+
+        RetainStats {
+            examined,
+            removed,
+            duration: start.elapsed(),
+        }
+    }
+
+    /// Like [`retain`](Self::retain), but for running inside an async task: the work is split into
+    /// time slices of about `slice`, and the future yields to the executor between them.
+    ///
+    /// Like [`watch_key`](Self::watch_key) and [`get_or_insert_with_async`](Self::get_or_insert_with_async),
+    /// this isn't behind a feature: yielding only returns `Pending` after waking the task, so it
+    /// works on any executor without depending on a runtime.
+    ///
+    /// The budget is only checked between shards: every shard is retained completely under one write
+    /// lock, since the shard can change while it is unlocked and there is no position to resume from
+    /// which would still call the predicate exactly once for each entry. A slice therefore ends after
+    /// the first shard which takes it over `slice`, and a single large shard runs past `slice`
+    /// without yielding. Create maps which hold millions of entries with more shards
+    /// ([`with_shard_amount`](Self::with_shard_amount)) to shorten the slices. Entries inserted
+    /// into a shard after it was processed are kept without being examined.
+    ///
+    /// **Locking behaviour:** The future never blocks. If a shard is locked when it is polled,
+    /// it yields and tries again when it is polled the next time, which the executor does right away.
+    /// Holding a reference into the map for long therefore keeps the task busy; every retry counts
+    /// as a [slice](SliceProgress::slices). No lock is held while it is suspended.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    /// use std::time::Duration;
+    ///
+    /// async fn evict_stale(sessions: &DashMap<u32, u64>, now: u64) {
+    ///     let progress = sessions
+    ///         .retain_async(Duration::from_millis(1), |_, last_seen| now - *last_seen < 60)
+    ///         .await;
+    ///     println!("evicted {} sessions", progress.removed());
+    /// }
+    /// ```
+    pub async fn retain_async(
+        &self,
+        slice: Duration,
+        f: impl FnMut(&K, &mut V) -> bool,
+    ) -> SliceProgress {
+        self._retain_async(slice, f).await
+    }
+
+    /// Retain only the entries whose keys are in a set, removing everything else.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map,
+    /// or a mutable reference into the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::{DashMap, DashSet};
+    ///
+    /// let people = DashMap::new();
+    /// people.insert("Albin", 15);
+    /// people.insert("Jones", 22);
+    ///
+    /// let active = DashSet::new();
+    /// active.insert("Jones");
+    ///
+    /// people.retain_keys(&active);
+    /// assert!(people.contains_key("Jones"));
+    /// assert!(!people.contains_key("Albin"));
+    /// ```
+    pub fn retain_keys<S2: BuildHasher>(&self, keys: &DashSet<K, S2>) {
+        self._retain(|k, _| keys.contains(k));
+    }
+
+    /// Removes the entries of several keys, returning how many were present.
+    ///
+    /// The keys are grouped by shard first, so that every shard is locked only once.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let people = DashMap::new();
+    /// people.insert("Albin", 15);
+    /// people.insert("Jones", 22);
+    /// people.insert("Charlie", 27);
+    ///
+    /// assert_eq!(people.remove_keys(["Albin", "Charlie", "Dave"].iter()), 2);
+    /// assert_eq!(people.len(), 1);
+    /// ```
+    pub fn remove_keys<'q, Q>(&self, keys: impl IntoIterator<Item = &'q Q>) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized + 'q,
+    {
+        self._remove_keys(keys)
+    }
+
+    /// Applies updates to the values of existing keys, returning for every update whether its key was present.
+    ///
+    /// The updates are grouped by shard first, so that every shard is write-locked only once,
+    /// and the updates of one shard are applied in the order they were given. Keys which are
+    /// not present are not inserted. The updates of different shards are not applied atomically.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let stock = DashMap::new();
+    /// stock.insert("apples", 3);
+    /// stock.insert("pears", 5);
+    ///
+    /// let updated = stock.update_batch(vec![
+    ///     ("apples", Box::new(|v: &mut i32| *v -= 1) as Box<dyn FnOnce(&mut i32)>),
+    ///     ("plums", Box::new(|v: &mut i32| *v -= 1)),
+    ///     ("pears", Box::new(|v: &mut i32| *v = 0)),
+    /// ]);
+    /// assert_eq!(updated, [true, false, true]);
+    /// assert_eq!(*stock.get("apples").unwrap(), 2);
+    /// assert_eq!(*stock.get("pears").unwrap(), 0);
+    /// ```
+    pub fn update_batch<F: FnOnce(&mut V)>(
+        &self,
+        updates: impl IntoIterator<Item = (K, F)>,
+    ) -> Vec<bool> {
+        self._update_batch(updates)
+    }
+
+    /// Fetches the total number of key-value pairs stored in the map.
+    ///
+    /// Every shard keeps a counter of its entries which is updated under its write lock,
+    /// so this only sums the counters. While the map is modified concurrently, the result
+    /// may combine counters from slightly different moments.
+    ///
+    /// **Locking behaviour:** Takes no locks, so it never deadlocks.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let people = DashMap::new();
+    /// people.insert("Albin", 15);
+    /// people.insert("Jones", 22);
+    /// people.insert("Charlie", 27);
+    /// assert_eq!(people.len(), 3);
+    /// ```
+    pub fn len(&self) -> usize {
+        self._len()
+    }
+
+    /// Counts the key-value pairs stored in the map by locking every shard,
+    /// which is what [`len`](Self::len) did before it read the shard counters.
+    ///
+    /// Also corrects the counters if the shards were modified directly through the `raw-api`.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let people = DashMap::new();
+    /// people.insert("Albin", 15);
+    /// people.insert("Jones", 22);
+    /// assert_eq!(people.len_exact(), 2);
+    /// ```
+    pub fn len_exact(&self) -> usize {
+        self._len_exact()
+    }
+
+    /// Returns a copy of the latency histograms recorded for every shard.
+    ///
+    /// The histograms are updated concurrently, so the copy of a shard may be slightly inconsistent
+    /// with itself, and samples are only counted once the timed operation has finished.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let map = DashMap::new();
+    /// map.insert("Albin", 15);
+    /// map.get("Albin");
+    ///
+    /// let total = map.metrics_snapshot().total();
+    /// assert_eq!(total.insert.count(), 1);
+    /// assert_eq!(total.get.count(), 1);
+    /// assert!(total.get.quantile(0.99).is_some());
+    /// ```
+    #[cfg(feature = "metrics")]
+    pub fn metrics_snapshot(&self) -> MetricsSnapshot {
+        self.metrics.snapshot()
+    }
+
+    /// Checks if the map is empty or not.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let map = DashMap::<(), ()>::new();
+    /// assert!(map.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self._is_empty()
+    }
+
+    /// Removes all key-value pairs in the map.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let stats = DashMap::new();
+    /// stats.insert("Goals", 4);
+    /// assert!(!stats.is_empty());
+    /// stats.clear();
+    /// assert!(stats.is_empty());
+    /// ```
+    pub fn clear(&self) {
+        self._clear();
+    }
+
+    /// Removes up to `chunk` key-value pairs from the map, returning how many were removed
+    /// and how many remain.
+    ///
+    /// Unlike [`clear`](Self::clear), this can be called repeatedly to clear a large map
+    /// in steps, so that other threads don't have to wait for the whole map to be cleared.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let map: DashMap<u32, u32> = (0..100).map(|i| (i, i)).collect();
+    /// let progress = map.clear_incremental(60);
+    /// assert_eq!(progress.cleared(), 60);
+    /// assert_eq!(progress.remaining(), 40);
+    /// while !map.clear_incremental(60).is_done() {}
+    /// assert!(map.is_empty());
+    /// ```
+    pub fn clear_incremental(&self, chunk: usize) -> ClearProgress {
+        self._clear_incremental(chunk)
+    }
+
+    /// Removes all key-value pairs from the shards which are not currently locked.
+    /// Returns the number of shards which were skipped because they were locked.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let map = DashMap::new();
+    /// map.insert("Johnny", 21);
+    /// map.insert("Jimmy", 22);
+    /// {
+    ///     let _guard = map.get("Johnny");
+    ///     assert_eq!(map.try_clear(), 1);
+    ///     assert!(map.contains_key("Johnny"));
+    /// }
+    /// assert_eq!(map.try_clear(), 0);
+    /// assert!(map.is_empty());
+    /// ```
+    pub fn try_clear(&self) -> usize {
+        self._try_clear()
+    }
+
+    /// Removes all key-value pairs from the map from inside an async task, yielding to the executor
+    /// every time it has worked for about `slice`.
+    ///
+    /// Unlike [`retain_async`](Self::retain_async), this also splits the work within a shard. Like it,
+    /// this works on any executor and isn't behind a feature. Entries inserted into a shard after it
+    /// was cleared are left in the map.
+    ///
+    /// **Locking behaviour:** The future never blocks. If a shard is locked when it is polled,
+    /// it yields and tries again when it is polled the next time, which the executor does right away.
+    /// Holding a reference into the map for long therefore keeps the task busy; every retry counts
+    /// as a [slice](SliceProgress::slices). No lock is held while it is suspended.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    /// use std::time::Duration;
+    ///
+    /// async fn reset(cache: &DashMap<u64, Vec<u8>>) {
+    ///     let progress = cache.clear_async(Duration::from_millis(1)).await;
+    ///     println!("cleared {} entries in {} slices", progress.removed(), progress.slices());
+    /// }
+    /// ```
+    pub async fn clear_async(&self, slice: Duration) -> SliceProgress {
+        self._clear_async(slice).await
+    }
+
+    /// Returns how many key-value pairs the map can store without reallocating.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    pub fn capacity(&self) -> usize {
+        self._capacity()
+    }
+
+    /// Applies a batch of operations, returning the result of each operation in order.
+    ///
+    /// The operations are grouped by shard, and each group is applied under a single write lock.
+    /// Operations on the same key are applied in the order they appear in the batch,
+    /// but the batch as a whole is not atomic: other threads may observe some shards
+    /// before and others after the batch was applied.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::batch::{Op, OpResult};
+    /// use dashmap::DashMap;
+    ///
+    /// let map = DashMap::new();
+    /// map.insert("Goals", 4);
+    /// let results = map.apply_batch(vec![
+    ///     Op::Insert("Assists", 2),
+    ///     Op::Alter("Goals", Box::new(|_, v| v * 2)),
+    ///     Op::Remove("Assists"),
+    /// ]);
+    /// assert_eq!(
+    ///     results,
+    ///     [OpResult::Inserted(None), OpResult::Altered(true), OpResult::Removed(Some(("Assists", 2)))]
+    /// );
+    /// assert_eq!(*map.get("Goals").unwrap(), 8);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If an [`Op::Alter`] closure panics, then `apply_batch` will abort the process
+    pub fn apply_batch(&self, ops: Vec<Op<K, V>>) -> Vec<OpResult<K, V>> {
+        self._apply_batch(ops)
+    }
+
+    /// Modify a specific value according to a function.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let stats = DashMap::new();
+    /// stats.insert("Goals", 4);
+    /// stats.alter("Goals", |_, v| v * 2);
+    /// assert_eq!(*stats.get("Goals").unwrap(), 8);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If the given closure panics, then `alter` will abort the process
+    pub fn alter<Q>(&self, key: &Q, f: impl FnOnce(&K, V) -> V)
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self._alter(key, f);
+    }
+
+    /// Like [`alter`](Self::alter), but doesn't wait if the shard is locked.
+    ///
+    /// Returns [`TryResult::Present`] if the value was modified, [`TryResult::Absent`] if the key is missing
+    /// and [`TryResult::Locked`] if the shard is locked.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let stats = DashMap::new();
+    /// stats.insert("Goals", 4);
+    /// assert!(stats.try_alter("Goals", |_, v| v * 2).is_present());
+    /// assert!(stats.try_alter("Assists", |_, v| v * 2).is_absent());
+    /// assert_eq!(*stats.get("Goals").unwrap(), 8);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If the given closure panics, then `try_alter` will abort the process
+    pub fn try_alter<Q>(&self, key: &Q, f: impl FnOnce(&K, V) -> V) -> TryResult<()>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self._try_alter(key, f)
+    }
+
+    /// Fetches the value of a key and applies a function to it that returns an optional new value,
+    /// in the style of [`AtomicUsize::fetch_update`](core::sync::atomic::AtomicUsize::fetch_update).
+    ///
+    /// Returns `None` if the key is not present. Otherwise returns `Ok(previous_value)` if the function
+    /// returned `Some(_)` and the value was updated, else `Err(previous_value)`.
+    /// The whole operation happens under a single write lock, so the function is called exactly once.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let tokens = DashMap::new();
+    /// tokens.insert("bucket", 1u32);
+    /// assert_eq!(tokens.fetch_update("bucket", |t| t.checked_sub(1)), Some(Ok(1)));
+    /// assert_eq!(tokens.fetch_update("bucket", |t| t.checked_sub(1)), Some(Err(0)));
+    /// assert_eq!(tokens.fetch_update("missing", |t| t.checked_sub(1)), None);
+    /// ```
+    pub fn fetch_update<Q>(&self, key: &Q, f: impl FnOnce(V) -> Option<V>) -> Option<Result<V, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        V: Copy,
+    {
+        self._fetch_update(key, f)
+    }
+
+    /// Modify every value in the map according to a function.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let stats = DashMap::new();
+    /// stats.insert("Wins", 4);
+    /// stats.insert("Losses", 2);
+    /// stats.alter_all(|_, v| v + 1);
+    /// assert_eq!(*stats.get("Wins").unwrap(), 5);
+    /// assert_eq!(*stats.get("Losses").unwrap(), 3);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If the given closure panics, then `alter_all` will abort the process
+    pub fn alter_all(&self, f: impl FnMut(&K, V) -> V) {
+        self._alter_all(f);
+    }
+
+    /// Modify every value in the map according to a function, releasing the shard locks
+    /// after every `chunk_size` entries so that readers and writers of the same shard can interleave.
+    ///
+    /// Every entry present for the whole call is altered exactly once. Entries inserted while
+    /// the map is altered may or may not be altered, and entries removed in the meantime are skipped.
+    /// A `chunk_size` of 0 is treated as 1.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let stats = DashMap::new();
+    /// stats.insert("Wins", 4);
+    /// stats.insert("Losses", 2);
+    /// stats.alter_all_chunked(1, |_, v| v + 1);
+    /// assert_eq!(*stats.get("Wins").unwrap(), 5);
+    /// assert_eq!(*stats.get("Losses").unwrap(), 3);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If the given closure panics, then `alter_all_chunked` will abort the process
+    pub fn alter_all_chunked(&self, chunk_size: usize, f: impl FnMut(&K, V) -> V) {
+        self._alter_all_chunked(chunk_size, f);
+    }
+
+    /// Modify every value in the map according to a function, with the given [`Priority`]
+    /// towards other users of the map.
+    ///
+    /// With [`Priority::Background`], shards which are locked when they are reached are skipped and
+    /// altered after all other shards, retrying them with an increasing backoff. A shard which stays
+    /// contended through every retry is eventually waited for, so the call always completes.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::{DashMap, Priority};
+    ///
+    /// let stats = DashMap::new();
+    /// stats.insert("Wins", 4);
+    /// stats.insert("Losses", 2);
+    /// stats.alter_all_with_priority(|_, v| v + 1, Priority::Background);
+    /// assert_eq!(*stats.get("Wins").unwrap(), 5);
+    /// assert_eq!(*stats.get("Losses").unwrap(), 3);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If the given closure panics, then `alter_all_with_priority` will abort the process
+    pub fn alter_all_with_priority(&self, f: impl FnMut(&K, V) -> V, priority: Priority) {
+        match priority {
+            Priority::Foreground => self._alter_all(f),
+            Priority::Background => self._alter_all_background(f),
+        }
+    }
+
+    /// Scoped access into an item of the map according to a function.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let warehouse = DashMap::new();
+    /// warehouse.insert(4267, ("Banana", 100));
+    /// warehouse.insert(2359, ("Pear", 120));
+    /// let fruit = warehouse.view(&4267, |_k, v| *v);
+    /// assert_eq!(fruit, Some(("Banana", 100)));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If the given closure panics, then `view` will abort the process
+    pub fn view<Q, R>(&self, key: &Q, f: impl FnOnce(&K, &V) -> R) -> Option<R>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self._view(key, f)
+    }
+
+    /// Scoped access into an item of the map according to a function, returning `default`
+    /// if the key is not in the map.
+    ///
+    /// The shard is only locked while the function runs, so no guard can be held by mistake.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let warehouse = DashMap::new();
+    /// warehouse.insert(4267, ("Banana", 100));
+    /// assert_eq!(warehouse.view_or(&4267, 0, |_k, v| v.1), 100);
+    /// assert_eq!(warehouse.view_or(&2359, 0, |_k, v| v.1), 0);
+    /// ```
+    pub fn view_or<Q, R>(&self, key: &Q, default: R, f: impl FnOnce(&K, &V) -> R) -> R
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self._view(key, f).unwrap_or(default)
+    }
+
+    /// Scoped mutable access into an item of the map according to a function,
+    /// inserting the result of `init` first if the key is not in the map.
+    ///
+    /// The shard is only locked while the function runs, so no guard can be held by mistake.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let counts = DashMap::new();
+    /// let count = counts.view_mut_or_insert_with("hits", || 0, |_k, v| {
+    ///     *v += 1;
+    ///     *v
+    /// });
+    /// assert_eq!(count, 1);
+    /// ```
+    pub fn view_mut_or_insert_with<R>(
+        &self,
+        key: K,
+        init: impl FnOnce() -> V,
+        f: impl FnOnce(&K, &mut V) -> R,
+    ) -> R {
+        self._view_mut_or_insert_with(key, init, f)
+    }
+
+    /// Checks if the map contains a specific key.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let team_sizes = DashMap::new();
+    /// team_sizes.insert("Dakota Cherries", 23);
+    /// assert!(team_sizes.contains_key("Dakota Cherries"));
+    /// ```
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self._contains_key(key)
+    }
+
+    /// Advanced entry API that tries to mimic `std::collections::HashMap`.
+    /// See the documentation on `dashmap::mapref::entry` for more details.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    pub fn entry(&'a self, key: K) -> Entry<'a, K, V> {
+        self._entry(key)
+    }
+
+    /// Returns an entry which owns a clone of the map's `Arc` and the key instead of borrowing them,
+    /// so it can be moved into a spawned task. See [`OwnedEntry`] for how it locks the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    /// use std::sync::Arc;
+    /// use std::thread;
+    ///
+    /// let cache: Arc<DashMap<u32, String>> = Arc::new(DashMap::new());
+    ///
+    /// let entry = cache.entry_owned(7);
+    /// thread::spawn(move || {
+    ///     let value = format!("user {}", entry.key());
+    ///     entry.or_insert(value);
+    /// })
+    /// .join()
+    /// .unwrap();
+    ///
+    /// assert_eq!(*cache.get(&7).unwrap(), "user 7");
+    /// ```
+    pub fn entry_owned(self: &Arc<Self>, key: K) -> OwnedEntry<K, V, S> {
+        OwnedEntry::new(Arc::clone(self), key)
+    }
+
+    /// Like [`entry`](Self::entry), but borrows the key and only clones it
+    /// if a value is inserted into a vacant entry.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let counts: DashMap<String, u32> = DashMap::new();
+    /// let word = "hello".to_owned();
+    ///
+    /// *counts.entry_cloned(&word).or_insert(0) += 1;
+    /// // The key is already present, so it is not cloned again.
+    /// *counts.entry_cloned(&word).or_insert(0) += 1;
+    /// assert_eq!(*counts.get("hello").unwrap(), 2);
+    /// ```
+    pub fn entry_cloned<'q>(&'a self, key: &'q K) -> EntryRef<'a, 'q, K, V>
+    where
+        K: Clone,
+    {
+        self._entry_cloned(key)
+    }
+
+    /// Advanced entry API that tries to mimic `std::collections::HashMap`.
+    /// See the documentation on `dashmap::mapref::entry` for more details.
+    ///
+    /// Returns None if the shard is currently locked.
+    pub fn try_entry(&'a self, key: K) -> Option<Entry<'a, K, V>> {
+        self._try_entry(key)
+    }
+
+    /// Like [`entry`](Self::entry), but a vacant entry is only returned if `gate` allows it.
+    ///
+    /// If the key is absent, `gate` is called with the current lengths of the map and of the key's
+    /// shard while the shard is locked, so no other thread can insert into that shard in between.
+    /// Returns `None` if `gate` returns `false`, without reserving any room for the key.
+    /// An occupied entry is always returned, without calling `gate`.
+    ///
+    /// Other shards are not locked while `gate` runs, so a map bounded through [`EntryGateInfo::len`]
+    /// may briefly exceed its bound by up to one entry per concurrently inserting thread;
+    /// [`EntryGateInfo::shard_len`] is exact.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map,
+    /// or if `gate` accesses the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let sessions = DashMap::new();
+    /// let admit = |info: dashmap::EntryGateInfo| info.len() < 2;
+    ///
+    /// sessions.entry_if("alice", admit).unwrap().or_insert(1);
+    /// sessions.entry_if("bob", admit).unwrap().or_insert(2);
+    /// assert!(sessions.entry_if("carol", admit).is_none());
+    ///
+    /// // Existing keys can still be updated when the map is full.
+    /// *sessions.entry_if("alice", admit).unwrap().or_insert(0) += 10;
+    /// assert_eq!(*sessions.get("alice").unwrap(), 11);
+    /// assert_eq!(sessions.len(), 2);
+    /// ```
+    pub fn entry_if(
+        &'a self,
+        key: K,
+        gate: impl FnOnce(EntryGateInfo) -> bool,
+    ) -> Option<Entry<'a, K, V>> {
+        self._entry_if(key, gate)
+    }
+
+    /// Like [`entry_cloned`](Self::entry_cloned), but doesn't wait if the shard is locked.
+    ///
+    /// Returns None if the shard is currently locked.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let counts: DashMap<String, u32> = DashMap::new();
+    /// let word = "hello".to_owned();
+    ///
+    /// *counts.try_entry_cloned(&word).unwrap().or_insert(0) += 1;
+    ///
+    /// let reading = counts.get("hello");
+    /// assert!(counts.try_entry_cloned(&word).is_none());
+    /// drop(reading);
+    /// ```
+    pub fn try_entry_cloned<'q>(&'a self, key: &'q K) -> Option<EntryRef<'a, 'q, K, V>>
+    where
+        K: Clone,
+    {
+        self._try_entry_cloned(key)
+    }
+
+    /// Inserts the value produced by `on_insert` if the key is vacant,
+    /// otherwise modifies the existing value with `on_update`.
+    /// Returns a mutable reference to the value now stored in the map.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let hits = DashMap::new();
+    /// hits.upsert("/index", || 1, |_, v| *v += 1);
+    /// let count = hits.upsert("/index", || 1, |_, v| *v += 1);
+    /// assert_eq!(*count, 2);
+    /// ```
+    pub fn upsert(
+        &'a self,
+        key: K,
+        on_insert: impl FnOnce() -> V,
+        on_update: impl FnOnce(&K, &mut V),
+    ) -> RefMut<'a, K, V> {
+        self._upsert(key, on_insert, on_update)
+    }
+
+    /// Returns a mutable reference to the value of the key, inserting the result of `f` if it is vacant.
+    /// The flag is `true` if a new value was inserted.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let sessions = DashMap::new();
+    /// let (_, created) = sessions.get_or_insert_with_flag("alice", Vec::new);
+    /// assert!(created);
+    /// let (mut session, created) = sessions.get_or_insert_with_flag("alice", Vec::new);
+    /// assert!(!created);
+    /// session.push("login");
+    /// ```
+    pub fn get_or_insert_with_flag(
+        &'a self,
+        key: K,
+        f: impl FnOnce() -> V,
+    ) -> (RefMut<'a, K, V>, bool) {
+        self._get_or_insert_with_flag(key, f)
+    }
+
+    /// Advanced entry API that tries to mimic `std::collections::HashMap::try_reserve`.
+    /// Tries to reserve capacity for at least `shard * additional`
+    /// and may reserve more space to avoid frequent reallocations.
+    ///
+    /// # Errors
+    ///
+    /// If the capacity overflows, or the allocator reports a failure, then an error is returned.
+    // TODO: return std::collections::TryReserveError once std::collections::TryReserveErrorKind stabilises.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        for shard in self.shards.iter() {
+            let mut shard = shard.write();
+            let before = shard.allocation_size();
+            let result = shard.try_reserve(additional, |(k, _v)| {
+                let mut hasher = self.hasher.build_hasher();
+                k.hash(&mut hasher);
+                hasher.finish()
+            });
+            tracker::resized(self.tracker(), before, shard.allocation_size());
+            result?;
+        }
+        Ok(())
+    }
+}
+
+impl<K: Eq + Hash, S: BuildHasher> DashMap<K, (), S> {
+    /// Converts the map into a [`DashSet`] of its keys, without moving the entries.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let map = DashMap::new();
+    /// map.insert("Johnny", ());
+    /// let set = map.into_set();
+    /// assert!(set.contains("Johnny"));
+    /// ```
+    pub fn into_set(self) -> DashSet<K, S> {
+        DashSet { inner: self }
+    }
+}
+
+impl<K: Eq + Hash + Ord, V, S: BuildHasher> DashMap<K, V, S> {
+    /// Consumes the map, returning its entries sorted by key.
+    ///
+    /// The shards are sorted one at a time and then merged, which is faster than sorting
+    /// all the entries at once and frees every shard as soon as it was moved out.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let map = DashMap::new();
+    /// map.insert(3, "c");
+    /// map.insert(1, "a");
+    /// map.insert(2, "b");
+    ///
+    /// assert_eq!(map.into_sorted_vec(), [(1, "a"), (2, "b"), (3, "c")]);
+    /// ```
+    pub fn into_sorted_vec(self) -> Vec<(K, V)> {
+        let runs = self
+            .shards
+            .into_vec()
+            .into_iter()
+            .map(|shard| {
+                let mut entries: Vec<_> = shard.into_inner().into_inner().into_iter().collect();
+                entries.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+                entries
+            })
+            .collect();
+
+        util::merge_sorted_runs(runs)
+    }
+
+    /// Creates a map from entries sorted by key, with the default shard amount and the provided hasher.
+    ///
+    /// Every shard is allocated up front for its expected share of the entries, based on the
+    /// iterator's size hint, so bulk-loading a large map doesn't rehash the shards repeatedly.
+    /// Since the keys are sorted, duplicates are adjacent and are detected by comparing each key
+    /// with the previous one, and entries are inserted without probing for an equal key. Of
+    /// several entries with the same key, the last one is kept, like when collecting.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    /// use std::collections::hash_map::RandomState;
+    ///
+    /// let rows = (0..1000).map(|id| (id, id * 2));
+    /// let map = DashMap::from_sorted_iter(rows, RandomState::new());
+    /// assert_eq!(map.len(), 1000);
+    /// assert_eq!(*map.get(&21).unwrap(), 42);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the keys are not sorted in ascending order.
+    pub fn from_sorted_iter<I: IntoIterator<Item = (K, V)>>(iter: I, hasher: S) -> Self {
+        let iter = iter.into_iter();
+        let (lower, upper) = iter.size_hint();
+        let mut map = Self::with_hasher_and_shard_amount(hasher, default_shard_amount());
+
+        // Leave room for the deviation of a shard's share from the average, which grows with
+        // its square root, so that the fullest shards don't have to grow either.
+        let share = upper.unwrap_or(lower) / map.shards.len();
+        let share = share + 4 * (share as f64).sqrt() as usize;
+
+        let hasher = &map.hasher;
+        for shard in map.shards.iter_mut() {
+            shard.get_mut().reserve(share, |(k, _v)| {
+                let mut hasher = hasher.build_hasher();
+                k.hash(&mut hasher);
+                hasher.finish()
+            });
+        }
+
+        let mut pending: Option<(K, V)> = None;
+
+        for (key, value) in iter {
+            if let Some((last, _)) = &pending {
+                match last.cmp(&key) {
+                    cmp::Ordering::Less => {}
+                    cmp::Ordering::Equal => {
+                        pending = Some((key, value));
+                        continue;
+                    }
+                    cmp::Ordering::Greater => panic!("from_sorted_iter: keys are not sorted"),
+                }
+            }
+
+            if let Some((key, value)) = pending.replace((key, value)) {
+                map.insert_unique_exclusive(key, value);
+            }
+        }
+
+        if let Some((key, value)) = pending {
+            map.insert_unique_exclusive(key, value);
+        }
+
+        for (len, shard) in map.lens.iter_mut().zip(map.shards.iter_mut()) {
+            *len.get_mut() = shard.get_mut().len();
+        }
+
+        map
+    }
+
+    /// Inserts an entry whose key is known not to be in the map yet, without locking.
+    fn insert_unique_exclusive(&mut self, key: K, value: V) {
+        let hash = self.hash_u64(&key);
+        let idx = self.determine_shard(hash as usize);
+        let hasher = &self.hasher;
+
+        self.shards[idx]
+            .get_mut()
+            .insert_unique(hash, (key, value), |(k, _v)| {
+                let mut hasher = hasher.build_hasher();
+                k.hash(&mut hasher);
+                hasher.finish()
+            });
+    }
+
+    /// Consumes the map, moving its entries into a [`BTreeMap`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let map = DashMap::new();
+    /// map.insert("b", 2);
+    /// map.insert("a", 1);
+    ///
+    /// let tree = map.into_btreemap();
+    /// assert_eq!(tree.keys().collect::<Vec<_>>(), [&"a", &"b"]);
+    /// ```
+    pub fn into_btreemap(self) -> BTreeMap<K, V> {
+        // Collecting an already sorted sequence builds the tree in bulk.
+        self.into_sorted_vec().into_iter().collect()
+    }
+}
+
+impl<K: Eq + Hash, V> DashMap<K, V, IdentityState> {
+    /// Creates a new DashMap with a capacity of 0, hashing its integer keys with [`IdentityState`].
+    ///
+    /// Small integer keys then skip the cost of a full hash function while still being spread
+    /// over all shards. Only use this for keys which are roughly uniformly distributed, such as
+    /// ids from a counter, and never for keys controlled by an attacker.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let users = DashMap::with_integer_keys();
+    /// users.insert(1u64, "alice");
+    /// users.insert(2u64, "bob");
+    /// assert_eq!(*users.get(&2).unwrap(), "bob");
+    /// ```
+    pub fn with_integer_keys() -> Self {
+        Self::with_integer_keys_and_shard_amount(default_shard_amount())
+    }
+
+    /// Creates a new DashMap with a capacity of 0 and the specified shard amount,
+    /// hashing its integer keys with [`IdentityState`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shard_amount` is not greater than 1 and a power of two.
+    pub fn with_integer_keys_and_shard_amount(shard_amount: usize) -> Self {
+        Self::with_hasher_and_shard_amount(IdentityState::new(shard_amount), shard_amount)
+    }
+}
+
+impl<K: ShardAffinity, V> DashMap<K, V, AffinityState> {
+    /// Creates a new DashMap with a capacity of 0, storing keys with the same
+    /// [`shard_hint`](ShardAffinity::shard_hint) in the same shard.
+    ///
+    /// # Examples
+    ///
+    /// See [`ShardAffinity`].
+    pub fn with_shard_affinity() -> Self {
+        Self::with_shard_affinity_and_shard_amount(default_shard_amount())
+    }
+
+    /// Creates a new DashMap with a capacity of 0 and the specified shard amount,
+    /// storing keys with the same [`shard_hint`](ShardAffinity::shard_hint) in the same shard.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shard_amount` is not greater than 1 and a power of two.
+    pub fn with_shard_affinity_and_shard_amount(shard_amount: usize) -> Self {
+        Self::with_hasher_and_shard_amount(AffinityState::new(shard_amount), shard_amount)
+    }
+}
+
+impl<K: Eq + Hash, V> DashMap<K, V, SeededState> {
+    /// Creates a new DashMap with a capacity of 0, hashing with an explicit seed.
+    ///
+    /// Maps created with the same seed and shard amount place every key in the same shard,
+    /// see [`SeededState`]. The default shard amount depends on the number of CPUs, so processes
+    /// which have to agree on shards should use [`with_hasher_seeded_and_shard_amount`](Self::with_hasher_seeded_and_shard_amount).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let map = DashMap::with_hasher_seeded(7);
+    /// map.insert("alice", 1);
+    /// assert_eq!(map.hasher_seed(), 7);
+    /// ```
+    pub fn with_hasher_seeded(seed: u128) -> Self {
+        Self::with_hasher(SeededState::new(seed))
+    }
+
+    /// Creates a new DashMap with a capacity of 0 and the specified shard amount, hashing with an explicit seed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shard_amount` is not greater than 1 and a power of two.
+    pub fn with_hasher_seeded_and_shard_amount(seed: u128, shard_amount: usize) -> Self {
+        Self::with_hasher_and_shard_amount(SeededState::new(seed), shard_amount)
+    }
+
+    /// Returns the seed the map hashes with.
+    pub fn hasher_seed(&self) -> u128 {
+        self.hasher.seed()
+    }
+}
+
+impl<K: Eq + Hash, V> DashMap<K, Arc<V>, RandomState> {
+    /// Creates a new DashMap storing its values behind an `Arc`, with a capacity of 0.
+    ///
+    /// Shared values can be read with [`get_shared`](Self::get_shared) and [`iter_shared`](Self::iter_shared),
+    /// which hand out clones of the `Arc` instead of references, so long reads don't hold any locks.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    /// use std::sync::Arc;
+    ///
+    /// let documents = DashMap::with_arc_values();
+    /// documents.insert("readme", Arc::new(String::from("# DashMap")));
+    /// ```
+    pub fn with_arc_values() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher> DashMap<K, Arc<V>, S> {
+    /// Get a clone of the shared value corresponding to a key.
+    ///
+    /// Unlike [`get`](Self::get), the returned value keeps no lock on the map,
+    /// so it can be held for as long as needed, even while the entry is replaced or removed.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    /// use std::sync::Arc;
+    ///
+    /// let documents = DashMap::with_arc_values();
+    /// documents.insert("readme", Arc::new(String::from("# DashMap")));
+    ///
+    /// let readme = documents.get_shared("readme").unwrap();
+    /// documents.remove("readme");
+    /// assert_eq!(*readme, "# DashMap");
+    /// ```
+    pub fn get_shared<Q>(&self, key: &Q) -> Option<Arc<V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get(key).map(|r| Arc::clone(r.value()))
+    }
+
+    /// Creates an iterator over clones of the keys and shared values of the map.
+    ///
+    /// **Locking behaviour:** Each shard is read-locked only while its entries are cloned,
+    /// so the items can be held and the map modified while iterating. May deadlock if called
+    /// or advanced when holding a mutable reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    /// use std::sync::Arc;
+    ///
+    /// let documents = DashMap::with_arc_values();
+    /// documents.insert("readme", Arc::new(String::from("# DashMap")));
+    ///
+    /// for (name, document) in documents.iter_shared() {
+    ///     documents.insert(name, Arc::new(document.to_uppercase()));
+    /// }
+    /// assert_eq!(*documents.get_shared("readme").unwrap(), "# DASHMAP");
+    /// ```
+    pub fn iter_shared(&self) -> SharedIter<'_, K, V>
+    where
+        K: Clone,
+    {
+        SharedIter::new(self)
+    }
+}
+
+impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher> DashMap<K, Option<V>, S> {
+    /// Get an immutable reference to the inner value of an entry in the map,
+    /// if the entry exists and holds `Some`.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let slots = DashMap::new();
+    /// slots.insert(0, Some("taken"));
+    /// slots.insert(1, None);
+    /// assert_eq!(*slots.get_flatten(&0).unwrap(), "taken");
+    /// assert!(slots.get_flatten(&1).is_none());
+    /// assert!(slots.get_flatten(&2).is_none());
+    /// ```
+    pub fn get_flatten<Q>(&'a self, key: &Q) -> Option<MappedRef<'a, K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get(key)?.try_map(|v| v.as_ref()).ok()
+    }
+
+    /// Takes the inner value out of an entry in the map, leaving `None` in its place
+    /// while keeping the key in the map.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let slots = DashMap::new();
+    /// slots.insert(0, Some("taken"));
+    /// assert_eq!(slots.take_value(&0), Some("taken"));
+    /// assert_eq!(slots.take_value(&0), None);
+    /// assert!(slots.contains_key(&0));
+    /// ```
+    pub fn take_value<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_mut(key)?.value_mut().take()
+    }
+
+    /// Stores a value in an entry of the map, returning the inner value it replaced.
+    ///
+    /// Together with [`take_value`](Self::take_value), this lets a map of `Option`s serve as a map with
+    /// tombstones: removing a key leaves its entry in place holding `None`, and inserting the key again
+    /// reuses the entry instead of growing or rehashing the shard. Workloads which repeatedly remove and
+    /// reinsert the same hot keys avoid most of the table churn this way, and call [`compact`](Self::compact)
+    /// when convenient to drop the tombstones.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let sessions = DashMap::new();
+    /// assert_eq!(sessions.insert_value("alice", 1), None);
+    /// assert_eq!(sessions.take_value("alice"), Some(1));
+    /// assert_eq!(sessions.insert_value("alice", 2), None);
+    /// assert_eq!(sessions.insert_value("alice", 3), Some(2));
+    /// ```
+    pub fn insert_value(&self, key: K, value: V) -> Option<V> {
+        // `insert` makes room for a new entry before looking for the key, which could grow a full shard.
+        if let Some(mut slot) = self.get_mut(&key) {
+            return slot.value_mut().replace(value);
+        }
+
+        self.insert(key, Some(value)).flatten()
+    }
+
+    /// Removes the entries holding `None`, returning how many were removed.
+    ///
+    /// The shards are then shrunk according to the [`shrink_policy`](Self::shrink_policy) of the map,
+    /// like after [`retain`](Self::retain).
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let slots = DashMap::new();
+    /// slots.insert_value(0, "taken");
+    /// slots.insert_value(1, "freed");
+    /// slots.take_value(&1);
+    ///
+    /// assert_eq!(slots.compact(), 1);
+    /// assert_eq!(slots.len(), 1);
+    /// ```
+    pub fn compact(&self) -> usize {
+        self.retain_counted(|_, v| v.is_some()).removed()
+    }
+}
+
+impl<'a, K: 'a + Eq + Hash, V: 'a + Versioned, S: BuildHasher> DashMap<K, V, S> {
+    /// Creates an iterator over the entries whose [`version`](Versioned::version) is greater than `generation`.
+    ///
+    /// Every shard is scanned, but only the changed entries are yielded, which makes it cheap to
+    /// replicate a map incrementally. Entries which were removed are not reported.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::{DashMap, Versioned};
+    /// use std::sync::atomic::{AtomicU64, Ordering};
+    ///
+    /// struct Row {
+    ///     version: u64,
+    ///     name: &'static str,
+    /// }
+    ///
+    /// impl Versioned for Row {
+    ///     fn version(&self) -> u64 {
+    ///         self.version
+    ///     }
+    /// }
+    ///
+    /// let generation = AtomicU64::new(0);
+    /// let next = || generation.fetch_add(1, Ordering::Relaxed) + 1;
+    ///
+    /// let rows = DashMap::new();
+    /// rows.insert(1, Row { version: next(), name: "alice" });
+    /// let synced = generation.load(Ordering::Relaxed);
+    /// rows.insert(2, Row { version: next(), name: "bob" });
+    ///
+    /// let changed: Vec<_> = rows.iter_changed_since(synced).map(|r| r.value().name).collect();
+    /// assert_eq!(changed, ["bob"]);
+    /// ```
+    pub fn iter_changed_since(
+        &'a self,
+        generation: u64,
+    ) -> impl Iterator<Item = RefMulti<'a, K, V>> + 'a {
+        self.iter()
+            .filter(move |r| r.value().version() > generation)
+    }
+}
+
+impl<'a, K: 'a + Eq + Hash, V: 'a, S: 'a + BuildHasher> DashMap<K, V, S> {
+    fn _insert(&self, key: K, value: V) -> Option<V> {
+        let timer = Timer::start();
+
+        let hash = self.hash_u64(&key);
+
+        let idx = self.determine_shard(hash as usize);
+
+        let old = match self.entry_in(idx, hash, key) {
+            Entry::Occupied(mut o) => Some(o.insert(value)),
+            Entry::Vacant(v) => {
+                v.insert(value);
+                None
+            }
+        };
+
+        self.metrics.record(idx, Kind::Insert, timer);
+        old
+    }
+
+    fn _insert_with(&self, key: K, value: impl FnOnce() -> V) -> Option<V> {
+        let timer = Timer::start();
+
+        let hash = self.hash_u64(&key);
+
+        let idx = self.determine_shard(hash as usize);
+
+        let old = match self.entry_in(idx, hash, key) {
+            Entry::Occupied(mut o) => Some(o.insert(value())),
+            Entry::Vacant(v) => {
+                v.insert(value());
+                None
+            }
+        };
+
+        self.metrics.record(idx, Kind::Insert, timer);
+        old
+    }
+
+    fn _insert_with_cow<Q>(&self, key: Cow<'_, Q>, value: V) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ToOwned<Owned = K> + ?Sized,
+    {
+        let key = match key {
+            Cow::Owned(key) => return self._insert(key, value),
+            Cow::Borrowed(key) => key,
+        };
+
+        let timer = Timer::start();
+
+        let hash = self.hash_u64(&key);
+
+        let idx = self.determine_shard(hash as usize);
+
+        let mut shard = self.shards[idx].write();
+        self.metrics.record(idx, Kind::WriteWait, timer);
+
+        self.reserve_one_tracked(&mut shard);
+
+        let old = match shard.entry(
+            hash,
+            |(k, _v)| key == k.borrow(),
+            |(k, _v)| {
+                let mut hasher = self.hasher.build_hasher();
+                k.hash(&mut hasher);
+                hasher.finish()
+            },
+        ) {
+            hash_table::Entry::Occupied(mut entry) => {
+                let (k, v) = entry.get_mut();
+                let old = mem::replace(v, value);
+                tracker::entry_removed(self.tracker(), k, &old);
+                tracker::entry_added(self.tracker(), k, v);
+                Some(old)
+            }
+            hash_table::Entry::Vacant(entry) => {
+                let key = key.to_owned();
+                tracker::entry_added(self.tracker(), &key, &value);
+                entry.insert((key, value));
+                util::count_inserted(self.len_counter(idx));
+                watch::key_inserted(self.notify(idx, hash));
+                None
+            }
+        };
+
+        drop(shard);
+        self.metrics.record(idx, Kind::Insert, timer);
+        old
+    }
+
+    fn _remove<Q>(&self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.hash_u64(&key);
+
+        let idx = self.determine_shard(hash as usize);
+
+        let timer = Timer::start();
+        let mut shard = self.shards[idx].write();
+        self.metrics.record(idx, Kind::WriteWait, timer);
+
+        let removed = if let Ok(entry) = shard.find_entry(hash, |(k, _v)| key == k.borrow()) {
+            let ((k, v), _) = entry.remove();
+            util::entry_removed(
+                &mut shard,
+                self.len_counter(idx),
+                self.tracker(),
+                self.shrinker(),
+                (&k, &v),
+            );
+            Some((k, v))
+        } else {
+            None
+        };
+
+        drop(shard);
+        self.metrics.record(idx, Kind::Remove, timer);
+        removed
+    }
+
+    fn _remove_keys<'q, Q>(&self, keys: impl IntoIterator<Item = &'q Q>) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized + 'q,
+    {
+        let mut groups: Vec<Vec<_>> = self.shards.iter().map(|_| Vec::new()).collect();
+
+        for key in keys {
+            let hash = self.hash_u64(&key);
+            groups[self.determine_shard(hash as usize)].push((hash, key));
+        }
+
+        let mut removed = 0;
+
+        for (idx, group) in groups.into_iter().enumerate() {
+            if group.is_empty() {
+                continue;
+            }
+
+            let mut shard = self.shards[idx].write();
+
+            for (hash, key) in group {
+                if let Ok(entry) = shard.find_entry(hash, |(k, _v)| key == k.borrow()) {
+                    let ((k, v), _) = entry.remove();
+                    tracker::entry_removed(self.tracker(), &k, &v);
+                    removed += 1;
+                }
+            }
+
+            self.shrink_after_removal(&mut shard);
+            self.sync_len(idx, shard.len());
+        }
+
+        removed
+    }
+
+    fn _update_batch<F: FnOnce(&mut V)>(
+        &self,
+        updates: impl IntoIterator<Item = (K, F)>,
+    ) -> Vec<bool> {
+        let mut groups: Vec<Vec<_>> = self.shards.iter().map(|_| Vec::new()).collect();
+        let mut count = 0;
+
+        for (key, f) in updates {
+            let hash = self.hash_u64(&key);
+            groups[self.determine_shard(hash as usize)].push((count, hash, key, f));
+            count += 1;
+        }
+
+        let mut updated = vec![false; count];
+
+        for (idx, group) in groups.into_iter().enumerate() {
+            if group.is_empty() {
+                continue;
+            }
+
+            let timer = Timer::start();
+            let mut shard = self.shards[idx].write();
+            self.metrics.record(idx, Kind::WriteWait, timer);
+
+            for (i, hash, key, f) in group {
+                if let Some((k, v)) = shard.find_mut(hash, |(k, _v)| *k == key) {
+                    tracker::entry_updated(self.tracker(), k, v, f);
+                    updated[i] = true;
+                }
+            }
+        }
+
+        updated
+    }
+
+    fn _remove_if<Q>(&self, key: &Q, f: impl FnOnce(&K, &V) -> bool) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.hash_u64(&key);
+
+        let idx = self.determine_shard(hash as usize);
+
+        let timer = Timer::start();
+        let mut shard = self.shards[idx].write();
+        self.metrics.record(idx, Kind::WriteWait, timer);
+
+        let removed = self.remove_if_in(&mut shard, idx, hash, key, f);
+
+        drop(shard);
+        self.metrics.record(idx, Kind::Remove, timer);
+        removed
+    }
+
+    fn _try_remove_if<Q>(&self, key: &Q, f: impl FnOnce(&K, &V) -> bool) -> TryResult<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.hash_u64(&key);
+
+        let idx = self.determine_shard(hash as usize);
+
+        let mut shard = match self.shards[idx].try_write() {
+            Some(shard) => shard,
+            None => return TryResult::Locked,
+        };
+
+        match self.remove_if_in(&mut shard, idx, hash, key, f) {
+            Some(removed) => TryResult::Present(removed),
+            None => TryResult::Absent,
+        }
+    }
+
+    fn remove_if_in<Q>(
+        &self,
+        shard: &mut HashMap<K, V>,
+        idx: usize,
+        hash: u64,
+        key: &Q,
+        f: impl FnOnce(&K, &V) -> bool,
+    ) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if let Ok(entry) = shard.find_entry(hash, |(k, _v)| key == k.borrow()) {
+            let (k, v) = entry.get();
+            if f(k, v) {
+                let ((k, v), _) = entry.remove();
+                util::entry_removed(
+                    shard,
+                    self.len_counter(idx),
+                    self.tracker(),
+                    self.shrinker(),
+                    (&k, &v),
+                );
+                Some((k, v))
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    }
+
+    fn _remove_if_mut<Q>(&self, key: &Q, f: impl FnOnce(&K, &mut V) -> bool) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.hash_u64(&key);
+
+        let idx = self.determine_shard(hash as usize);
+
+        let timer = Timer::start();
+        let mut shard = self.shards[idx].write();
+        self.metrics.record(idx, Kind::WriteWait, timer);
+
+        let removed = if let Ok(mut entry) = shard.find_entry(hash, |(k, _v)| key == k.borrow()) {
+            let (k, v) = entry.get_mut();
+            if f(k, v) {
+                let ((k, v), _) = entry.remove();
+                util::entry_removed(
+                    &mut shard,
+                    self.len_counter(idx),
+                    self.tracker(),
+                    self.shrinker(),
+                    (&k, &v),
+                );
+                Some((k, v))
+            } else {
+                None
+            }
+        } else {
+            None
+        };
+
+        drop(shard);
+        self.metrics.record(idx, Kind::Remove, timer);
+        removed
+    }
+
+    fn _iter(&'a self) -> Iter<'a, K, V> {
+        Iter::new(self)
+    }
+
+    fn _iter_mut(&'a self) -> IterMut<'a, K, V> {
+        IterMut::new(self)
+    }
+
+    fn _iter_mut_chunks(&'a self, chunk_size: usize) -> IterMutChunks<'a, K, V, S> {
+        IterMutChunks::new(self, chunk_size.max(1))
+    }
+
+    fn _diff_by<'b, S2: BuildHasher, F: FnMut(&V, &V) -> bool>(
+        &'a self,
+        other: &'b DashMap<K, V, S2>,
+        eq: F,
+    ) -> DiffIter<'a, 'b, K, V, S, S2, F> {
+        DiffIter::new(self, other, eq)
+    }
+
+    fn _iter_deterministic(&'a self) -> DeterministicIter<'a, K, V, S> {
+        DeterministicIter::new(self)
+    }
+
+    fn _find_any(&'a self, mut pred: impl FnMut(&K, &V) -> bool) -> Option<Ref<'a, K, V>> {
+        for shard in self.shards.iter() {
+            let shard = shard.read();
+            // SAFETY: The data will not outlive the guard, since we pass the guard to `Ref`
+            // or drop both when nothing matches.
+            let (guard, shard) = unsafe { RwLockReadGuardDetached::detach_from(shard) };
+
+            if let Some((k, v)) = shard.iter().find(|(k, v)| pred(k, v)) {
+                return Some(Ref::new(guard, k, v));
+            }
+        }
+
+        None
+    }
+
+    fn _map_reduce<T>(
+        &self,
+        mut map_fn: impl FnMut(ShardIter<'_, K, V>) -> T,
+        reduce_fn: impl FnMut(T, T) -> T,
+    ) -> T {
+        self.shards
+            .iter()
+            .map(|shard| map_fn(ShardIter::new(&shard.read())))
+            .reduce(reduce_fn)
+            .expect("a map always has shards")
+    }
+
+    fn _for_each_mut_resilient(
+        &self,
+        mut f: impl FnMut(&K, &mut V),
+    ) -> Result<(), Vec<ShardError>> {
+        let mut errors = Vec::new();
+
+        for (idx, shard) in self.shards.iter().enumerate() {
+            let mut shard = shard.write();
+
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                for (k, v) in shard.iter_mut() {
+                    f(k, v);
+                }
+            }));
+
+            if let Err(payload) = result {
+                errors.push(ShardError {
+                    shard: idx,
+                    payload,
+                });
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn _visit_entries(&self, mut f: impl FnMut(&K, &V)) {
+        for shard in self.shards.iter() {
+            for (k, v) in shard.read().iter() {
+                f(k, v);
+            }
+        }
+    }
+
+    fn _keys_cloned(&self) -> Vec<K>
+    where
+        K: Clone,
+    {
+        let mut keys = Vec::new();
+
+        for shard in self.shards.iter() {
+            let shard = shard.read();
+            keys.reserve(shard.len());
+            keys.extend(shard.iter().map(|(k, _v)| k.clone()));
+        }
+
+        keys
+    }
+
+    fn _get<Q>(&'a self, key: &Q) -> Option<Ref<'a, K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.hash_u64(&key);
+
+        let idx = self.determine_shard(hash as usize);
+
+        let timer = Timer::start();
+        let shard = self.shards[idx].read();
+        self.metrics.record(idx, Kind::ReadWait, timer);
+        // SAFETY: The data will not outlive the guard, since we pass the guard to `Ref`.
+        let (guard, shard) = unsafe { RwLockReadGuardDetached::detach_from(shard) };
+
+        let found = shard
+            .find(hash, |(k, _v)| key == k.borrow())
+            .map(|(k, v)| Ref::new(guard, k, v));
+
+        self.metrics.record(idx, Kind::Get, timer);
+        found
+    }
+
+    fn _get_snapshot<Q>(&self, key: &Q) -> Option<EntrySnapshot<K, V>>
+    where
+        K: Borrow<Q> + Clone,
+        V: Clone,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.hash_u64(&key);
+
+        let idx = self.determine_shard(hash as usize);
+
+        let timer = Timer::start();
+        let shard = self.shards[idx].read();
+        self.metrics.record(idx, Kind::ReadWait, timer);
+
+        let found = shard
+            .find(hash, |(k, _v)| key == k.borrow())
+            .map(|(k, v)| EntrySnapshot::new(k.clone(), v.clone()));
+
+        drop(shard);
+        self.metrics.record(idx, Kind::Get, timer);
+        found
+    }
+
+    fn _get_mut<Q>(&'a self, key: &Q) -> Option<RefMut<'a, K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.hash_u64(&key);
+
+        let idx = self.determine_shard(hash as usize);
+
+        let timer = Timer::start();
+        let shard = self.shards[idx].write();
+        self.metrics.record(idx, Kind::WriteWait, timer);
+        // SAFETY: The data will not outlive the guard, since we pass the guard to `RefMut`.
+        let (guard, shard) = unsafe { RwLockWriteGuardDetached::detach_from(shard) };
+
+        let found = shard
+            .find_mut(hash, |(k, _v)| key == k.borrow())
+            .map(|(k, v)| RefMut::new(guard, k, v));
+
+        self.metrics.record(idx, Kind::Get, timer);
+        found
+    }
+
+    fn _get_mut_same_shard<Q>(&'a self, keys: &[&Q]) -> Option<Vec<RefMutMulti<'a, K, V>>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hashes: Vec<u64> = keys.iter().map(|key| self.hash_u64(key)).collect();
+
+        let idx = match hashes.first() {
+            Some(&hash) => self.determine_shard(hash as usize),
+            None => return Some(Vec::new()),
+        };
+
+        if hashes
+            .iter()
+            .any(|&hash| self.determine_shard(hash as usize) != idx)
+        {
+            return None;
+        }
+
+        let timer = Timer::start();
+        let shard = self.shards[idx].write();
+        self.metrics.record(idx, Kind::WriteWait, timer);
+        // SAFETY: The data will not outlive the guard, since every `RefMutMulti` holds a clone of it.
+        let (guard, shard) = unsafe { RwLockWriteGuardDetached::detach_from(shard) };
+        let shard: *mut HashMap<K, V> = shard;
+
+        let mut found = Vec::with_capacity(keys.len());
+
+        for (key, &hash) in keys.iter().zip(hashes.iter()) {
+            // SAFETY: Looking up an entry doesn't move the other ones, and the references already
+            // found are only turned into `RefMutMulti`s after checking that they don't alias.
+            let (k, v) = unsafe { &mut *shard }.find_mut(hash, |(k, _v)| *key == k.borrow())?;
+            found.push((k as *const K, v as *mut V));
+        }
+
+        let mut addresses: Vec<*mut V> = found.iter().map(|&(_k, v)| v).collect();
+        addresses.sort_unstable();
+        addresses.dedup();
+
+        if addresses.len() != found.len() {
+            return None;
+        }
+
+        let guard = Arc::new(guard);
+
+        Some(
+            found
+                .into_iter()
+                // SAFETY: The entries are distinct and stay locked for as long as the guard lives.
+                .map(|(k, v)| RefMutMulti::new(guard.clone(), unsafe { &*k }, unsafe { &mut *v }))
+                .collect(),
+        )
+    }
+
+    fn _try_get<Q>(&'a self, key: &Q) -> TryResult<Ref<'a, K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.hash_u64(&key);
+
+        let idx = self.determine_shard(hash as usize);
+
+        let shard = match self.shards[idx].try_read() {
+            Some(shard) => shard,
+            None => return TryResult::Locked,
+        };
+        // SAFETY: The data will not outlive the guard, since we pass the guard to `Ref`.
+        let (guard, shard) = unsafe { RwLockReadGuardDetached::detach_from(shard) };
+
+        if let Some((k, v)) = shard.find(hash, |(k, _v)| key == k.borrow()) {
+            TryResult::Present(Ref::new(guard, k, v))
+        } else {
+            TryResult::Absent
+        }
+    }
+
+    fn _try_get_detailed<Q>(&'a self, key: &Q) -> TryResultDetailed<Ref<'a, K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.hash_u64(&key);
+
+        let idx = self.determine_shard(hash as usize);
+
+        let shard = match self.shards[idx].try_read() {
+            Some(shard) => shard,
+            None => return self.would_block(idx),
+        };
+        // SAFETY: The data will not outlive the guard, since we pass the guard to `Ref`.
+        let (guard, shard) = unsafe { RwLockReadGuardDetached::detach_from(shard) };
+
+        if let Some((k, v)) = shard.find(hash, |(k, _v)| key == k.borrow()) {
+            TryResultDetailed::Present(Ref::new(guard, k, v))
+        } else {
+            TryResultDetailed::Absent
+        }
+    }
+
+    fn _try_get_mut_detailed<Q>(&'a self, key: &Q) -> TryResultDetailed<RefMut<'a, K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.hash_u64(&key);
+
+        let idx = self.determine_shard(hash as usize);
+
+        let shard = match self.shards[idx].try_write() {
+            Some(shard) => shard,
+            None => return self.would_block(idx),
+        };
+        // SAFETY: The data will not outlive the guard, since we pass the guard to `RefMut`.
+        let (guard, shard) = unsafe { RwLockWriteGuardDetached::detach_from(shard) };
+
+        if let Some((k, v)) = shard.find_mut(hash, |(k, _v)| key == k.borrow()) {
+            TryResultDetailed::Present(RefMut::new(guard, k, v))
+        } else {
+            TryResultDetailed::Absent
+        }
+    }
+
+    fn would_block<R>(&self, idx: usize) -> TryResultDetailed<R> {
+        // SAFETY: The raw lock is only inspected, never locked or unlocked.
+        let (readers, writer) = unsafe { self.shards[idx].raw() }.holders();
+
+        TryResultDetailed::WouldBlock { readers, writer }
+    }
+
+    fn _try_get_mut<Q>(&'a self, key: &Q) -> TryResult<RefMut<'a, K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.hash_u64(&key);
+
+        let idx = self.determine_shard(hash as usize);
+
+        let shard = match self.shards[idx].try_write() {
+            Some(shard) => shard,
+            None => return TryResult::Locked,
+        };
+        // SAFETY: The data will not outlive the guard, since we pass the guard to `RefMut`.
+        let (guard, shard) = unsafe { RwLockWriteGuardDetached::detach_from(shard) };
+
+        if let Some((k, v)) = shard.find_mut(hash, |(k, _v)| key == k.borrow()) {
+            TryResult::Present(RefMut::new(guard, k, v))
+        } else {
+            TryResult::Absent
+        }
+    }
+
+    fn _shrink_to_fit(&self) {
+        self.shards.iter().for_each(|s| {
+            let mut shard = s.write();
+            let size = shard.len();
+            let before = shard.allocation_size();
+            shard.shrink_to(size, |(k, _v)| {
+                let mut hasher = self.hasher.build_hasher();
+                k.hash(&mut hasher);
+                hasher.finish()
+            });
+            tracker::resized(self.tracker(), before, shard.allocation_size());
+        });
+    }
+
+    /// Returns the number of examined and removed entries.
+    fn _retain(&self, mut f: impl FnMut(&K, &mut V) -> bool) -> (usize, usize) {
+        let (mut examined, mut removed) = (0, 0);
+
+        for (idx, shard) in self.shards.iter().enumerate() {
+            let (shard_examined, shard_removed) = self.retain_in(idx, &mut shard.write(), &mut f);
+            examined += shard_examined;
+            removed += shard_removed;
+        }
+
+        (examined, removed)
+    }
+
+    fn retain_in(
+        &self,
+        idx: usize,
+        shard: &mut HashMap<K, V>,
+        f: &mut impl FnMut(&K, &mut V) -> bool,
+    ) -> (usize, usize) {
+        let tracker = self.tracker();
+        let before = shard.len();
+
+        match tracker {
+            None => shard.retain(|(k, v)| f(k, v)),
+            Some(tracker) => shard.retain(|(k, v)| {
+                let keep = f(k, v);
+                if !keep {
+                    tracker::entry_removed(Some(tracker), k, v);
+                }
+                keep
+            }),
+        }
+
+        let removed = before - shard.len();
+
+        self.shrink_after_removal(shard);
+        self.sync_len(idx, shard.len());
+
+        (before, removed)
+    }
+
+    async fn _retain_async(
+        &self,
+        slice: Duration,
+        mut f: impl FnMut(&K, &mut V) -> bool,
+    ) -> SliceProgress {
+        let start = Instant::now();
+        let mut slice_start = start;
+        let (mut examined, mut removed, mut slices) = (0, 0, 1);
+
+        for (idx, shard) in self.shards.iter().enumerate() {
+            loop {
+                if let Some(mut shard) = shard.try_write() {
+                    let (shard_examined, shard_removed) = self.retain_in(idx, &mut shard, &mut f);
+                    examined += shard_examined;
+                    removed += shard_removed;
+                    break;
+                }
+
+                watch::YieldNow::new().await;
+                slice_start = Instant::now();
+                slices += 1;
+            }
+
+            if slice_start.elapsed() >= slice && idx + 1 < self.shards.len() {
+                watch::YieldNow::new().await;
+                slice_start = Instant::now();
+                slices += 1;
+            }
+        }
+
+        SliceProgress {
+            examined,
+            removed,
+            slices,
+            duration: start.elapsed(),
+        }
+    }
+
+    fn _len(&self) -> usize {
+        self.lens
+            .iter()
+            .map(|len| len.load(Ordering::Relaxed))
+            .sum()
+    }
+
+    fn _len_exact(&self) -> usize {
+        self.shards
+            .iter()
+            .enumerate()
+            .map(|(idx, shard)| {
+                let len = shard.read().len();
+                // Writers are excluded by the read lock, so this also corrects the counter
+                // after modifications made directly through the raw shards.
+                self.sync_len(idx, len);
+                len
+            })
+            .sum()
+    }
+
+    fn _capacity(&self) -> usize {
+        self.shards.iter().map(|s| s.read().capacity()).sum()
+    }
+
+    fn _alter<Q>(&self, key: &Q, f: impl FnOnce(&K, V) -> V)
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if let Some(mut r) = self.get_mut(key) {
+            util::map_in_place_2(r.pair_mut(), f);
+        }
+    }
+
+    fn _try_alter<Q>(&self, key: &Q, f: impl FnOnce(&K, V) -> V) -> TryResult<()>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        match self.try_get_mut(key) {
+            TryResult::Present(mut r) => {
+                util::map_in_place_2(r.pair_mut(), f);
+                TryResult::Present(())
+            }
+            TryResult::Absent => TryResult::Absent,
+            TryResult::Locked => TryResult::Locked,
+        }
+    }
+
+    fn _apply_batch(&self, ops: Vec<Op<K, V>>) -> Vec<OpResult<K, V>> {
+        let len = ops.len();
+        let mut groups: Vec<Vec<_>> = self.shards.iter().map(|_| Vec::new()).collect();
+
+        for (i, op) in ops.into_iter().enumerate() {
+            let hash = self.hash_u64(op.key());
+            let idx = self.determine_shard(hash as usize);
+            groups[idx].push((i, hash, op));
+        }
+
+        let mut results: Vec<Option<OpResult<K, V>>> = (0..len).map(|_| None).collect();
+
+        for (idx, group) in groups.into_iter().enumerate() {
+            if group.is_empty() {
+                continue;
+            }
+
+            let timer = Timer::start();
+            let mut shard = self.shards[idx].write();
+            self.metrics.record(idx, Kind::WriteWait, timer);
+
+            for (i, hash, op) in group {
+                results[i] = Some(self.apply_op(&mut shard, self.notify(idx, hash), hash, op));
+            }
+
+            self.shrink_after_removal(&mut shard);
+            self.sync_len(idx, shard.len());
+        }
+
+        results.into_iter().map(Option::unwrap).collect()
+    }
+
+    fn apply_op(
+        &self,
+        shard: &mut HashMap<K, V>,
+        notify: watch::Notify<'_>,
+        hash: u64,
+        op: Op<K, V>,
+    ) -> OpResult<K, V> {
+        match op {
+            Op::Insert(key, value) => {
+                self.reserve_one_tracked(shard);
+
+                match shard.entry(
+                    hash,
+                    |(k, _v)| k == &key,
+                    |(k, _v)| {
+                        let mut hasher = self.hasher.build_hasher();
+                        k.hash(&mut hasher);
+                        hasher.finish()
+                    },
+                ) {
+                    hash_table::Entry::Occupied(mut entry) => {
+                        let (k, v) = entry.get_mut();
+                        tracker::entry_added(self.tracker(), k, &value);
+                        let old = mem::replace(v, value);
+                        tracker::entry_removed(self.tracker(), k, &old);
+                        OpResult::Inserted(Some(old))
+                    }
+                    hash_table::Entry::Vacant(entry) => {
+                        tracker::entry_added(self.tracker(), &key, &value);
+                        entry.insert((key, value));
+                        watch::key_inserted(notify);
+                        OpResult::Inserted(None)
+                    }
+                }
+            }
+            Op::Remove(key) => match shard.find_entry(hash, |(k, _v)| k == &key) {
+                Ok(entry) => {
+                    let ((k, v), _) = entry.remove();
+                    tracker::entry_removed(self.tracker(), &k, &v);
+                    OpResult::Removed(Some((k, v)))
+                }
+                Err(_) => OpResult::Removed(None),
+            },
+            Op::Alter(key, f) => match shard.find_mut(hash, |(k, _v)| k == &key) {
+                Some((k, v)) => {
+                    let k = &*k;
+                    tracker::entry_updated(self.tracker(), k, v, |v| {
+                        util::map_in_place_2((k, v), f)
+                    });
+                    OpResult::Altered(true)
+                }
+                None => OpResult::Altered(false),
+            },
+        }
+    }
+
+    fn _fetch_update<Q>(&self, key: &Q, f: impl FnOnce(V) -> Option<V>) -> Option<Result<V, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        V: Copy,
+    {
+        let mut r = self.get_mut(key)?;
+        let prev = *r.value();
+
+        match f(prev) {
+            Some(next) => {
+                *r.value_mut() = next;
+                Some(Ok(prev))
+            }
+            None => Some(Err(prev)),
+        }
+    }
+
+    fn _alter_all(&self, mut f: impl FnMut(&K, V) -> V) {
+        self.iter_mut()
+            .for_each(|mut m| util::map_in_place_2(m.pair_mut(), &mut f));
+    }
+
+    fn _alter_all_chunked(&self, chunk_size: usize, mut f: impl FnMut(&K, V) -> V) {
+        for (idx, shard) in self.shards.iter().enumerate() {
+            for chunk in self.shard_hashes(idx).chunks(chunk_size.max(1)) {
+                let mut shard = shard.write();
+
+                for &hash in chunk {
+                    for (k, v) in self.iter_hash_exact_mut(&mut shard, hash) {
+                        util::map_in_place_2((&*k, v), &mut f);
+                    }
+                }
+            }
+        }
+    }
+
+    fn _alter_all_background(&self, mut f: impl FnMut(&K, V) -> V) {
+        // Retries of contended shards, sleeping twice as long after every round which left some.
+        const ROUNDS: u32 = 12;
+        const FIRST_BACKOFF: Duration = Duration::from_micros(10);
+
+        let mut alter = |shard: &mut HashMap<K, V>| {
+            for (k, v) in shard.iter_mut() {
+                util::map_in_place_2((&*k, v), &mut f);
+            }
+        };
+
+        let mut deferred = Vec::new();
+
+        for (idx, shard) in self.shards.iter().enumerate() {
+            match shard.try_write() {
+                Some(mut shard) => alter(&mut shard),
+                None => deferred.push(idx),
+            }
+        }
+
+        let mut backoff = FIRST_BACKOFF;
+
+        for _ in 0..ROUNDS {
+            if deferred.is_empty() {
+                return;
+            }
+
+            std::thread::sleep(backoff);
+            backoff *= 2;
+
+            deferred.retain(|&idx| match self.shards[idx].try_write() {
+                Some(mut shard) => {
+                    alter(&mut shard);
+                    false
+                }
+                None => true,
+            });
+        }
+
+        for idx in deferred {
+            alter(&mut self.shards[idx].write());
+        }
+    }
+
+    fn _view<Q, R>(&self, key: &Q, f: impl FnOnce(&K, &V) -> R) -> Option<R>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get(key).map(|r| {
+            let (k, v) = r.pair();
+            f(k, v)
+        })
+    }
+
+    fn _view_mut_or_insert_with<R>(
+        &self,
+        key: K,
+        init: impl FnOnce() -> V,
+        f: impl FnOnce(&K, &mut V) -> R,
+    ) -> R {
+        let mut r = self.entry(key).or_insert_with(init);
+        let (k, v) = r.pair_mut();
+        f(k, v)
+    }
+
+    fn _entry(&'a self, key: K) -> Entry<'a, K, V> {
+        let hash = self.hash_u64(&key);
+
+        let idx = self.determine_shard(hash as usize);
+
+        self.entry_in(idx, hash, key)
+    }
+
+    fn entry_in(&'a self, idx: usize, hash: u64, key: K) -> Entry<'a, K, V> {
+        let timer = Timer::start();
+        let shard = self.shards[idx].write();
+        self.metrics.record(idx, Kind::WriteWait, timer);
+        // SAFETY: The data will not outlive the guard, since we pass the guard to `Entry`.
+        let (guard, shard) = unsafe { RwLockWriteGuardDetached::detach_from(shard) };
+
+        self.entry_from(guard, shard, idx, hash, key)
+    }
+
+    fn _entry_if(
+        &'a self,
+        key: K,
+        gate: impl FnOnce(EntryGateInfo) -> bool,
+    ) -> Option<Entry<'a, K, V>> {
+        let hash = self.hash_u64(&key);
+
+        let idx = self.determine_shard(hash as usize);
+
+        let timer = Timer::start();
+        let shard = self.shards[idx].write();
+        self.metrics.record(idx, Kind::WriteWait, timer);
+        // SAFETY: The data will not outlive the guard, since we pass the guard to `Entry`.
+        let (guard, shard) = unsafe { RwLockWriteGuardDetached::detach_from(shard) };
+
+        if shard.find(hash, |(k, _v)| k == &key).is_none() {
+            let info = EntryGateInfo {
+                len: self._len(),
+                shard_len: shard.len(),
+                shard_capacity: shard.capacity(),
+            };
+
+            if !gate(info) {
+                return None;
+            }
+        }
+
+        Some(self.entry_from(guard, shard, idx, hash, key))
+    }
+
+    /// Looks up the entry of a key in a shard which was already locked for writing.
+    fn entry_from(
+        &'a self,
+        guard: RwLockWriteGuardDetached<'a>,
+        shard: &'a mut HashMap<K, V>,
+        idx: usize,
+        hash: u64,
+        key: K,
+    ) -> Entry<'a, K, V> {
+        self.reserve_one_tracked(shard);
+
+        match shard.entry(
+            hash,
+            |(k, _v)| k == &key,
+            |(k, _v)| {
+                let mut hasher = self.hasher.build_hasher();
+                k.hash(&mut hasher);
+                hasher.finish()
+            },
+        ) {
+            hash_table::Entry::Occupied(entry) => Entry::Occupied(OccupiedEntry::new(
+                guard,
+                key,
+                entry,
+                self.tracker(),
+                self.len_counter(idx),
+                self.shrinker(),
+            )),
+            hash_table::Entry::Vacant(entry) => Entry::Vacant(VacantEntry::new(
+                guard,
+                key,
+                entry,
+                self.tracker(),
+                self.notify(idx, hash),
+                self.len_counter(idx),
+                self.shrinker(),
+            )),
+        }
+    }
+
+    fn _entry_cloned<'q>(&'a self, key: &'q K) -> EntryRef<'a, 'q, K, V>
+    where
+        K: Clone,
+    {
+        let hash = self.hash_u64(key);
+
+        let idx = self.determine_shard(hash as usize);
+
+        let timer = Timer::start();
+        let shard = self.shards[idx].write();
+        self.metrics.record(idx, Kind::WriteWait, timer);
+        // SAFETY: The data will not outlive the guard, since we pass the guard to `EntryRef`.
+        let (guard, shard) = unsafe { RwLockWriteGuardDetached::detach_from(shard) };
+
+        self.entry_cloned_in(guard, shard, idx, hash, key)
+    }
+
+    fn _try_entry_cloned<'q>(&'a self, key: &'q K) -> Option<EntryRef<'a, 'q, K, V>>
+    where
+        K: Clone,
+    {
+        let hash = self.hash_u64(key);
+
+        let idx = self.determine_shard(hash as usize);
+
+        let shard = self.shards[idx].try_write()?;
+        // SAFETY: The data will not outlive the guard, since we pass the guard to `EntryRef`.
+        let (guard, shard) = unsafe { RwLockWriteGuardDetached::detach_from(shard) };
+
+        Some(self.entry_cloned_in(guard, shard, idx, hash, key))
+    }
+
+    fn entry_cloned_in<'q>(
+        &'a self,
+        guard: RwLockWriteGuardDetached<'a>,
+        shard: &'a mut HashMap<K, V>,
+        idx: usize,
+        hash: u64,
+        key: &'q K,
+    ) -> EntryRef<'a, 'q, K, V>
+    where
+        K: Clone,
+    {
+        self.reserve_one_tracked(shard);
+
+        match shard.entry(
+            hash,
+            |(k, _v)| k == key,
+            |(k, _v)| {
+                let mut hasher = self.hasher.build_hasher();
+                k.hash(&mut hasher);
+                hasher.finish()
+            },
+        ) {
+            hash_table::Entry::Occupied(entry) => EntryRef::Occupied(OccupiedEntryRef::new(
+                guard,
+                entry,
+                self.tracker(),
+                self.len_counter(idx),
+                self.shrinker(),
+            )),
+            hash_table::Entry::Vacant(entry) => EntryRef::Vacant(VacantEntryRef::new(
+                guard,
+                key,
+                entry,
+                self.tracker(),
+                self.notify(idx, hash),
+                self.len_counter(idx),
+            )),
+        }
+    }
+
+    fn _try_entry(&'a self, key: K) -> Option<Entry<'a, K, V>> {
+        self._try_entry_or_key(key).ok()
+    }
+
+    /// Like `_try_entry`, but hands the key back if the shard is locked.
+    fn _try_entry_or_key(&'a self, key: K) -> Result<Entry<'a, K, V>, K> {
+        let hash = self.hash_u64(&key);
+
+        let idx = self.determine_shard(hash as usize);
+
+        let shard = match self.shards[idx].try_write() {
+            Some(shard) => shard,
+            None => return Err(key),
+        };
+        // SAFETY: The data will not outlive the guard, since we pass the guard to `Entry`.
+        let (guard, shard) = unsafe { RwLockWriteGuardDetached::detach_from(shard) };
+
+        Ok(self.entry_from(guard, shard, idx, hash, key))
+    }
+
+    async fn _get_or_insert_with_async<F, Fut>(&'a self, mut key: K, f: F) -> Ref<'a, K, V>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = V>,
+    {
+        let hash = self.hash_u64(&key);
+        let watchers = &self.watchers()[self.determine_shard(hash as usize)];
+
+        let claim = loop {
+            match self._try_entry_or_key(key) {
+                Ok(Entry::Occupied(entry)) => return entry.into_ref().downgrade(),
+                Ok(Entry::Vacant(entry)) => {
+                    let claim = watchers.try_claim(hash);
+                    key = entry.into_key();
+
+                    match claim {
+                        Some(claim) => break claim,
+                        None => watch::InFlight::new(watchers, hash).await,
+                    }
+                }
+                Err(returned) => {
+                    key = returned;
+                    watch::YieldNow::new().await;
+                }
+            }
+        };
+
+        let value = f().await;
+
+        loop {
+            match self._try_entry_or_key(key) {
+                Ok(entry) => {
+                    let inserted = entry.or_insert(value).downgrade();
+                    drop(claim);
+                    return inserted;
+                }
+                Err(returned) => {
+                    key = returned;
+                    watch::YieldNow::new().await;
+                }
+            }
+        }
+    }
+
+    fn _upsert(
+        &'a self,
+        key: K,
+        on_insert: impl FnOnce() -> V,
+        on_update: impl FnOnce(&K, &mut V),
+    ) -> RefMut<'a, K, V> {
+        match self._entry(key) {
+            Entry::Occupied(entry) => {
+                let mut r = entry.into_ref();
+                let (k, v) = r.pair_mut();
+                on_update(k, v);
+                r
+            }
+            Entry::Vacant(entry) => entry.insert(on_insert()),
+        }
+    }
+
+    fn _get_or_insert_with_flag(
+        &'a self,
+        key: K,
+        f: impl FnOnce() -> V,
+    ) -> (RefMut<'a, K, V>, bool) {
+        match self._entry(key) {
+            Entry::Occupied(entry) => (entry.into_ref(), false),
+            Entry::Vacant(entry) => (entry.insert(f()), true),
+        }
+    }
+
+    /// Returns the distinct hashes of the keys in a shard, sorted, for visiting it in chunks.
+    ///
+    /// Positions in the table change when it is resized, so the hashes of the keys are what
+    /// identifies the entries left to visit once the lock was released between two chunks.
+    /// Entries inserted in the meantime are visited if their hash is still ahead.
+    fn shard_hashes(&self, idx: usize) -> Vec<u64> {
+        let mut hashes: Vec<u64> = self.shards[idx]
+            .read()
+            .iter()
+            .map(|(k, _v)| self.hash_u64(k))
+            .collect();
+        hashes.sort_unstable();
+        hashes.dedup();
+        hashes
+    }
+
+    /// Returns the entries of a shard whose key has exactly the given hash, out of the ones
+    /// `iter_hash_mut` finds by its top bits.
+    fn iter_hash_exact_mut<'s>(
+        &'s self,
+        shard: &'s mut HashMap<K, V>,
+        hash: u64,
+    ) -> impl Iterator<Item = &'s mut (K, V)> + 's {
+        shard
+            .iter_hash_mut(hash)
+            .filter(move |(k, _v)| self.hash_u64(k) == hash)
+    }
+
+    // With a tracker, grow the shard before creating an entry so that the change of the
+    // allocation can be measured. `HashTable::entry` reserves room for one more element
+    // even if the key is already present, so this has to do the same.
+    fn reserve_one_tracked(&self, shard: &mut HashMap<K, V>) {
+        if let Some(tracker) = self.tracker() {
+            let before = shard.allocation_size();
+            shard.reserve(1, |(k, _v)| {
+                let mut hasher = self.hasher.build_hasher();
+                k.hash(&mut hasher);
+                hasher.finish()
+            });
+            tracker::resized(Some(tracker), before, shard.allocation_size());
+        }
+    }
+
+    fn _clear(&self) {
+        self._retain(|_, _| false);
+    }
+
+    fn _clear_incremental(&self, chunk: usize) -> ClearProgress {
+        let mut cleared = 0;
+        let mut remaining = 0;
+
+        for (idx, shard) in self.shards.iter().enumerate() {
+            if cleared < chunk {
+                let mut shard = shard.write();
+
+                for (k, v) in shard.extract_if(|_| true).take(chunk - cleared) {
+                    tracker::entry_removed(self.tracker(), &k, &v);
+                    cleared += 1;
+                }
+
+                self.shrink_after_removal(&mut shard);
+                self.sync_len(idx, shard.len());
+                remaining += shard.len();
+            } else {
+                remaining += shard.read().len();
+            }
+        }
+
+        ClearProgress { cleared, remaining }
+    }
+
+    async fn _clear_async(&self, slice: Duration) -> SliceProgress {
+        // Reading the clock for every entry would cost about as much as removing it.
+        const CHECK_EVERY: usize = 64;
+
+        let start = Instant::now();
+        let mut slice_start = start;
+        let (mut removed, mut slices) = (0, 1);
+
+        for (idx, shard) in self.shards.iter().enumerate() {
+            loop {
+                let (mut finished, mut out_of_time) = (false, false);
+
+                if let Some(mut shard) = shard.try_write() {
+                    for (k, v) in shard.extract_if(|_| true) {
+                        tracker::entry_removed(self.tracker(), &k, &v);
+                        removed += 1;
+
+                        if removed % CHECK_EVERY == 0 && slice_start.elapsed() >= slice {
+                            out_of_time = true;
+                            break;
+                        }
+                    }
+
+                    finished = !out_of_time;
+                    if finished {
+                        self.shrink_after_removal(&mut shard);
+                    }
+                    self.sync_len(idx, shard.len());
+                }
+
+                if finished {
+                    break;
+                }
+
+                watch::YieldNow::new().await;
+                slice_start = Instant::now();
+                slices += 1;
+            }
+        }
+
+        SliceProgress {
+            examined: removed,
+            removed,
+            slices,
+            duration: start.elapsed(),
+        }
+    }
+
+    fn _try_clear(&self) -> usize {
+        self.shards
+            .iter()
+            .enumerate()
+            .filter(|(idx, shard)| match shard.try_write() {
+                Some(mut shard) => {
+                    if let Some(tracker) = self.tracker() {
+                        for (k, v) in shard.iter() {
+                            tracker::entry_removed(Some(tracker), k, v);
+                        }
+                    }
+                    shard.clear();
+                    self.shrink_after_removal(&mut shard);
+                    self.sync_len(*idx, 0);
+                    false
+                }
+                None => true,
+            })
+            .count()
+    }
+
+    fn _contains_key<Q>(&'a self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self._get(key).is_some()
+    }
+
+    fn _is_empty(&self) -> bool {
+        self._len() == 0
+    }
+}
+
+/// Formats the entries of the map, read-locking one shard at a time.
+///
+/// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+/// Use [`DashMap::debug_lossy`] to format a map which may be locked, such as in log statements.
+impl<K: Eq + Hash + fmt::Debug, V: fmt::Debug, S: BuildHasher> fmt::Debug for DashMap<K, V, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut pmap = f.debug_map();
+
+        for r in self {
+            let (k, v) = r.pair();
+
+            pmap.entry(k, v);
+        }
+
+        pmap.finish()
+    }
+}
+
+impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher> Shl<(K, V)> for &'a DashMap<K, V, S> {
+    type Output = Option<V>;
+
+    fn shl(self, pair: (K, V)) -> Self::Output {
+        self.insert(pair.0, pair.1)
+    }
+}
+
+impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher, Q> Shr<&Q> for &'a DashMap<K, V, S>
+where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+{
+    type Output = Ref<'a, K, V>;
+
+    fn shr(self, key: &Q) -> Self::Output {
+        self.get(key).unwrap()
+    }
+}
+
+impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher, Q> BitOr<&Q> for &'a DashMap<K, V, S>
+where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+{
+    type Output = RefMut<'a, K, V>;
+
+    fn bitor(self, key: &Q) -> Self::Output {
+        self.get_mut(key).unwrap()
+    }
+}
+
+impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher, Q> Sub<&Q> for &'a DashMap<K, V, S>
+where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+{
+    type Output = Option<(K, V)>;
+
+    fn sub(self, key: &Q) -> Self::Output {
+        self.remove(key)
+    }
+}
+
+impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher, Q> BitAnd<&Q> for &'a DashMap<K, V, S>
+where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+{
+    type Output = bool;
+
+    fn bitand(self, key: &Q) -> Self::Output {
+        self.contains_key(key)
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher> IntoIterator for DashMap<K, V, S> {
+    type Item = (K, V);
+
+    type IntoIter = OwningIter<K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        OwningIter::new(self)
+    }
+}
+
+impl<'a, K: Eq + Hash, V, S: BuildHasher> IntoIterator for &'a DashMap<K, V, S> {
+    type Item = RefMulti<'a, K, V>;
+
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher> Extend<(K, V)> for DashMap<K, V, S> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, intoiter: I) {
+        for pair in intoiter.into_iter() {
+            self.insert(pair.0, pair.1);
+        }
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher + Default> FromIterator<(K, V)> for DashMap<K, V, S> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(intoiter: I) -> Self {
+        let mut map = DashMap::default();
+
+        map.extend(intoiter);
+
+        map
+    }
+}
+
+#[cfg(feature = "typesize")]
+impl<K, V, S> typesize::TypeSize for DashMap<K, V, S>
+where
+    K: typesize::TypeSize + Eq + Hash,
+    V: typesize::TypeSize,
+    S: typesize::TypeSize + BuildHasher,
+{
+    fn extra_size(&self) -> usize {
+        let shards_extra_size: usize = self
+            .shards
+            .iter()
+            .map(|shard_lock| {
+                let shard = shard_lock.read();
+                let hashtable_size = shard.allocation_size();
+
+                // Safety: The iterator is dropped before the HashTable
+                let iter = shard.iter();
+                let entry_size_iter =
+                    iter.map(|(key, value)| key.extra_size() + value.extra_size());
+
+                core::mem::size_of::<CachePadded<RwLock<HashMap<K, V>>>>()
+                    + hashtable_size
+                    + entry_size_iter.sum::<usize>()
+            })
+            .sum();
+
+        self.hasher.extra_size() + shards_extra_size
+    }
+
+    typesize::if_typesize_details! {
+        fn get_collection_item_count(&self) -> Option<usize> {
+            Some(self.len())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DashMap, EntryGateInfo};
+    use std::collections::hash_map::RandomState;
+
+    #[test]
+    fn test_basic() {
+        let dm = DashMap::new();
+
+        dm.insert(0, 0);
+
+        assert_eq!(dm.get(&0).unwrap().value(), &0);
+    }
+
+    #[test]
+    fn test_default() {
+        let dm: DashMap<u32, u32> = DashMap::default();
+
+        dm.insert(0, 0);
+
+        assert_eq!(dm.get(&0).unwrap().value(), &0);
+    }
+
+    #[test]
+    fn test_downgrade_keeps_read_lock() {
+        let dm = DashMap::new();
+
+        let r = dm.entry(0).or_insert(1).downgrade();
+        let other = dm.get(&0).unwrap();
+        assert_eq!((*r, *other), (1, 1));
+        drop(r);
+        drop(other);
+
+        dm.insert(0, 2);
+        assert_eq!(*dm.get(&0).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_multiple_hashes() {
+        let dm: DashMap<u32, u32> = DashMap::default();
+
+        for i in 0..100 {
+            dm.insert(0, i);
+
+            dm.insert(i, i);
+        }
+
+        for i in 1..100 {
+            let r = dm.get(&i).unwrap();
+
+            assert_eq!(i, *r.value());
+
+            assert_eq!(i, *r.key());
+        }
+
+        let r = dm.get(&0).unwrap();
+
+        assert_eq!(99, *r.value());
+    }
+
+    #[test]
+    fn test_more_complex_values() {
+        #[derive(Hash, PartialEq, Debug, Clone)]
+
+        struct T0 {
+            s: String,
+            u: u8,
+        }
+
+        let dm = DashMap::new();
+
+        let range = 0..10;
+
+        for i in range {
+            let t = T0 {
+                s: i.to_string(),
+                u: i as u8,
+            };
+
+            dm.insert(i, t.clone());
+
+            assert_eq!(&t, dm.get(&i).unwrap().value());
+        }
+    }
+
+    #[test]
+    fn test_different_hashers_randomstate() {
+        let dm_hm_default: DashMap<u32, u32, RandomState> =
+            DashMap::with_hasher(RandomState::new());
+
+        for i in 0..10 {
+            dm_hm_default.insert(i, i);
+
+            assert_eq!(i, *dm_hm_default.get(&i).unwrap().value());
+        }
+    }
+
+    #[test]
+    fn test_map_view() {
+        let dm = DashMap::new();
+
+        let vegetables: [String; 4] = [
+            "Salad".to_string(),
+            "Beans".to_string(),
+            "Potato".to_string(),
+            "Tomato".to_string(),
+        ];
+
+        // Give it some values
+        dm.insert(0, "Banana".to_string());
+        dm.insert(4, "Pear".to_string());
+        dm.insert(9, "Potato".to_string());
+        dm.insert(12, "Chicken".to_string());
+
+        let potato_vegetableness = dm.view(&9, |_, v| vegetables.contains(v));
+        assert_eq!(potato_vegetableness, Some(true));
+
+        let chicken_vegetableness = dm.view(&12, |_, v| vegetables.contains(v));
+        assert_eq!(chicken_vegetableness, Some(false));
+
+        let not_in_map = dm.view(&30, |_k, _v| false);
+        assert_eq!(not_in_map, None);
+    }
+
+    #[test]
+    fn test_try_get() {
+        {
+            let map = DashMap::new();
+            map.insert("Johnny", 21);
+
+            assert_eq!(*map.try_get("Johnny").unwrap(), 21);
+
+            let _result1_locking = map.get_mut("Johnny");
+
+            let result2 = map.try_get("Johnny");
+            assert!(result2.is_locked());
+        }
+
+        {
+            let map = DashMap::new();
+            map.insert("Johnny", 21);
+
+            *map.try_get_mut("Johnny").unwrap() += 1;
+            assert_eq!(*map.get("Johnny").unwrap(), 22);
+
+            let _result1_locking = map.get("Johnny");
+
+            let result2 = map.try_get_mut("Johnny");
+            assert!(result2.is_locked());
+        }
+    }
+
+    #[test]
+    fn test_len_counter() {
+        let map = DashMap::with_shard_amount(4);
+
+        for i in 0..100 {
+            map.insert(i, i);
+        }
+        map.insert(0, 1);
+        map.entry(100).or_insert(100);
+        map.entry(101).or_insert(101).downgrade();
+        assert_eq!(map.len(), 102);
+
+        map.remove(&0);
+        map.remove_if(&1, |_, _| true);
+        if let crate::Entry::Occupied(entry) = map.entry(2) {
+            entry.remove();
+        }
+        assert_eq!(map.len(), 99);
+
+        map.retain(|k, _| k % 2 == 0);
+        assert_eq!(map.len(), map.len_exact());
+
+        let mut view = map.clone().into_read_only();
+        view.insert_mut(1000, 0);
+        view.remove_mut(&4);
+        assert_eq!(view.len(), map.len());
+
+        map.clear_incremental(10);
+        assert_eq!(map.len(), map.len_exact());
+        map.clear();
+        assert_eq!(map.len(), 0);
+    }
+
+    #[test]
+    fn test_alter_all_chunked() {
+        let map = DashMap::with_shard_amount(4);
+
+        for i in 0..100 {
+            map.insert(i, i);
+        }
+
+        let mut calls = 0;
+        map.alter_all_chunked(7, |_, v| {
+            calls += 1;
+            v * 2
+        });
+
+        assert_eq!(calls, 100);
+        for i in 0..100 {
+            assert_eq!(*map.get(&i).unwrap(), i * 2);
+        }
+    }
+
+    #[test]
+    fn test_apply_batch() {
+        use crate::batch::{Op, OpResult};
+
+        let map = DashMap::with_shard_amount(4);
+
+        for i in 0..50 {
+            map.insert(i, i);
+        }
+
+        let mut ops = Vec::new();
+        let mut expected = Vec::new();
+
+        for i in 0..100 {
+            ops.push(Op::Insert(i, i + 1));
+            expected.push(OpResult::Inserted(if i < 50 { Some(i) } else { None }));
+
+            ops.push(Op::Alter(i, Box::new(|_, v| v * 10)));
+            expected.push(OpResult::Altered(true));
+
+            if i % 2 == 0 {
+                ops.push(Op::Remove(i));
+                expected.push(OpResult::Removed(Some((i, (i + 1) * 10))));
+            }
+        }
+
+        ops.push(Op::Alter(1000, Box::new(|_, v| v)));
+        expected.push(OpResult::Altered(false));
+
+        assert_eq!(map.apply_batch(ops), expected);
+        assert_eq!(map.len(), 50);
+        assert_eq!(*map.get(&7).unwrap(), 80);
+    }
+
+    #[test]
+    fn test_apply_batch_sent_to_flusher() {
+        use crate::batch::Op;
+        use std::sync::mpsc;
+        use std::thread;
+
+        let map = DashMap::new();
+        let (tx, rx) = mpsc::channel::<Vec<Op<u32, u32>>>();
+
+        thread::scope(|s| {
+            let map = &map;
+            let flusher = s.spawn(move || {
+                rx.iter()
+                    .map(|ops| map.apply_batch(ops).len())
+                    .sum::<usize>()
+            });
+
+            for i in 0..10 {
+                tx.send(vec![
+                    Op::Insert(i, i),
+                    Op::Alter(i, Box::new(move |_, v| v + i)),
+                ])
+                .unwrap();
+            }
+            drop(tx);
+
+            assert_eq!(flusher.join().unwrap(), 20);
+        });
+
+        assert_eq!(map.len(), 10);
+        assert_eq!(*map.get(&7).unwrap(), 14);
+    }
+
+    #[test]
+    fn test_buffered_writer() {
+        use crate::batch::BufferedWriter;
+
+        let map = DashMap::with_shard_amount(4);
+
+        {
+            let mut writer = BufferedWriter::new(&map, 1000);
+            for i in 0..100 {
+                writer.insert(i % 10, i);
+            }
+            assert_eq!(writer.pending(), 100);
+            assert!(map.is_empty());
+        }
+
+        assert_eq!(map.len(), 10);
+        assert_eq!(*map.get(&3).unwrap(), 93);
+
+        let mut writer = BufferedWriter::new(&map, 2);
+        writer.insert(10, 0);
+        writer.insert(10, 1);
+        assert_eq!(writer.pending(), 0);
+        assert_eq!(*map.get(&10).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_buffered_writer_per_thread_clones() {
+        use crate::batch::BufferedWriter;
+        use std::thread;
+
+        let map = DashMap::with_shard_amount(4);
+        let mut writer = BufferedWriter::new(&map, 16);
+        writer.insert(u32::MAX, 0);
+
+        thread::scope(|s| {
+            for t in 0..4 {
+                let mut writer = writer.clone();
+                assert_eq!(writer.pending(), 0);
+
+                s.spawn(move || {
+                    for i in 0..100 {
+                        writer.insert(t * 100 + i, t);
+                    }
+                });
+            }
+        });
+
+        // Every clone flushed its buffers when its thread dropped it.
+        assert_eq!(map.len(), 400);
+        assert_eq!(*map.get(&399).unwrap(), 3);
+        assert_eq!(writer.pending(), 1);
+        drop(writer);
+        assert_eq!(map.len(), 401);
+    }
+
+    #[test]
+    fn test_from_sorted_iter() {
+        let entries = [
+            (1, 'a'),
+            (2, 'b'),
+            (2, 'c'),
+            (5, 'd'),
+            (5, 'e'),
+            (5, 'f'),
+            (9, 'g'),
+        ];
+        let map = DashMap::from_sorted_iter(entries, RandomState::new());
+
+        assert_eq!(map.len(), 4);
+        assert_eq!(map.len_exact(), 4);
+        assert_eq!(
+            map.into_sorted_vec(),
+            [(1, 'a'), (2, 'c'), (5, 'f'), (9, 'g')]
+        );
+
+        let map = DashMap::from_sorted_iter((0..10_000).map(|i| (i, i)), RandomState::new());
+        let capacity = map.capacity();
+        assert!((0..10_000).all(|i| *map.get(&i).unwrap() == i));
+
+        map.insert(10_000, 10_000);
+        assert_eq!(map.capacity(), capacity);
+    }
+
+    #[test]
+    #[should_panic(expected = "keys are not sorted")]
+    fn test_from_sorted_iter_unsorted() {
+        DashMap::from_sorted_iter([(2, ()), (1, ())], RandomState::new());
+    }
+
+    #[test]
+    fn test_into_sorted() {
+        let map: DashMap<u32, u32> = (0..1000).rev().map(|i| (i * 7 % 1000, i)).collect();
+        let sorted = map.clone().into_sorted_vec();
+
+        assert_eq!(sorted.len(), 1000);
+        assert!(sorted.windows(2).all(|w| w[0].0 < w[1].0));
+        assert!(map.into_btreemap().into_iter().eq(sorted));
+    }
+
+    #[test]
+    fn test_occupancy_report() {
+        let map = DashMap::with_capacity_and_shard_amount(1000, 4);
+        let empty = map.occupancy_report().total();
+        assert_eq!((empty.len, empty.estimated_tombstones), (0, 0));
+        assert!(empty.capacity >= 1000 && empty.bytes > 0);
+
+        for i in 0..1000 {
+            map.insert(i, i);
+        }
+
+        for i in 0..500 {
+            map.remove(&i);
+        }
+
+        let total = map.occupancy_report().total();
+        assert_eq!(total.len, 500);
+        assert_eq!(total.bytes, empty.bytes);
+        assert_eq!(total.capacity + total.estimated_tombstones, empty.capacity);
+    }
+
+    #[test]
+    fn test_shrink_policy() {
+        use crate::ShrinkPolicy;
+
+        let map = DashMap::with_shard_amount(4).with_shrink_policy(ShrinkPolicy::OnFallBelow(0.5));
+
+        for i in 0..1000 {
+            map.insert(i, i);
+        }
+
+        let grown = map.capacity();
+
+        for i in 0..990 {
+            map.remove(&i);
+        }
+
+        assert!(map.capacity() < grown / 10);
+        assert_eq!(map.len(), 10);
+        assert_eq!(*map.get(&995).unwrap(), 995);
+    }
 
-        let idx = self.determine_shard(hash as usize);
+    #[test]
+    fn test_shrink_policy_every_removal_path() {
+        use crate::mapref::entry::{Entry, EntryRef};
+        use crate::setref::entry::EntryRef as SetEntryRef;
+        use crate::{DashSet, Op, ShrinkPolicy};
+
+        let filled = || {
+            let map =
+                DashMap::with_shard_amount(4).with_shrink_policy(ShrinkPolicy::OnFallBelow(0.5));
+            for i in 0..1000 {
+                map.insert(i, i);
+            }
+            let grown = map.capacity();
+            (map, grown)
+        };
 
-        let mut shard = self.shards[idx].write();
+        let (map, grown) = filled();
+        for i in 0..990 {
+            if let Entry::Occupied(entry) = map.entry(i) {
+                assert_eq!(entry.remove(), i);
+            }
+        }
+        assert!(map.capacity() < grown / 10);
+        assert_eq!(map.len(), 10);
 
-        if let Ok(entry) = shard.find_entry(hash, |(k, _v)| key == k.borrow()) {
-            let (k, v) = entry.get();
-            if f(k, v) {
-                let ((k, v), _) = entry.remove();
-                Some((k, v))
-            } else {
-                None
+        let (map, grown) = filled();
+        for i in 0..990 {
+            if let EntryRef::Occupied(entry) = map.entry_cloned(&i) {
+                entry.remove_entry();
             }
-        } else {
-            None
         }
-    }
+        assert!(map.capacity() < grown / 10);
 
-    fn _remove_if_mut<Q>(&self, key: &Q, f: impl FnOnce(&K, &mut V) -> bool) -> Option<(K, V)>
-    where
-        K: Borrow<Q>,
-        Q: Hash + Eq + ?Sized,
-    {
-        let hash = self.hash_u64(&key);
+        let (map, grown) = filled();
+        map.apply_batch((0..990).map(Op::Remove).collect());
+        assert!(map.capacity() < grown / 10);
+        assert_eq!(map.len(), 10);
 
-        let idx = self.determine_shard(hash as usize);
+        let (map, grown) = filled();
+        map.clear_incremental(990);
+        assert!(map.capacity() < grown / 10);
 
-        let mut shard = self.shards[idx].write();
+        let (map, _) = filled();
+        assert_eq!(map.try_clear(), 0);
+        assert_eq!(map.capacity(), 0);
 
-        if let Ok(mut entry) = shard.find_entry(hash, |(k, _v)| key == k.borrow()) {
-            let (k, v) = entry.get_mut();
-            if f(k, v) {
-                let ((k, v), _) = entry.remove();
-                Some((k, v))
-            } else {
-                None
+        let set = DashSet {
+            inner: DashMap::with_shard_amount(4).with_shrink_policy(ShrinkPolicy::OnFallBelow(0.5)),
+        };
+        for i in 0..1000 {
+            set.insert(i);
+        }
+        let grown = set.capacity();
+        for i in 0..990 {
+            if let SetEntryRef::Occupied(entry) = set.entry_ref(&i) {
+                entry.remove();
             }
-        } else {
-            None
         }
+        assert!(set.capacity() < grown / 10);
     }
 
-    fn _iter(&'a self) -> Iter<'a, K, V> {
-        Iter::new(self)
-    }
+    #[test]
+    fn test_ref_into_shared() {
+        let map = DashMap::new();
+        map.insert("config", vec![1, 2, 3]);
 
-    fn _iter_mut(&'a self) -> IterMut<'a, K, V> {
-        IterMut::new(self)
+        let shared = map.get("config").unwrap().into_shared();
+
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                let shared = shared.clone();
+                s.spawn(move || assert_eq!(shared.iter().sum::<i32>(), 6));
+            }
+        });
+
+        assert!(map.try_get_mut("config").is_locked());
+        drop(shared);
+        assert!(map.try_get_mut("config").is_present());
     }
 
-    fn _get<Q>(&'a self, key: &Q) -> Option<Ref<'a, K, V>>
-    where
-        K: Borrow<Q>,
-        Q: Hash + Eq + ?Sized,
-    {
-        let hash = self.hash_u64(&key);
+    #[test]
+    fn test_ref_mut_replace_and_take() {
+        let map = DashMap::new();
+        map.insert("log", vec![1, 2]);
 
-        let idx = self.determine_shard(hash as usize);
+        let mut log = map.get_mut("log").unwrap();
+        assert_eq!(log.replace(vec![3]), [1, 2]);
+        assert_eq!(log.take(), [3]);
+        drop(log);
 
-        let shard = self.shards[idx].read();
-        // SAFETY: The data will not outlive the guard, since we pass the guard to `Ref`.
-        let (guard, shard) = unsafe { RwLockReadGuardDetached::detach_from(shard) };
+        assert!(map.get("log").unwrap().is_empty());
+    }
 
-        if let Some((k, v)) = shard.find(hash, |(k, _v)| key == k.borrow()) {
-            Some(Ref::new(guard, k, v))
-        } else {
+    #[test]
+    fn test_insert_with_cow() {
+        use std::borrow::Cow;
+
+        let map: DashMap<String, u32> = DashMap::with_shard_amount(4);
+
+        assert_eq!(map.insert_with_cow(Cow::Borrowed("a"), 1), None);
+        assert_eq!(map.insert_with_cow(Cow::Borrowed("a"), 2), Some(1));
+        assert_eq!(
+            map.insert_with_cow(Cow::<str>::Owned("a".to_owned()), 3),
+            Some(2)
+        );
+        assert_eq!(
+            map.insert_with_cow(Cow::<str>::Owned("b".to_owned()), 4),
             None
-        }
+        );
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.len_exact(), 2);
+        assert_eq!(*map.get("a").unwrap(), 3);
     }
 
-    fn _get_mut<Q>(&'a self, key: &Q) -> Option<RefMut<'a, K, V>>
-    where
-        K: Borrow<Q>,
-        Q: Hash + Eq + ?Sized,
-    {
-        let hash = self.hash_u64(&key);
+    #[test]
+    fn test_remove_and_retain_keys() {
+        let map = DashMap::with_shard_amount(4);
 
-        let idx = self.determine_shard(hash as usize);
+        for i in 0..100 {
+            map.insert(i, i);
+        }
 
-        let shard = self.shards[idx].write();
-        // SAFETY: The data will not outlive the guard, since we pass the guard to `RefMut`.
-        let (guard, shard) = unsafe { RwLockWriteGuardDetached::detach_from(shard) };
+        let evens: Vec<i32> = (0..100).step_by(2).collect();
+        assert_eq!(map.remove_keys(evens.iter()), 50);
+        assert_eq!(map.remove_keys(evens.iter()), 0);
+        assert_eq!(map.len(), 50);
 
-        if let Some((k, v)) = shard.find_mut(hash, |(k, _v)| key == k.borrow()) {
-            Some(RefMut::new(guard, k, v))
-        } else {
-            None
-        }
+        let keep = crate::DashSet::new();
+        keep.insert(1);
+        keep.insert(2);
+        keep.insert(3);
+
+        map.retain_keys(&keep);
+        assert_eq!(map.len(), 2);
+        assert!(map.contains_key(&1) && map.contains_key(&3));
     }
 
-    fn _try_get<Q>(&'a self, key: &Q) -> TryResult<Ref<'a, K, V>>
-    where
-        K: Borrow<Q>,
-        Q: Hash + Eq + ?Sized,
-    {
-        let hash = self.hash_u64(&key);
+    #[cfg(feature = "raw-api")]
+    #[test]
+    fn test_shard_init() {
+        use hashbrown::HashTable;
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::BuildHasherDefault;
 
-        let idx = self.determine_shard(hash as usize);
+        let hasher = BuildHasherDefault::<DefaultHasher>::default();
+        let probe: DashMap<u32, u32, _> = DashMap::with_hasher_and_shard_amount(hasher.clone(), 4);
 
-        let shard = match self.shards[idx].try_read() {
-            Some(shard) => shard,
-            None => return TryResult::Locked,
-        };
-        // SAFETY: The data will not outlive the guard, since we pass the guard to `Ref`.
-        let (guard, shard) = unsafe { RwLockReadGuardDetached::detach_from(shard) };
+        let map = DashMap::with_hasher_and_shard_amount_and_init(hasher, 4, |idx| {
+            let mut shard = HashTable::with_capacity(if idx == 0 { 1000 } else { 0 });
 
-        if let Some((k, v)) = shard.find(hash, |(k, _v)| key == k.borrow()) {
-            TryResult::Present(Ref::new(guard, k, v))
-        } else {
-            TryResult::Absent
-        }
-    }
+            for i in (0..100).filter(|i| probe.determine_map(i) == idx) {
+                let hash = probe.hash_u64(&i);
+                shard.insert_unique(hash, (i, i * 2), |(k, _v)| probe.hash_u64(k));
+            }
 
-    fn _try_get_mut<Q>(&'a self, key: &Q) -> TryResult<RefMut<'a, K, V>>
-    where
-        K: Borrow<Q>,
-        Q: Hash + Eq + ?Sized,
-    {
-        let hash = self.hash_u64(&key);
+            shard
+        });
 
-        let idx = self.determine_shard(hash as usize);
+        assert_eq!(map.len(), 100);
+        assert!((0..100).all(|i| *map.get(&i).unwrap() == i * 2));
+        assert!(map.shards()[0].read().capacity() >= 1000);
 
-        let shard = match self.shards[idx].try_write() {
-            Some(shard) => shard,
-            None => return TryResult::Locked,
-        };
-        // SAFETY: The data will not outlive the guard, since we pass the guard to `RefMut`.
-        let (guard, shard) = unsafe { RwLockWriteGuardDetached::detach_from(shard) };
+        let sized: DashMap<u32, u32> =
+            DashMap::with_shard_amount_and_init(8, |idx| HashTable::with_capacity(idx * 10));
+        assert_eq!(sized.shards().len(), 8);
+        assert!(sized.shards()[7].read().capacity() >= 70);
+    }
 
-        if let Some((k, v)) = shard.find_mut(hash, |(k, _v)| key == k.borrow()) {
-            TryResult::Present(RefMut::new(guard, k, v))
-        } else {
-            TryResult::Absent
+    #[test]
+    fn test_alter_all_background() {
+        let map = DashMap::with_shard_amount(4);
+
+        for i in 0..100 {
+            map.insert(i, i);
         }
-    }
 
-    fn _shrink_to_fit(&self) {
-        self.shards.iter().for_each(|s| {
-            let mut shard = s.write();
-            let size = shard.len();
-            shard.shrink_to(size, |(k, _v)| {
-                let mut hasher = self.hasher.build_hasher();
-                k.hash(&mut hasher);
-                hasher.finish()
-            })
+        let barrier = std::sync::Barrier::new(2);
+
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                let guard = map.get_mut(&1).unwrap();
+                barrier.wait();
+                std::thread::sleep(std::time::Duration::from_millis(5));
+                drop(guard);
+            });
+
+            barrier.wait();
+            map.alter_all_with_priority(|_, v| v + 1000, crate::Priority::Background);
         });
+
+        assert!((0..100).all(|i| *map.get(&i).unwrap() == i + 1000));
+
+        map.alter_all_with_priority(|_, v| v - 1000, crate::Priority::Foreground);
+        assert!((0..100).all(|i| *map.get(&i).unwrap() == i));
     }
 
-    fn _retain(&self, mut f: impl FnMut(&K, &mut V) -> bool) {
-        self.shards.iter().for_each(|s| {
-            s.write().retain(|(k, v)| f(k, v));
+    #[test]
+    fn test_read_scope() {
+        let map = DashMap::with_shard_amount(4);
+
+        for i in 0..100 {
+            map.insert(i, i * 2);
+        }
+
+        let sum: i32 = map.read_scope(|tx| {
+            let sum = (0..200).filter_map(|i| tx.get(&i).copied()).sum();
+            assert_eq!(tx.locked_shards(), 4);
+            assert!(map.try_get_mut(&1).is_locked());
+            sum
         });
-    }
+        assert_eq!(sum, 9900);
+        assert!(map.try_get_mut(&1).is_present());
+
+        // A shard which is locked elsewhere makes the scope give up the shards it holds while waiting.
+        let guard = map.get_mut(&1).unwrap();
+        let shard = map.determine_shard(map.hash_u64(&1) as usize);
+        let other = (0..100)
+            .find(|i| map.determine_shard(map.hash_u64(i) as usize) != shard)
+            .unwrap();
+
+        std::thread::scope(|s| {
+            let reader = s.spawn(|| {
+                map.read_scope(|tx| {
+                    assert!(tx.contains_key(&other));
+                    tx.get(&1).copied()
+                })
+            });
 
-    fn _len(&self) -> usize {
-        self.shards.iter().map(|s| s.read().len()).sum()
+            // The reader is blocked on the shard of `1` by now, without holding the other one.
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            assert!(map.try_get_mut(&other).is_present());
+            drop(guard);
+
+            assert_eq!(reader.join().unwrap(), Some(2));
+        });
     }
 
-    fn _capacity(&self) -> usize {
-        self.shards.iter().map(|s| s.read().capacity()).sum()
+    #[test]
+    fn test_get_snapshot() {
+        let map = DashMap::new();
+        map.insert(String::from("a"), vec![1]);
+
+        let snapshot = map.get_snapshot("a").unwrap();
+        map.get_mut("a").unwrap().push(2);
+        assert!(map.get_snapshot("b").is_none());
+
+        assert_eq!(*snapshot, [1]);
+        assert_eq!(snapshot.into_pair(), (String::from("a"), vec![1]));
+        assert_eq!(map.get_snapshot("a").unwrap().into_value(), [1, 2]);
     }
 
-    fn _alter<Q>(&self, key: &Q, f: impl FnOnce(&K, V) -> V)
-    where
-        K: Borrow<Q>,
-        Q: Hash + Eq + ?Sized,
-    {
-        if let Some(mut r) = self.get_mut(key) {
-            util::map_in_place_2(r.pair_mut(), f);
+    #[test]
+    fn test_debug_lossy() {
+        let map = DashMap::with_shard_amount(4);
+
+        for i in 0..100 {
+            map.insert(i, i);
         }
-    }
 
-    fn _alter_all(&self, mut f: impl FnMut(&K, V) -> V) {
-        self.iter_mut()
-            .for_each(|mut m| util::map_in_place_2(m.pair_mut(), &mut f));
+        let shard = map.determine_shard(map.hash_u64(&7) as usize);
+        let hidden = (0..100)
+            .filter(|i| map.determine_shard(map.hash_u64(i) as usize) == shard)
+            .count();
+
+        let locked = map.get_mut(&7).unwrap();
+        let printed = format!("{:?}", map.debug_lossy());
+        assert_eq!(printed.matches("<locked>").count(), 1);
+        assert!(printed.contains(&format!("<locked>: <shard {}>", shard)));
+        assert_eq!(printed.matches(": ").count(), 100 - hidden + 1);
+        assert!(map.try_debug().is_none());
+        drop(locked);
+
+        assert_eq!(
+            format!("{:?}", map.try_debug().unwrap()),
+            format!("{:?}", map)
+        );
     }
 
-    fn _view<Q, R>(&self, key: &Q, f: impl FnOnce(&K, &V) -> R) -> Option<R>
-    where
-        K: Borrow<Q>,
-        Q: Hash + Eq + ?Sized,
-    {
-        self.get(key).map(|r| {
-            let (k, v) = r.pair();
-            f(k, v)
-        })
-    }
+    #[test]
+    fn test_iter_changed_since() {
+        struct Counter(u64, u64);
 
-    fn _entry(&'a self, key: K) -> Entry<'a, K, V> {
-        let hash = self.hash_u64(&key);
+        impl crate::Versioned for Counter {
+            fn version(&self) -> u64 {
+                self.0
+            }
+        }
 
-        let idx = self.determine_shard(hash as usize);
+        let map = DashMap::with_shard_amount(4);
 
-        let shard = self.shards[idx].write();
-        // SAFETY: The data will not outlive the guard, since we pass the guard to `Entry`.
-        let (guard, shard) = unsafe { RwLockWriteGuardDetached::detach_from(shard) };
+        for i in 0..100 {
+            map.insert(i, Counter(1, 0));
+        }
 
-        match shard.entry(
-            hash,
-            |(k, _v)| k == &key,
-            |(k, _v)| {
-                let mut hasher = self.hasher.build_hasher();
-                k.hash(&mut hasher);
-                hasher.finish()
-            },
-        ) {
-            hash_table::Entry::Occupied(entry) => {
-                Entry::Occupied(OccupiedEntry::new(guard, key, entry))
-            }
-            hash_table::Entry::Vacant(entry) => Entry::Vacant(VacantEntry::new(guard, key, entry)),
+        for i in (0..100).step_by(10) {
+            let mut counter = map.get_mut(&i).unwrap();
+            *counter = Counter(2, counter.1 + 1);
         }
+
+        let mut changed: Vec<u64> = map.iter_changed_since(1).map(|r| *r.key()).collect();
+        changed.sort_unstable();
+        assert_eq!(changed, (0..100).step_by(10).collect::<Vec<_>>());
+        assert!(map.iter_changed_since(1).all(|r| r.value().1 == 1));
+        assert_eq!(map.iter_changed_since(0).count(), 100);
+        assert_eq!(map.iter_changed_since(2).count(), 0);
     }
 
-    fn _try_entry(&'a self, key: K) -> Option<Entry<'a, K, V>> {
-        let hash = self.hash_u64(&key);
+    #[test]
+    fn test_update_batch() {
+        let map = DashMap::with_shard_amount(4);
 
-        let idx = self.determine_shard(hash as usize);
+        for i in 0..100 {
+            map.insert(i, i);
+        }
 
-        let shard = match self.shards[idx].try_write() {
-            Some(shard) => shard,
-            None => return None,
-        };
-        // SAFETY: The data will not outlive the guard, since we pass the guard to `Entry`.
-        let (guard, shard) = unsafe { RwLockWriteGuardDetached::detach_from(shard) };
+        let updates = (90..110).chain([95]).map(|k| (k, |v: &mut i32| *v += 1000));
+        let updated = map.update_batch(updates);
+
+        assert_eq!(updated.len(), 21);
+        assert!(updated[..10].iter().all(|&u| u));
+        assert!(updated[10..20].iter().all(|&u| !u));
+        assert!(updated[20]);
+        assert_eq!(*map.get(&90).unwrap(), 1090);
+        assert_eq!(*map.get(&95).unwrap(), 2095);
+        assert_eq!(*map.get(&89).unwrap(), 89);
+        assert_eq!(map.len(), 100);
+    }
 
-        match shard.entry(
-            hash,
-            |(k, _v)| k == &key,
-            |(k, _v)| {
-                let mut hasher = self.hasher.build_hasher();
-                k.hash(&mut hasher);
-                hasher.finish()
-            },
-        ) {
-            hash_table::Entry::Occupied(entry) => {
-                Some(Entry::Occupied(OccupiedEntry::new(guard, key, entry)))
+    #[test]
+    fn test_update_batch_panic_keeps_tracker_balanced() {
+        use crate::MemoryTracker;
+        use std::panic::{self, AssertUnwindSafe};
+        use std::sync::atomic::{AtomicIsize, Ordering};
+        use std::sync::Arc;
+
+        #[derive(Default)]
+        struct Counter(AtomicIsize);
+
+        impl MemoryTracker<u32, Vec<u8>> for Counter {
+            fn entry_size(&self, _key: &u32, value: &Vec<u8>) -> usize {
+                value.len()
             }
-            hash_table::Entry::Vacant(entry) => {
-                Some(Entry::Vacant(VacantEntry::new(guard, key, entry)))
+
+            fn on_change(&self, delta: isize) {
+                self.0.fetch_add(delta, Ordering::Relaxed);
             }
         }
-    }
 
-    fn _clear(&self) {
-        self._retain(|_, _| false)
+        let counter = Arc::new(Counter::default());
+        let map = DashMap::with_memory_tracker(counter.clone());
+        map.insert(1, vec![0; 10]);
+
+        let updates: Vec<(u32, Box<dyn FnOnce(&mut Vec<u8>)>)> = vec![(
+            1,
+            Box::new(|v| {
+                v.resize(25, 0);
+                panic!("update failed");
+            }),
+        )];
+        let result = panic::catch_unwind(AssertUnwindSafe(|| map.update_batch(updates)));
+        assert!(result.is_err());
+        assert_eq!(map.get(&1).unwrap().len(), 25);
+
+        map.clear();
+        map.shrink_to_fit();
+        assert_eq!(counter.0.load(Ordering::Relaxed), 0);
     }
 
-    fn _contains_key<Q>(&'a self, key: &Q) -> bool
-    where
-        K: Borrow<Q>,
-        Q: Hash + Eq + ?Sized,
-    {
-        self._get(key).is_some()
+    #[test]
+    fn test_shard_id() {
+        let map: DashMap<u32, u32> = DashMap::with_shard_amount(4);
+
+        let ids: std::collections::HashSet<_> = (0..1000).map(|i| map.shard_id(&i)).collect();
+        assert_eq!(ids.len(), 4);
+
+        let (a, b) = (0..1000)
+            .flat_map(|a| (0..1000).map(move |b| (a, b)))
+            .find(|(a, b)| map.shard_id(a) != map.shard_id(b))
+            .unwrap();
+        assert!(!map.same_shard(&a, &b));
+        assert!(map.same_shard(&a, &a));
     }
 
-    fn _is_empty(&self) -> bool {
-        self._len() == 0
-    }
-}
+    #[test]
+    fn test_try_get_detailed() {
+        use crate::try_result::{TryResult, TryResultDetailed};
 
-impl<K: Eq + Hash + fmt::Debug, V: fmt::Debug, S: BuildHasher + Clone> fmt::Debug
-    for DashMap<K, V, S>
-{
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut pmap = f.debug_map();
+        let map = DashMap::new();
+        map.insert("Johnny", 21);
 
-        for r in self {
-            let (k, v) = r.pair();
+        assert!(map.try_get_detailed("Jimmy").is_absent());
 
-            pmap.entry(k, v);
+        {
+            let _r1 = map.get("Johnny");
+            let _r2 = map.get("Johnny");
+
+            assert!(map.try_get_detailed("Johnny").is_present());
+            assert!(matches!(
+                map.try_get_mut_detailed("Johnny"),
+                TryResultDetailed::WouldBlock {
+                    readers: 2,
+                    writer: false
+                }
+            ));
         }
 
-        pmap.finish()
+        let _w = map.get_mut("Johnny");
+
+        assert!(matches!(
+            map.try_get_detailed("Johnny"),
+            TryResultDetailed::WouldBlock {
+                readers: 0,
+                writer: true
+            }
+        ));
+        assert!(TryResult::from(map.try_get_mut_detailed("Johnny")).is_locked());
     }
-}
 
-impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher + Clone> Shl<(K, V)> for &'a DashMap<K, V, S> {
-    type Output = Option<V>;
+    #[test]
+    fn test_watch_key() {
+        use crate::test_util::ThreadWaker;
+        use std::future::Future;
+        use std::pin::Pin;
+        use std::task::{Context, Poll, Waker};
+
+        let counter = ThreadWaker::new();
+        let waker = Waker::from(counter.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        let map = DashMap::new();
+        map.insert(1, "one");
+
+        let mut present = map.watch_key(&1);
+        match Pin::new(&mut present).poll(&mut cx) {
+            Poll::Ready(r) => assert_eq!(*r, "one"),
+            Poll::Pending => panic!("key is present"),
+        }
 
-    fn shl(self, pair: (K, V)) -> Self::Output {
-        self.insert(pair.0, pair.1)
-    }
-}
+        let mut waiting = map.watch_key(&2);
+        let mut cancelled = map.watch_key(&2);
+        assert!(Pin::new(&mut waiting).poll(&mut cx).is_pending());
+        assert!(Pin::new(&mut cancelled).poll(&mut cx).is_pending());
+        drop(cancelled);
 
-impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher + Clone, Q> Shr<&Q> for &'a DashMap<K, V, S>
-where
-    K: Borrow<Q>,
-    Q: Hash + Eq + ?Sized,
-{
-    type Output = Ref<'a, K, V>;
+        map.insert(3, "three");
+        assert_eq!(counter.wakes(), 0);
 
-    fn shr(self, key: &Q) -> Self::Output {
-        self.get(key).unwrap()
+        map.insert(2, "two");
+        assert_eq!(counter.wakes(), 1);
+
+        match Pin::new(&mut waiting).poll(&mut cx) {
+            Poll::Ready(r) => assert_eq!(*r, "two"),
+            Poll::Pending => panic!("key was inserted"),
+        };
     }
-}
 
-impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher + Clone, Q> BitOr<&Q> for &'a DashMap<K, V, S>
-where
-    K: Borrow<Q>,
-    Q: Hash + Eq + ?Sized,
-{
-    type Output = RefMut<'a, K, V>;
+    #[test]
+    fn test_get_or_insert_with_async() {
+        use crate::test_util::{block_on, waker};
+        use std::future::Future;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Barrier;
+        use std::task::Context;
+        use std::thread;
+        use std::time::Duration;
+
+        let map = DashMap::new();
+        let fetches = AtomicUsize::new(0);
+
+        let waker = waker();
+        let mut cx = Context::from_waker(&waker);
+        let mut cancelled = Box::pin(map.get_or_insert_with_async(1, std::future::pending));
+        assert!(cancelled.as_mut().poll(&mut cx).is_pending());
+        drop(cancelled);
+
+        let barrier = Barrier::new(4);
+
+        thread::scope(|s| {
+            for _ in 0..4 {
+                let (map, fetches, barrier) = (&map, &fetches, &barrier);
+
+                s.spawn(move || {
+                    barrier.wait();
+
+                    let value = block_on(map.get_or_insert_with_async(1, || async {
+                        fetches.fetch_add(1, Ordering::SeqCst);
+                        thread::sleep(Duration::from_millis(50));
+                        "fetched"
+                    }));
+                    assert_eq!(*value, "fetched");
+                });
+            }
+        });
 
-    fn bitor(self, key: &Q) -> Self::Output {
-        self.get_mut(key).unwrap()
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            *block_on(map.get_or_insert_with_async(1, || async { "again" })),
+            "fetched"
+        );
     }
-}
 
-impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher + Clone, Q> Sub<&Q> for &'a DashMap<K, V, S>
-where
-    K: Borrow<Q>,
-    Q: Hash + Eq + ?Sized,
-{
-    type Output = Option<(K, V)>;
+    #[test]
+    fn test_visit() {
+        let map: DashMap<u32, u32> = (0..100).map(|i| (i, i * 2)).collect();
+
+        let mut keys = 0;
+        map.visit_keys(|k| keys += k);
+        assert_eq!(keys, (0..100).sum());
+
+        let mut visited = 0;
+        map.visit_entries(|k, v| {
+            assert_eq!(*v, k * 2);
+            assert!(map.try_get(k).is_present());
+            visited += 1;
+        });
+        assert_eq!(visited, 100);
+    }
 
-    fn sub(self, key: &Q) -> Self::Output {
-        self.remove(key)
+    #[test]
+    fn test_try_variants_locked() {
+        let map: DashMap<String, u32> = DashMap::new();
+        map.insert("a".to_owned(), 1);
+        let key = "a".to_owned();
+
+        let guard = map.get_mut("a").unwrap();
+        assert!(map.try_entry_cloned(&key).is_none());
+        assert!(map.try_alter("a", |_, v| v + 1).is_locked());
+        assert!(map.try_remove_if("a", |_, _| true).is_locked());
+        drop(guard);
+
+        assert!(map.try_alter("a", |_, v| v + 1).is_present());
+        assert_eq!(*map.try_entry_cloned(&key).unwrap().or_insert(0), 2);
+        assert!(map.try_remove_if("b", |_, _| true).is_absent());
+        assert_eq!(map.try_remove_if("a", |_, v| *v == 2).unwrap().1, 2);
+        assert!(map.is_empty());
     }
-}
 
-impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher + Clone, Q> BitAnd<&Q> for &'a DashMap<K, V, S>
-where
-    K: Borrow<Q>,
-    Q: Hash + Eq + ?Sized,
-{
-    type Output = bool;
+    #[test]
+    fn test_try_reserve() {
+        let mut map: DashMap<i32, i32> = DashMap::new();
+        // DashMap is empty and doesn't allocate memory
+        assert_eq!(map.capacity(), 0);
 
-    fn bitand(self, key: &Q) -> Self::Output {
-        self.contains_key(key)
-    }
-}
+        map.try_reserve(10).unwrap();
 
-impl<K: Eq + Hash, V, S: BuildHasher + Clone> IntoIterator for DashMap<K, V, S> {
-    type Item = (K, V);
+        // And now map can hold at least 10 elements
+        assert!(map.capacity() >= 10);
+    }
 
-    type IntoIter = OwningIter<K, V>;
+    #[test]
+    fn test_try_reserve_errors() {
+        let mut map: DashMap<i32, i32> = DashMap::new();
 
-    fn into_iter(self) -> Self::IntoIter {
-        OwningIter::new(self)
+        match map.try_reserve(usize::MAX) {
+            Err(_) => {}
+            _ => panic!("should have raised CapacityOverflow error"),
+        }
     }
-}
 
-impl<'a, K: Eq + Hash, V, S: BuildHasher + Clone> IntoIterator for &'a DashMap<K, V, S> {
-    type Item = RefMulti<'a, K, V>;
+    #[test]
+    fn test_ref_split() {
+        let map = DashMap::new();
+        map.insert("Johnny", 21);
 
-    type IntoIter = Iter<'a, K, V>;
+        let (key, value) = map.get("Johnny").unwrap().split();
+        assert_eq!(*key, "Johnny");
 
-    fn into_iter(self) -> Self::IntoIter {
-        self.iter()
-    }
-}
+        drop(key);
+        assert!(map.try_get_mut("Johnny").is_locked());
 
-impl<K: Eq + Hash, V, S: BuildHasher + Clone> Extend<(K, V)> for DashMap<K, V, S> {
-    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, intoiter: I) {
-        for pair in intoiter.into_iter() {
-            self.insert(pair.0, pair.1);
-        }
+        assert_eq!(*value, 21);
+        drop(value);
+        assert!(map.try_get_mut("Johnny").is_present());
     }
-}
 
-impl<K: Eq + Hash, V, S: BuildHasher + Clone + Default> FromIterator<(K, V)> for DashMap<K, V, S> {
-    fn from_iter<I: IntoIterator<Item = (K, V)>>(intoiter: I) -> Self {
-        let mut map = DashMap::default();
+    #[test]
+    fn test_ref_mut_downgrade() {
+        let map = DashMap::new();
+        map.insert("Johnny", 21);
 
-        map.extend(intoiter);
+        let r = map.get_mut("Johnny").unwrap().downgrade();
+        assert!(map.try_get("Johnny").is_present());
+        assert!(map.try_get_mut("Johnny").is_locked());
 
-        map
+        drop(r);
+        assert!(map.try_get_mut("Johnny").is_present());
     }
-}
 
-#[cfg(feature = "typesize")]
-impl<K, V, S> typesize::TypeSize for DashMap<K, V, S>
-where
-    K: typesize::TypeSize + Eq + Hash,
-    V: typesize::TypeSize,
-    S: typesize::TypeSize + Clone + BuildHasher,
-{
-    fn extra_size(&self) -> usize {
-        let shards_extra_size: usize = self
-            .shards
-            .iter()
-            .map(|shard_lock| {
-                let shard = shard_lock.read();
-                let hashtable_size = shard.allocation_size();
+    #[test]
+    fn test_ref_mut_downgrade_map() {
+        let map = DashMap::new();
+        map.insert("Johnny", (21, "Tokyo"));
+
+        let mut r = map.get_mut("Johnny").unwrap();
+        r.0 += 1;
+        let city = r.downgrade_map(|(_, city)| city);
+        assert_eq!(*city, "Tokyo");
+        assert!(map.try_get("Johnny").is_present());
+        assert!(map.try_get_mut("Johnny").is_locked());
+
+        drop(city);
+        assert_eq!(map.get("Johnny").unwrap().0, 22);
+    }
 
-                // Safety: The iterator is dropped before the HashTable
-                let iter = shard.iter();
-                let entry_size_iter =
-                    iter.map(|(key, value)| key.extra_size() + value.extra_size());
+    #[test]
+    fn test_non_clone_hasher() {
+        use std::hash::BuildHasher;
 
-                core::mem::size_of::<CachePadded<RwLock<HashMap<K, V>>>>()
-                    + hashtable_size
-                    + entry_size_iter.sum::<usize>()
-            })
-            .sum();
+        struct NoClone(RandomState);
 
-        self.hasher.extra_size() + shards_extra_size
-    }
+        impl BuildHasher for NoClone {
+            type Hasher = <RandomState as BuildHasher>::Hasher;
 
-    typesize::if_typesize_details! {
-        fn get_collection_item_count(&self) -> Option<usize> {
-            Some(self.len())
+            fn build_hasher(&self) -> Self::Hasher {
+                self.0.build_hasher()
+            }
         }
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use crate::DashMap;
-    use std::collections::hash_map::RandomState;
 
-    #[test]
-    fn test_basic() {
-        let dm = DashMap::new();
+        let map = DashMap::with_hasher(NoClone(RandomState::new()));
+        map.insert(1, 2);
+        map.alter(&1, |_, v| v + 1);
+        assert_eq!(*map.get(&1).unwrap(), 3);
 
-        dm.insert(0, 0);
+        let set = crate::DashSet::with_hasher(NoClone(RandomState::new()));
+        set.insert(1);
+        assert!(set.contains(&1));
 
-        assert_eq!(dm.get(&0).unwrap().value(), &0);
+        let view = map.into_read_only();
+        assert_eq!(view.get(&1), Some(&3));
     }
 
     #[test]
-    fn test_default() {
-        let dm: DashMap<u32, u32> = DashMap::default();
+    fn test_try_reserve_error() {
+        use crate::TryReserveErrorKind;
 
-        dm.insert(0, 0);
+        let mut map: DashMap<u64, u64> = DashMap::new();
 
-        assert_eq!(dm.get(&0).unwrap().value(), &0);
+        let err = map.try_reserve(usize::MAX).unwrap_err();
+        assert_eq!(err.kind(), TryReserveErrorKind::CapacityOverflow);
+        assert!(err.to_string().contains("capacity"));
+
+        assert!(map.try_reserve(16).is_ok());
     }
 
     #[test]
-    fn test_multiple_hashes() {
-        let dm: DashMap<u32, u32> = DashMap::default();
+    fn test_try_with_shard_amount() {
+        for shard_amount in [0, 1, 3, 12] {
+            let err = DashMap::<u32, u32>::try_with_capacity_and_hasher_and_shard_amount(
+                0,
+                RandomState::new(),
+                shard_amount,
+            )
+            .unwrap_err();
+            assert_eq!(err.shard_amount(), shard_amount);
+        }
 
-        for i in 0..100 {
-            dm.insert(0, i);
+        let map = DashMap::<u32, u32>::try_with_capacity_and_hasher_and_shard_amount(
+            0,
+            RandomState::new(),
+            8,
+        )
+        .unwrap();
+        assert_eq!(map.shards().len(), 8);
+    }
 
-            dm.insert(i, i);
-        }
+    #[test]
+    fn test_memory_tracker_balances() {
+        use crate::MemoryTracker;
+        use std::sync::atomic::{AtomicIsize, Ordering};
+        use std::sync::Arc;
 
-        for i in 1..100 {
-            let r = dm.get(&i).unwrap();
+        #[derive(Default)]
+        struct Counter(AtomicIsize);
 
-            assert_eq!(i, *r.value());
+        impl MemoryTracker<u32, Vec<u8>> for Counter {
+            fn entry_size(&self, _key: &u32, value: &Vec<u8>) -> usize {
+                value.len()
+            }
 
-            assert_eq!(i, *r.key());
+            fn on_change(&self, delta: isize) {
+                self.0.fetch_add(delta, Ordering::Relaxed);
+            }
         }
 
-        let r = dm.get(&0).unwrap();
+        let counter = Arc::new(Counter::default());
+        let map = DashMap::with_memory_tracker(counter.clone());
 
-        assert_eq!(99, *r.value());
+        for i in 0..100 {
+            map.insert(i, vec![0; 10]);
+        }
+        map.insert(0, vec![0; 20]);
+        map.entry(1).or_insert_with(Vec::new);
+        map.remove(&2);
+        map.remove_if(&3, |_, v| v.len() == 10);
+        map.retain(|k, _| k % 2 == 0);
+
+        let twin = map.clone();
+        map.clear();
+        map.shrink_to_fit();
+        twin.clear();
+        twin.shrink_to_fit();
+
+        assert_eq!(counter.0.load(Ordering::Relaxed), 0);
     }
 
     #[test]
-    fn test_more_complex_values() {
-        #[derive(Hash, PartialEq, Debug, Clone)]
+    fn test_apply_batch_tracks_altered_values() {
+        use crate::batch::Op;
+        use crate::MemoryTracker;
+        use std::sync::atomic::{AtomicIsize, Ordering};
+        use std::sync::Arc;
+
+        #[derive(Default)]
+        struct Counter(AtomicIsize);
+
+        impl MemoryTracker<u32, Vec<u8>> for Counter {
+            fn entry_size(&self, _key: &u32, value: &Vec<u8>) -> usize {
+                value.len()
+            }
 
-        struct T0 {
-            s: String,
-            u: u8,
+            fn on_change(&self, delta: isize) {
+                self.0.fetch_add(delta, Ordering::Relaxed);
+            }
         }
 
-        let dm = DashMap::new();
+        let counter = Arc::new(Counter::default());
+        let map = DashMap::with_memory_tracker(counter.clone());
+        map.insert(1, vec![0; 10]);
+        map.insert(2, vec![0; 5]);
+        // Also counts the allocation of the shards, which the batch doesn't change.
+        let before = counter.0.load(Ordering::Relaxed);
+
+        map.apply_batch(vec![
+            Op::Alter(
+                1,
+                Box::new(|_, mut v: Vec<u8>| {
+                    v.resize(25, 0);
+                    v
+                }),
+            ),
+            Op::Alter(2, Box::new(|_, _| Vec::new())),
+        ]);
+        assert_eq!(counter.0.load(Ordering::Relaxed), before + 15 - 5);
+
+        map.clear();
+        map.shrink_to_fit();
+        assert_eq!(counter.0.load(Ordering::Relaxed), 0);
+    }
 
-        let range = 0..10;
+    #[test]
+    fn test_get_mut_same_shard() {
+        let map: DashMap<u32, u32> = DashMap::with_shard_amount(4);
 
-        for i in range {
-            let t = T0 {
-                s: i.to_string(),
-                u: i as u8,
-            };
+        for i in 0..32 {
+            map.insert(i, i);
+        }
 
-            dm.insert(i, t.clone());
+        let same: Vec<u32> = (0..32).filter(|k| map.same_shard(&0, k)).collect();
+        let other = (0..32).find(|k| !map.same_shard(&0, k)).unwrap();
+        let keys: Vec<&u32> = same.iter().collect();
 
-            assert_eq!(&t, dm.get(&i).unwrap().value());
+        let mut refs = map.get_mut_same_shard(&keys).unwrap();
+        for r in refs.iter_mut() {
+            *r.value_mut() += 100;
         }
+        assert!(map.try_get(&0).is_locked());
+        drop(refs);
+
+        assert!(same.iter().all(|k| *map.get(k).unwrap() == k + 100));
+        assert!(map.get_mut_same_shard(&[&0, &other]).is_none());
+        assert!(map.get_mut_same_shard(&[&0, &0]).is_none());
+        let missing = (32..).find(|k| map.same_shard(&0, k)).unwrap();
+        assert!(map.get_mut_same_shard(&[&0, &missing]).is_none());
+        assert!(map.get_mut_same_shard::<u32>(&[]).unwrap().is_empty());
     }
 
     #[test]
-    fn test_different_hashers_randomstate() {
-        let dm_hm_default: DashMap<u32, u32, RandomState> =
-            DashMap::with_hasher(RandomState::new());
+    fn test_tombstones() {
+        let map = DashMap::with_shard_amount(4);
 
-        for i in 0..10 {
-            dm_hm_default.insert(i, i);
+        for i in 0..64 {
+            map.insert_value(i, i);
+        }
+        let capacity = map.capacity();
 
-            assert_eq!(i, *dm_hm_default.get(&i).unwrap().value());
+        for _ in 0..10 {
+            for i in 0..64 {
+                assert_eq!(map.take_value(&i), Some(i));
+            }
+            for i in 0..64 {
+                assert_eq!(map.insert_value(i, i), None);
+            }
         }
+        assert_eq!(map.capacity(), capacity);
+
+        for i in 0..32 {
+            map.take_value(&i);
+        }
+        assert_eq!(map.compact(), 32);
+        assert_eq!(map.len(), 32);
+        assert_eq!(map.compact(), 0);
     }
 
     #[test]
-    fn test_map_view() {
-        let dm = DashMap::new();
-
-        let vegetables: [String; 4] = [
-            "Salad".to_string(),
-            "Beans".to_string(),
-            "Potato".to_string(),
-            "Tomato".to_string(),
-        ];
+    fn test_iter_available() {
+        let map: DashMap<u32, u32> = DashMap::with_shard_amount(8);
 
-        // Give it some values
-        dm.insert(0, "Banana".to_string());
-        dm.insert(4, "Pear".to_string());
-        dm.insert(9, "Potato".to_string());
-        dm.insert(12, "Chicken".to_string());
+        for i in 0..256 {
+            map.insert(i, i);
+        }
 
-        let potato_vegetableness = dm.view(&9, |_, v| vegetables.contains(v));
-        assert_eq!(potato_vegetableness, Some(true));
+        let writing = map.get_mut(&0).unwrap();
+        let shard_len = (0..256).filter(|k| map.same_shard(&0, k)).count();
 
-        let chicken_vegetableness = dm.view(&12, |_, v| vegetables.contains(v));
-        assert_eq!(chicken_vegetableness, Some(false));
+        let mut iter = map.iter_available();
+        assert_eq!(iter.by_ref().count(), 256 - shard_len);
+        assert_eq!(iter.skipped(), 1);
+        drop(writing);
 
-        let not_in_map = dm.view(&30, |_k, _v| false);
-        assert_eq!(not_in_map, None);
+        let reading = map.get(&0).unwrap();
+        assert_eq!(map.iter_available().count(), 256);
+        drop(reading);
     }
 
+    #[cfg(feature = "raw-api")]
     #[test]
-    fn test_try_get() {
-        {
-            let map = DashMap::new();
-            map.insert("Johnny", 21);
-
-            assert_eq!(*map.try_get("Johnny").unwrap(), 21);
+    fn test_ref_multi_guard_parts() {
+        use crate::mapref::multiple::{RefMulti, RefMutMulti};
 
-            let _result1_locking = map.get_mut("Johnny");
+        let map: DashMap<u32, u32> = DashMap::with_shard_amount(4);
 
-            let result2 = map.try_get("Johnny");
-            assert!(result2.is_locked());
+        for i in 0..64 {
+            map.insert(i, i);
         }
 
-        {
-            let map = DashMap::new();
-            map.insert("Johnny", 21);
+        let mut groups: Vec<Vec<RefMulti<'_, u32, u32>>> = Vec::new();
 
-            *map.try_get_mut("Johnny").unwrap() += 1;
-            assert_eq!(*map.get("Johnny").unwrap(), 22);
+        for r in map.iter() {
+            match groups.iter_mut().find(|group| group[0].same_guard(&r)) {
+                Some(group) => group.push(r),
+                None => groups.push(vec![r]),
+            }
+        }
+        assert_eq!(groups.len(), 4);
+
+        let done = groups.pop().unwrap();
+        let key = *done[0].key();
+        // SAFETY: the parts are put back together while the guard is still alive.
+        let (guard, k, v) = unsafe { done[0].clone().into_parts() };
+        assert!(guard.ptr_eq(&done[1].guard()));
+        let rebuilt = unsafe { RefMulti::from_parts(guard, k, v) };
+        assert_eq!(*rebuilt, key);
+
+        assert!(map.try_get_mut(&key).is_locked());
+        drop(rebuilt);
+        drop(done);
+        assert!(map.try_get_mut(&key).is_present());
+        assert!(map.try_get_mut(groups[0][0].key()).is_locked());
+        drop(groups);
+
+        let mut refs: Vec<_> = map.iter_mut().collect();
+        let r = refs.pop().unwrap();
+        let key = *r.key();
+        // SAFETY: the value is only written through while the guard is alive.
+        let (guard, k, v) = unsafe { r.into_parts() };
+        *v += 1000;
+        drop(refs);
+        let rebuilt = unsafe { RefMutMulti::from_parts(guard, k, v) };
+        drop(rebuilt);
+        assert_eq!(*map.get(&key).unwrap(), key + 1000);
+    }
 
-            let _result1_locking = map.get("Johnny");
+    #[test]
+    fn test_split_handles() {
+        let (reader, writer) = DashMap::new().split_handles();
+        let other = writer.clone();
+
+        writer.insert(1, "one");
+        other.insert(2, "two");
+        assert_eq!(reader.len(), 2);
+        assert_eq!(*reader.get(&2).unwrap(), "two");
+
+        let late = other.reader();
+        writer.remove(&1);
+        assert!(!late.contains_key(&1));
+
+        let mut visited = 0;
+        reader.clone().visit_entries(|_, _| visited += 1);
+        assert_eq!((visited, reader.iter().count()), (1, 1));
+    }
 
-            let result2 = map.try_get_mut("Johnny");
-            assert!(result2.is_locked());
-        }
+    #[test]
+    fn test_entry_if() {
+        let map: DashMap<u32, u32, RandomState> =
+            DashMap::with_hasher_and_shard_amount(RandomState::new(), 4);
+
+        let mut calls = 0;
+        assert!(map
+            .entry_if(1, |info| {
+                calls += 1;
+                assert_eq!(
+                    info,
+                    EntryGateInfo {
+                        len: 0,
+                        shard_len: 0,
+                        shard_capacity: 0
+                    }
+                );
+                true
+            })
+            .is_some());
+        // A vacant entry which isn't filled doesn't add anything.
+        assert_eq!(map.len(), 0);
+
+        let admitted = (0..8)
+            .filter(|&i| {
+                map.entry_if(i, |info| info.len() < 5)
+                    .map(|e| e.or_insert(i))
+                    .is_some()
+            })
+            .count();
+        assert_eq!((admitted, map.len()), (5, 5));
+
+        // Rejected keys don't grow the shard.
+        let idx = map.determine_shard(map.hash_u64(&100) as usize);
+        let capacity = map.shards[idx].read().capacity();
+        assert!(map.entry_if(100, |info| info.len() < 5).is_none());
+        assert_eq!(map.shards[idx].read().capacity(), capacity);
+
+        // Present keys are handed out without asking the gate.
+        *map.entry_if(0, |_| unreachable!()).unwrap().or_insert(0) += 1;
+        assert_eq!(*map.get(&0).unwrap(), 1);
+        assert_eq!(calls, 1);
+
+        let idx = map.determine_shard(map.hash_u64(&200) as usize);
+        let shard_len = map.shards[idx].read().len();
+        map.entry_if(200, |info| {
+            assert_eq!(info.shard_len(), shard_len);
+            info.shard_len() <= info.shard_capacity()
+        })
+        .unwrap()
+        .or_insert(0);
+        assert!(map.contains_key(&200));
     }
 
     #[test]
-    fn test_try_reserve() {
-        let mut map: DashMap<i32, i32> = DashMap::new();
-        // DashMap is empty and doesn't allocate memory
-        assert_eq!(map.capacity(), 0);
+    fn test_retain_clear_async() {
+        use crate::test_util::{block_on_counting, waker};
+        use std::future::Future;
+        use std::task::Context;
+        use std::time::Duration;
+
+        fn assert_send<T: Send>(_: &T) {}
+
+        let map: DashMap<u32, u32, RandomState> =
+            DashMap::with_hasher_and_shard_amount(RandomState::new(), 8);
+        for i in 0..10_000 {
+            map.insert(i, i);
+        }
 
-        map.try_reserve(10).unwrap();
+        let mut calls = 0;
+        let retain = map.retain_async(Duration::ZERO, |_, v| {
+            calls += 1;
+            *v % 4 == 0
+        });
+        assert_send(&retain);
+        let (progress, yields) = block_on_counting(retain);
+        assert_eq!(calls, 10_000);
+        assert_eq!((progress.examined(), progress.removed()), (10_000, 7_500));
+        assert_eq!(progress.kept(), map.len());
+        assert_eq!((progress.slices(), yields), (8, 7));
+
+        let (progress, yields) =
+            block_on_counting(map.retain_async(Duration::from_secs(60), |_, _| true));
+        assert_eq!((progress.kept(), progress.slices(), yields), (2_500, 1, 0));
+
+        // A locked shard is retried instead of blocked on.
+        let held = map.get(&0).unwrap();
+        let waker = waker();
+        let mut clear = Box::pin(map.clear_async(Duration::ZERO));
+        assert!(clear
+            .as_mut()
+            .poll(&mut Context::from_waker(&waker))
+            .is_pending());
+        assert!(map.contains_key(&0));
+        drop(held);
+
+        let (progress, yields) = block_on_counting(clear);
+        assert_eq!((progress.examined(), progress.removed()), (2_500, 2_500));
+        // The retry of the locked shard is a slice of its own.
+        assert_eq!(progress.slices(), yields + 2);
+        // Out of time after every check, which happens every 64 entries.
+        assert!(progress.slices() > 2_500 / 64);
+        assert!(map.is_empty());
+        assert_eq!(map.len_exact(), 0);
+    }
 
-        // And now map can hold at least 10 elements
-        assert!(map.capacity() >= 10);
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn test_metrics_every_op_path() {
+        use std::borrow::Cow;
+
+        let map = DashMap::<String, u32>::new();
+        map.insert("a".to_string(), 1);
+        map.insert_with("b".to_string(), || 2);
+        map.insert_with_cow(Cow::Borrowed("c"), 3);
+        map.insert_with_cow(Cow::<str>::Owned("d".to_string()), 4);
+
+        *map.get_mut("a").unwrap() += 1;
+        assert!(map.get_mut("z").is_none());
+        assert_eq!(*map.get("a").unwrap(), 2);
+        assert_eq!(*map.get_snapshot("b").unwrap().value(), 2);
+
+        map.remove("a");
+        map.remove_entry("b");
+        map.remove_if("c", |_, _| false);
+        map.remove_if_mut("d", |_, _| true);
+
+        // Not measured as operations.
+        map.entry("e".to_string()).or_insert(5);
+        map.try_get("e");
+
+        let total = map.metrics_snapshot().total();
+        assert_eq!(total.insert.count(), 4);
+        assert_eq!(total.get.count(), 4);
+        assert_eq!(total.remove.count(), 4);
+        assert_eq!(total.read_wait.count(), 2);
+        assert_eq!(total.write_wait.count(), 11);
     }
 
+    #[cfg(feature = "metrics")]
     #[test]
-    fn test_try_reserve_errors() {
-        let mut map: DashMap<i32, i32> = DashMap::new();
+    fn test_metrics_batch_lock_waits() {
+        use crate::batch::{BufferedWriter, Op};
 
-        match map.try_reserve(usize::MAX) {
-            Err(_) => {}
-            _ => panic!("should have raised CapacityOverflow error"),
-        }
+        let map = DashMap::<u32, u32>::with_shard_amount(4);
+        let shards_of = |keys: &[u32]| {
+            let ids: std::collections::HashSet<_> = keys.iter().map(|k| map.shard_id(k)).collect();
+            ids.len() as u64
+        };
+
+        let mut writer = BufferedWriter::new(&map, 100);
+        (0..10).for_each(|i| writer.insert(i, i));
+        writer.flush();
+        let flushed = shards_of(&(0..10).collect::<Vec<_>>());
+
+        map.apply_batch(vec![Op::Insert(1, 1), Op::Remove(2)]);
+        map.update_batch([(3, |v: &mut u32| *v += 1)]);
+
+        let total = map.metrics_snapshot().total();
+        assert_eq!(total.write_wait.count(), flushed + shards_of(&[1, 2]) + 1);
+        assert_eq!(total.insert.count(), 0);
     }
 }
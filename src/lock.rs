@@ -12,13 +12,18 @@ const ONE_WRITER: usize = !(READERS_PARKED | WRITERS_PARKED);
 
 pub struct RawRwLock {
     state: AtomicUsize,
+    #[cfg(feature = "blocking-detect")]
+    shard: usize,
+}
+
+/// Creates the lock of shard `idx` of a map.
+pub(crate) fn shard_lock<T>(idx: usize, value: T) -> RwLock<T> {
+    RwLock::const_new(RawRwLock::for_shard(idx), value)
 }
 
 unsafe impl lock_api::RawRwLock for RawRwLock {
     #[allow(clippy::declare_interior_mutable_const)]
-    const INIT: Self = Self {
-        state: AtomicUsize::new(0),
-    };
+    const INIT: Self = Self::for_shard(usize::MAX);
 
     type GuardMarker = lock_api::GuardSend;
 
@@ -31,12 +36,16 @@ unsafe impl lock_api::RawRwLock for RawRwLock {
 
     #[inline]
     fn lock_exclusive(&self) {
+        crate::audit::check(self, true);
+
         if self
             .state
             .compare_exchange_weak(0, ONE_WRITER, Ordering::Acquire, Ordering::Relaxed)
             .is_err()
         {
+            let timer = crate::blocking::Timer::start();
             self.lock_exclusive_slow();
+            crate::blocking::check(self, true, timer);
         }
     }
 
@@ -58,8 +67,12 @@ unsafe impl lock_api::RawRwLock for RawRwLock {
 
     #[inline]
     fn lock_shared(&self) {
+        crate::audit::check(self, false);
+
         if !self.try_lock_shared_fast() {
+            let timer = crate::blocking::Timer::start();
             self.lock_shared_slow();
+            crate::blocking::check(self, false, timer);
         }
     }
 
@@ -86,6 +99,33 @@ unsafe impl lock_api::RawRwLockDowngrade for RawRwLock {
 }
 
 impl RawRwLock {
+    const fn for_shard(_idx: usize) -> Self {
+        Self {
+            state: AtomicUsize::new(0),
+            #[cfg(feature = "blocking-detect")]
+            shard: _idx,
+        }
+    }
+
+    /// Returns the index of the shard this lock belongs to, if known.
+    #[cfg(feature = "blocking-detect")]
+    pub(crate) fn shard(&self) -> Option<usize> {
+        Some(self.shard).filter(|&shard| shard != usize::MAX)
+    }
+
+    /// Returns a snapshot of the number of readers and whether a writer holds the lock.
+    ///
+    /// The state may have changed by the time this returns.
+    pub(crate) fn holders(&self) -> (usize, bool) {
+        let state = self.state.load(Ordering::Relaxed) & ONE_WRITER;
+
+        if state == ONE_WRITER {
+            (0, true)
+        } else {
+            (state / ONE_READER, false)
+        }
+    }
+
     #[cold]
     fn lock_exclusive_slow(&self) {
         let mut acquire_with = 0;
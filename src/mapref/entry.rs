@@ -1,9 +1,16 @@
 use hashbrown::hash_table;
 
 use super::one::RefMut;
+use crate::layout::ShrinkShard;
 use crate::lock::RwLockWriteGuardDetached;
-use core::hash::Hash;
+use crate::tracker::{self, MemoryTracker};
+use crate::util;
+use crate::watch::{self, Notify};
+use crate::DashMap;
+use core::hash::{BuildHasher, Hash};
 use core::mem;
+use core::sync::atomic::AtomicUsize;
+use std::sync::Arc;
 
 pub enum Entry<'a, K, V> {
     Occupied(OccupiedEntry<'a, K, V>),
@@ -111,10 +118,22 @@ impl<'a, K: Eq + Hash, V> Entry<'a, K, V> {
     }
 }
 
+impl<'a, K: Eq + Hash, V> Entry<'a, K, Option<V>> {
+    /// Return a mutable reference to the element if it exists,
+    /// otherwise insert `None` and return a mutable reference to that.
+    pub fn or_insert_none(self) -> RefMut<'a, K, Option<V>> {
+        self.or_insert(None)
+    }
+}
+
 pub struct VacantEntry<'a, K, V> {
     shard: RwLockWriteGuardDetached<'a>,
     key: K,
     entry: hash_table::VacantEntry<'a, (K, V)>,
+    tracker: Option<&'a dyn MemoryTracker<K, V>>,
+    notify: Notify<'a>,
+    len: Option<&'a AtomicUsize>,
+    shrink: Option<&'a dyn ShrinkShard<K, V>>,
 }
 
 impl<'a, K: Eq + Hash, V> VacantEntry<'a, K, V> {
@@ -122,12 +141,28 @@ impl<'a, K: Eq + Hash, V> VacantEntry<'a, K, V> {
         shard: RwLockWriteGuardDetached<'a>,
         key: K,
         entry: hash_table::VacantEntry<'a, (K, V)>,
+        tracker: Option<&'a dyn MemoryTracker<K, V>>,
+        notify: Notify<'a>,
+        len: Option<&'a AtomicUsize>,
+        shrink: Option<&'a dyn ShrinkShard<K, V>>,
     ) -> Self {
-        Self { shard, key, entry }
+        Self {
+            shard,
+            key,
+            entry,
+            tracker,
+            notify,
+            len,
+            shrink,
+        }
     }
 
     pub fn insert(self, value: V) -> RefMut<'a, K, V> {
+        tracker::entry_added(self.tracker, &self.key, &value);
+
         let occupied = self.entry.insert((self.key, value));
+        util::count_inserted(self.len);
+        watch::key_inserted(self.notify);
 
         let (k, v) = occupied.into_mut();
 
@@ -139,8 +174,19 @@ impl<'a, K: Eq + Hash, V> VacantEntry<'a, K, V> {
     where
         K: Clone,
     {
+        tracker::entry_added(self.tracker, &self.key, &value);
+
         let entry = self.entry.insert((self.key.clone(), value));
-        OccupiedEntry::new(self.shard, self.key, entry)
+        util::count_inserted(self.len);
+        watch::key_inserted(self.notify);
+        OccupiedEntry::new(
+            self.shard,
+            self.key,
+            entry,
+            self.tracker,
+            self.len,
+            self.shrink,
+        )
     }
 
     pub fn into_key(self) -> K {
@@ -156,6 +202,9 @@ pub struct OccupiedEntry<'a, K, V> {
     shard: RwLockWriteGuardDetached<'a>,
     entry: hash_table::OccupiedEntry<'a, (K, V)>,
     key: K,
+    tracker: Option<&'a dyn MemoryTracker<K, V>>,
+    len: Option<&'a AtomicUsize>,
+    shrink: Option<&'a dyn ShrinkShard<K, V>>,
 }
 
 impl<'a, K: Eq + Hash, V> OccupiedEntry<'a, K, V> {
@@ -163,8 +212,18 @@ impl<'a, K: Eq + Hash, V> OccupiedEntry<'a, K, V> {
         shard: RwLockWriteGuardDetached<'a>,
         key: K,
         entry: hash_table::OccupiedEntry<'a, (K, V)>,
+        tracker: Option<&'a dyn MemoryTracker<K, V>>,
+        len: Option<&'a AtomicUsize>,
+        shrink: Option<&'a dyn ShrinkShard<K, V>>,
     ) -> Self {
-        Self { shard, entry, key }
+        Self {
+            shard,
+            entry,
+            key,
+            tracker,
+            len,
+            shrink,
+        }
     }
 
     pub fn get(&self) -> &V {
@@ -176,7 +235,11 @@ impl<'a, K: Eq + Hash, V> OccupiedEntry<'a, K, V> {
     }
 
     pub fn insert(&mut self, value: V) -> V {
-        mem::replace(self.get_mut(), value)
+        let old = mem::replace(self.get_mut(), value);
+        let (k, v) = self.entry.get();
+        tracker::entry_removed(self.tracker, k, &old);
+        tracker::entry_added(self.tracker, k, v);
+        old
     }
 
     pub fn into_ref(self) -> RefMut<'a, K, V> {
@@ -193,21 +256,300 @@ impl<'a, K: Eq + Hash, V> OccupiedEntry<'a, K, V> {
     }
 
     pub fn remove(self) -> V {
-        let ((_k, v), _) = self.entry.remove();
-        v
+        self.remove_entry().1
     }
 
     pub fn remove_entry(self) -> (K, V) {
-        let ((k, v), _) = self.entry.remove();
+        let ((k, v), vacant) = self.entry.remove();
+        util::entry_removed(
+            vacant.into_table(),
+            self.len,
+            self.tracker,
+            self.shrink,
+            (&k, &v),
+        );
         (k, v)
     }
 
     pub fn replace_entry(self, value: V) -> (K, V) {
-        let (k, v) = mem::replace(self.entry.into_mut(), (self.key, value));
+        let slot = self.entry.into_mut();
+        let (k, v) = mem::replace(slot, (self.key, value));
+        tracker::entry_removed(self.tracker, &k, &v);
+        tracker::entry_added(self.tracker, &slot.0, &slot.1);
+        (k, v)
+    }
+}
+
+/// An entry of [`DashMap::entry_cloned`](crate::DashMap::entry_cloned), which borrows its key
+/// and only clones it when a value is inserted into a vacant entry.
+pub enum EntryRef<'a, 'q, K, V> {
+    Occupied(OccupiedEntryRef<'a, K, V>),
+    Vacant(VacantEntryRef<'a, 'q, K, V>),
+}
+
+impl<'a, 'q, K: Eq + Hash + Clone, V> EntryRef<'a, 'q, K, V> {
+    /// Apply a function to the stored value if it exists.
+    pub fn and_modify(self, f: impl FnOnce(&mut V)) -> Self {
+        match self {
+            EntryRef::Occupied(mut entry) => {
+                f(entry.get_mut());
+
+                EntryRef::Occupied(entry)
+            }
+
+            EntryRef::Vacant(entry) => EntryRef::Vacant(entry),
+        }
+    }
+
+    /// Get the key of the entry.
+    pub fn key(&self) -> &K {
+        match *self {
+            EntryRef::Occupied(ref entry) => entry.key(),
+            EntryRef::Vacant(ref entry) => entry.key(),
+        }
+    }
+
+    /// Return a mutable reference to the element if it exists,
+    /// otherwise insert the default and return a mutable reference to that.
+    pub fn or_default(self) -> RefMut<'a, K, V>
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    /// Return a mutable reference to the element if it exists,
+    /// otherwise a provided value and return a mutable reference to that.
+    pub fn or_insert(self, value: V) -> RefMut<'a, K, V> {
+        self.or_insert_with(|| value)
+    }
+
+    /// Return a mutable reference to the element if it exists,
+    /// otherwise insert the result of a provided function and return a mutable reference to that.
+    pub fn or_insert_with(self, value: impl FnOnce() -> V) -> RefMut<'a, K, V> {
+        match self {
+            EntryRef::Occupied(entry) => entry.into_ref(),
+            EntryRef::Vacant(entry) => entry.insert(value()),
+        }
+    }
+
+    /// Sets the value of the entry, and returns a reference to the inserted value.
+    pub fn insert(self, value: V) -> RefMut<'a, K, V> {
+        match self {
+            EntryRef::Occupied(mut entry) => {
+                entry.insert(value);
+                entry.into_ref()
+            }
+            EntryRef::Vacant(entry) => entry.insert(value),
+        }
+    }
+}
+
+pub struct VacantEntryRef<'a, 'q, K, V> {
+    shard: RwLockWriteGuardDetached<'a>,
+    key: &'q K,
+    entry: hash_table::VacantEntry<'a, (K, V)>,
+    tracker: Option<&'a dyn MemoryTracker<K, V>>,
+    notify: Notify<'a>,
+    len: Option<&'a AtomicUsize>,
+}
+
+impl<'a, 'q, K: Eq + Hash + Clone, V> VacantEntryRef<'a, 'q, K, V> {
+    pub(crate) fn new(
+        shard: RwLockWriteGuardDetached<'a>,
+        key: &'q K,
+        entry: hash_table::VacantEntry<'a, (K, V)>,
+        tracker: Option<&'a dyn MemoryTracker<K, V>>,
+        notify: Notify<'a>,
+        len: Option<&'a AtomicUsize>,
+    ) -> Self {
+        Self {
+            shard,
+            key,
+            entry,
+            tracker,
+            notify,
+            len,
+        }
+    }
+
+    /// Clones the key and inserts it with the provided value.
+    pub fn insert(self, value: V) -> RefMut<'a, K, V> {
+        tracker::entry_added(self.tracker, self.key, &value);
+
+        let occupied = self.entry.insert((self.key.clone(), value));
+        util::count_inserted(self.len);
+        watch::key_inserted(self.notify);
+
+        let (k, v) = occupied.into_mut();
+
+        RefMut::new(self.shard, k, v)
+    }
+
+    pub fn key(&self) -> &'q K {
+        self.key
+    }
+}
+
+pub struct OccupiedEntryRef<'a, K, V> {
+    shard: RwLockWriteGuardDetached<'a>,
+    entry: hash_table::OccupiedEntry<'a, (K, V)>,
+    tracker: Option<&'a dyn MemoryTracker<K, V>>,
+    len: Option<&'a AtomicUsize>,
+    shrink: Option<&'a dyn ShrinkShard<K, V>>,
+}
+
+impl<'a, K: Eq + Hash, V> OccupiedEntryRef<'a, K, V> {
+    pub(crate) fn new(
+        shard: RwLockWriteGuardDetached<'a>,
+        entry: hash_table::OccupiedEntry<'a, (K, V)>,
+        tracker: Option<&'a dyn MemoryTracker<K, V>>,
+        len: Option<&'a AtomicUsize>,
+        shrink: Option<&'a dyn ShrinkShard<K, V>>,
+    ) -> Self {
+        Self {
+            shard,
+            entry,
+            tracker,
+            len,
+            shrink,
+        }
+    }
+
+    pub fn get(&self) -> &V {
+        &self.entry.get().1
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        &mut self.entry.get_mut().1
+    }
+
+    pub fn insert(&mut self, value: V) -> V {
+        let old = mem::replace(self.get_mut(), value);
+        let (k, v) = self.entry.get();
+        tracker::entry_removed(self.tracker, k, &old);
+        tracker::entry_added(self.tracker, k, v);
+        old
+    }
+
+    pub fn into_ref(self) -> RefMut<'a, K, V> {
+        let (k, v) = self.entry.into_mut();
+        RefMut::new(self.shard, k, v)
+    }
+
+    pub fn key(&self) -> &K {
+        &self.entry.get().0
+    }
+
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+
+    pub fn remove_entry(self) -> (K, V) {
+        let ((k, v), vacant) = self.entry.remove();
+        util::entry_removed(
+            vacant.into_table(),
+            self.len,
+            self.tracker,
+            self.shrink,
+            (&k, &v),
+        );
         (k, v)
     }
 }
 
+/// An entry of a map behind an [`Arc`], returned by [`DashMap::entry_owned`].
+///
+/// It owns a clone of the `Arc` and the key instead of borrowing them, so it can be moved into a
+/// spawned task, for example to insert a value once an asynchronous fetch completes. No lock is
+/// held by the entry itself: every method locks the key's shard while it runs, so the entry may be
+/// occupied or vacant by the time it is used, no matter what it was when it was created. Use
+/// [`with_entry`](Self::with_entry) to inspect and modify it under a single lock.
+pub struct OwnedEntry<K, V, S> {
+    map: Arc<DashMap<K, V, S>>,
+    key: K,
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher> OwnedEntry<K, V, S> {
+    pub(crate) fn new(map: Arc<DashMap<K, V, S>>, key: K) -> Self {
+        Self { map, key }
+    }
+
+    /// Get the key of the entry.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Get the map of the entry.
+    pub fn map(&self) -> &Arc<DashMap<K, V, S>> {
+        &self.map
+    }
+
+    /// Into the key of the entry.
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    /// Checks if the key is currently present in the map.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    pub fn is_occupied(&self) -> bool {
+        self.map.contains_key(&self.key)
+    }
+
+    /// Returns a clone of the current value of the key, if it is present.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    pub fn get_cloned(&self) -> Option<V>
+    where
+        V: Clone,
+    {
+        self.map.view(&self.key, |_, v| v.clone())
+    }
+
+    /// Locks the key's shard and calls `f` with the regular entry of the key.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    pub fn with_entry<R>(self, f: impl FnOnce(Entry<'_, K, V>) -> R) -> R {
+        f(self.map.entry(self.key))
+    }
+
+    /// Inserts a value, returning the old value of the key if there was one.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    pub fn insert(self, value: V) -> Option<V> {
+        self.map.insert(self.key, value)
+    }
+
+    /// Inserts a value if the key is vacant. Returns `true` if the value was inserted.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    pub fn or_insert(self, value: V) -> bool {
+        self.or_insert_with(|| value)
+    }
+
+    /// Inserts the result of a provided function if the key is vacant.
+    /// Returns `true` if the function was called and its result inserted.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    pub fn or_insert_with(self, value: impl FnOnce() -> V) -> bool {
+        self.with_entry(|entry| match entry {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(entry) => {
+                entry.insert(value());
+                true
+            }
+        })
+    }
+
+    /// Removes the key, returning its value if it was present.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    pub fn remove(self) -> Option<V> {
+        self.map.remove(&self.key).map(|(_, v)| v)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::DashMap;
@@ -249,4 +591,43 @@ mod tests {
 
         assert_eq!(*map.get(&1).unwrap(), 2);
     }
+
+    #[test]
+    fn test_entry_owned() {
+        let map: Arc<DashMap<u32, String>> = Arc::new(DashMap::new());
+
+        let entry = map.entry_owned(1);
+        assert!(!entry.is_occupied());
+
+        std::thread::spawn(move || assert!(entry.or_insert("fetched".to_owned())))
+            .join()
+            .unwrap();
+
+        let entry = map.entry_owned(1);
+        assert_eq!(entry.get_cloned().as_deref(), Some("fetched"));
+        assert!(!map.entry_owned(1).or_insert_with(|| unreachable!()));
+
+        let key = entry.with_entry(|entry| *entry.and_modify(|v| v.push('!')).key());
+        assert_eq!(key, 1);
+        assert_eq!(map.entry_owned(1).remove().as_deref(), Some("fetched!"));
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn test_entry_cloned() {
+        let map: DashMap<String, u32> = DashMap::new();
+        let key = "a".to_owned();
+
+        assert!(matches!(map.entry_cloned(&key), EntryRef::Vacant(_)));
+        *map.entry_cloned(&key).or_insert(1) += 1;
+        *map.entry_cloned(&key).or_insert(10) += 1;
+        assert_eq!(*map.get("a").unwrap(), 3);
+
+        match map.entry_cloned(&key) {
+            EntryRef::Occupied(entry) => assert_eq!(entry.remove_entry(), (key.clone(), 3)),
+            EntryRef::Vacant(_) => panic!("expected an occupied entry"),
+        }
+
+        assert!(map.is_empty());
+    }
 }
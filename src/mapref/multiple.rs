@@ -1,4 +1,6 @@
 use crate::lock::{RwLockReadGuardDetached, RwLockWriteGuardDetached};
+#[cfg(feature = "raw-api")]
+use core::fmt;
 use core::hash::Hash;
 use core::ops::{Deref, DerefMut};
 use std::sync::Arc;
@@ -29,6 +31,56 @@ impl<'a, K: Eq + Hash, V> RefMulti<'a, K, V> {
     pub fn pair(&self) -> (&K, &V) {
         (self.k, self.v)
     }
+
+    pub(crate) fn into_raw_parts(self) -> (Arc<RwLockReadGuardDetached<'a>>, &'a K, &'a V) {
+        (self._guard, self.k, self.v)
+    }
+}
+
+#[cfg(feature = "raw-api")]
+impl<'a, K: Eq + Hash, V> RefMulti<'a, K, V> {
+    /// Returns a handle to the read lock of the shard holding the entry,
+    /// which keeps the shard locked for as long as it or any reference from that shard lives.
+    pub fn guard(&self) -> ShardReadGuard<'a> {
+        ShardReadGuard(self._guard.clone())
+    }
+
+    /// Returns `true` if both references are kept alive by the same lock of the same shard.
+    ///
+    /// Grouping references this way allows dropping all of them from one shard, and with them its lock,
+    /// while references from other shards are still in use.
+    pub fn same_guard(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self._guard, &other._guard)
+    }
+
+    /// Splits the reference into the lock of its shard and the key and value it protects.
+    ///
+    /// # Safety
+    ///
+    /// The key and value must not be used after the guard, and every clone of it, was dropped.
+    pub unsafe fn into_parts(self) -> (ShardReadGuard<'a>, &'a K, &'a V) {
+        (ShardReadGuard(self._guard), self.k, self.v)
+    }
+
+    /// Creates a reference from the lock of a shard and a key and value stored in that shard,
+    /// typically obtained from [`into_parts`](Self::into_parts).
+    ///
+    /// # Safety
+    ///
+    /// The key and value must be stored in the shard locked by the guard.
+    pub unsafe fn from_parts(guard: ShardReadGuard<'a>, k: &'a K, v: &'a V) -> Self {
+        Self::new(guard.0, k, v)
+    }
+}
+
+impl<'a, K, V> Clone for RefMulti<'a, K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            _guard: self._guard.clone(),
+            k: self.k,
+            v: self.v,
+        }
+    }
 }
 
 impl<'a, K: Eq + Hash, V> Deref for RefMulti<'a, K, V> {
@@ -75,6 +127,40 @@ impl<'a, K: Eq + Hash, V> RefMutMulti<'a, K, V> {
     }
 }
 
+#[cfg(feature = "raw-api")]
+impl<'a, K: Eq + Hash, V> RefMutMulti<'a, K, V> {
+    /// Returns a handle to the write lock of the shard holding the entry,
+    /// which keeps the shard locked for as long as it or any reference from that shard lives.
+    pub fn guard(&self) -> ShardWriteGuard<'a> {
+        ShardWriteGuard(self._guard.clone())
+    }
+
+    /// Returns `true` if both references are kept alive by the same lock of the same shard.
+    pub fn same_guard(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self._guard, &other._guard)
+    }
+
+    /// Splits the reference into the lock of its shard and the key and value it protects.
+    ///
+    /// # Safety
+    ///
+    /// The key and value must not be used after the guard, and every clone of it, was dropped.
+    pub unsafe fn into_parts(self) -> (ShardWriteGuard<'a>, &'a K, &'a mut V) {
+        (ShardWriteGuard(self._guard), self.k, self.v)
+    }
+
+    /// Creates a reference from the lock of a shard and a key and value stored in that shard,
+    /// typically obtained from [`into_parts`](Self::into_parts).
+    ///
+    /// # Safety
+    ///
+    /// The key and value must be stored in the shard locked by the guard,
+    /// and no other reference to the value may exist.
+    pub unsafe fn from_parts(guard: ShardWriteGuard<'a>, k: &'a K, v: &'a mut V) -> Self {
+        Self::new(guard.0, k, v)
+    }
+}
+
 impl<'a, K: Eq + Hash, V> Deref for RefMutMulti<'a, K, V> {
     type Target = V;
 
@@ -88,3 +174,47 @@ impl<'a, K: Eq + Hash, V> DerefMut for RefMutMulti<'a, K, V> {
         self.value_mut()
     }
 }
+
+/// A shared read lock of a shard, held by every [`RefMulti`] an iterator yielded from that shard.
+///
+/// The shard is unlocked once the last clone of the guard and the last reference from the shard are dropped.
+#[cfg(feature = "raw-api")]
+#[derive(Clone)]
+pub struct ShardReadGuard<'a>(Arc<RwLockReadGuardDetached<'a>>);
+
+#[cfg(feature = "raw-api")]
+impl ShardReadGuard<'_> {
+    /// Returns `true` if both guards hold the same lock.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+#[cfg(feature = "raw-api")]
+impl fmt::Debug for ShardReadGuard<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShardReadGuard").finish_non_exhaustive()
+    }
+}
+
+/// A shared write lock of a shard, held by every [`RefMutMulti`] an iterator yielded from that shard.
+///
+/// The shard is unlocked once the last clone of the guard and the last reference from the shard are dropped.
+#[cfg(feature = "raw-api")]
+#[derive(Clone)]
+pub struct ShardWriteGuard<'a>(Arc<RwLockWriteGuardDetached<'a>>);
+
+#[cfg(feature = "raw-api")]
+impl ShardWriteGuard<'_> {
+    /// Returns `true` if both guards hold the same lock.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+#[cfg(feature = "raw-api")]
+impl fmt::Debug for ShardWriteGuard<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShardWriteGuard").finish_non_exhaustive()
+    }
+}
@@ -1,7 +1,9 @@
 use crate::lock::{RwLockReadGuardDetached, RwLockWriteGuardDetached};
 use core::hash::Hash;
+use core::mem;
 use core::ops::{Deref, DerefMut};
 use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
 
 pub struct Ref<'a, K, V> {
     _guard: RwLockReadGuardDetached<'a>,
@@ -30,6 +32,10 @@ impl<'a, K: Eq + Hash, V> Ref<'a, K, V> {
         (self.k, self.v)
     }
 
+    pub(crate) fn into_parts(self) -> (RwLockReadGuardDetached<'a>, &'a K, &'a V) {
+        (self._guard, self.k, self.v)
+    }
+
     pub fn map<F, T>(self, f: F) -> MappedRef<'a, K, T>
     where
         F: FnOnce(&V) -> &T,
@@ -55,6 +61,32 @@ impl<'a, K: Eq + Hash, V> Ref<'a, K, V> {
             Err(self)
         }
     }
+
+    /// Splits the reference into its key and value halves, which share the read guard.
+    /// The entry stays locked for reading until both halves are dropped.
+    pub fn split(self) -> (RefPart<'a, K>, RefPart<'a, V>) {
+        let guard = Arc::new(self._guard);
+        (
+            RefPart {
+                _guard: guard.clone(),
+                v: self.k,
+            },
+            RefPart {
+                _guard: guard,
+                v: self.v,
+            },
+        )
+    }
+
+    /// Converts the reference into one which can be cloned, for example to hand it to several threads.
+    /// The entry stays locked for reading until the last clone is dropped.
+    pub fn into_shared(self) -> SharedRef<'a, K, V> {
+        SharedRef {
+            _guard: Arc::new(self._guard),
+            k: self.k,
+            v: self.v,
+        }
+    }
 }
 
 impl<'a, K: Eq + Hash + Debug, V: Debug> Debug for Ref<'a, K, V> {
@@ -74,6 +106,137 @@ impl<'a, K: Eq + Hash, V> Deref for Ref<'a, K, V> {
     }
 }
 
+/// A reference to an entry which can be cloned, produced by [`Ref::into_shared`].
+pub struct SharedRef<'a, K, V> {
+    _guard: Arc<RwLockReadGuardDetached<'a>>,
+    k: &'a K,
+    v: &'a V,
+}
+
+impl<'a, K: Eq + Hash, V> SharedRef<'a, K, V> {
+    pub fn key(&self) -> &K {
+        self.pair().0
+    }
+
+    pub fn value(&self) -> &V {
+        self.pair().1
+    }
+
+    pub fn pair(&self) -> (&K, &V) {
+        (self.k, self.v)
+    }
+}
+
+impl<'a, K, V> Clone for SharedRef<'a, K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            _guard: self._guard.clone(),
+            k: self.k,
+            v: self.v,
+        }
+    }
+}
+
+impl<'a, K: Eq + Hash + Debug, V: Debug> Debug for SharedRef<'a, K, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SharedRef")
+            .field("k", &self.k)
+            .field("v", &self.v)
+            .finish()
+    }
+}
+
+impl<'a, K: Eq + Hash, V> Deref for SharedRef<'a, K, V> {
+    type Target = V;
+
+    fn deref(&self) -> &V {
+        self.value()
+    }
+}
+
+/// One half of a [`Ref`] produced by [`Ref::split`].
+pub struct RefPart<'a, T> {
+    _guard: Arc<RwLockReadGuardDetached<'a>>,
+    v: &'a T,
+}
+
+impl<'a, T> RefPart<'a, T> {
+    pub fn value(&self) -> &T {
+        self.v
+    }
+}
+
+impl<'a, T> Clone for RefPart<'a, T> {
+    fn clone(&self) -> Self {
+        Self {
+            _guard: self._guard.clone(),
+            v: self.v,
+        }
+    }
+}
+
+impl<'a, T: Debug> Debug for RefPart<'a, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RefPart").field("v", &self.v).finish()
+    }
+}
+
+impl<'a, T> Deref for RefPart<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value()
+    }
+}
+
+/// An owned copy of an entry, produced by [`DashMap::get_snapshot`](crate::DashMap::get_snapshot).
+///
+/// Unlike a [`Ref`], it holds no lock on the map, so it can be kept across `.await` points
+/// and while the map is modified.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EntrySnapshot<K, V> {
+    k: K,
+    v: V,
+}
+
+impl<K, V> EntrySnapshot<K, V> {
+    pub(crate) fn new(k: K, v: V) -> Self {
+        Self { k, v }
+    }
+
+    pub fn key(&self) -> &K {
+        &self.k
+    }
+
+    pub fn value(&self) -> &V {
+        &self.v
+    }
+
+    pub fn pair(&self) -> (&K, &V) {
+        (&self.k, &self.v)
+    }
+
+    pub fn into_key(self) -> K {
+        self.k
+    }
+
+    pub fn into_value(self) -> V {
+        self.v
+    }
+
+    pub fn into_pair(self) -> (K, V) {
+        (self.k, self.v)
+    }
+}
+
+impl<K, V> Deref for EntrySnapshot<K, V> {
+    type Target = V;
+
+    fn deref(&self) -> &V {
+        self.value()
+    }
+}
+
 pub struct RefMut<'a, K, V> {
     guard: RwLockWriteGuardDetached<'a>,
     k: &'a K,
@@ -105,6 +268,19 @@ impl<'a, K: Eq + Hash, V> RefMut<'a, K, V> {
         (self.k, self.v)
     }
 
+    /// Replaces the value, returning the old one, without releasing the lock.
+    pub fn replace(&mut self, value: V) -> V {
+        mem::replace(self.v, value)
+    }
+
+    /// Takes the value, leaving `V::default()` in its place, without releasing the lock.
+    pub fn take(&mut self) -> V
+    where
+        V: Default,
+    {
+        mem::take(self.v)
+    }
+
     pub fn downgrade(self) -> Ref<'a, K, V> {
         Ref::new(
             unsafe { RwLockWriteGuardDetached::downgrade(self.guard) },
@@ -113,6 +289,15 @@ impl<'a, K: Eq + Hash, V> RefMut<'a, K, V> {
         )
     }
 
+    /// Downgrades the write lock to a read lock and maps the value,
+    /// without releasing the lock in between or looking the key up again.
+    pub fn downgrade_map<F, T>(self, f: F) -> MappedRef<'a, K, T>
+    where
+        F: FnOnce(&V) -> &T,
+    {
+        self.downgrade().map(f)
+    }
+
     pub fn map<F, T>(self, f: F) -> MappedRefMut<'a, K, T>
     where
         F: FnOnce(&mut V) -> &mut T,
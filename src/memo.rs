@@ -0,0 +1,277 @@
+//! Memoization of expensive computations shared between threads.
+
+use crate::mapref::entry::Entry;
+use crate::DashMap;
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::{BuildHasher, Hash};
+use core::time::Duration;
+use once_cell::sync::OnceCell;
+use std::collections::hash_map::RandomState;
+use std::sync::Arc;
+use std::time::Instant;
+
+type Slot<V> = Arc<OnceCell<(V, Instant)>>;
+
+/// A cache of computed values, which computes the value of every key only once at a time.
+///
+/// When several threads ask for the value of the same missing key, one of them computes it
+/// while the others wait for its result, and nothing is locked while the value is computed.
+/// If the computation panics or fails, the next caller computes the value again.
+/// With a time to live, values are computed again once they are older than it.
+///
+/// # Examples
+///
+/// ```
+/// use dashmap::memo::Memo;
+/// use std::time::Duration;
+///
+/// let lengths = Memo::with_ttl(Duration::from_secs(60));
+///
+/// let len = lengths.get_or_compute("https://example.com", || 1256);
+/// assert_eq!(len, 1256);
+///
+/// // The value is cached, so this doesn't compute it again.
+/// assert_eq!(lengths.get_or_compute("https://example.com", || unreachable!()), 1256);
+/// ```
+pub struct Memo<K, V, S = RandomState> {
+    map: DashMap<K, Slot<V>, S>,
+    ttl: Option<Duration>,
+}
+
+impl<K: Eq + Hash, V: Clone> Memo<K, V, RandomState> {
+    /// Creates a cache whose values never expire.
+    pub fn new() -> Self {
+        Self::with_hasher(RandomState::default())
+    }
+
+    /// Creates a cache whose values are computed again once they are older than `ttl`.
+    pub fn with_ttl(ttl: Duration) -> Self {
+        Self::with_ttl_and_hasher(ttl, RandomState::default())
+    }
+}
+
+impl<K: Eq + Hash, V: Clone> Default for Memo<K, V, RandomState> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Eq + Hash, V: Clone, S: BuildHasher> Memo<K, V, S> {
+    /// Creates a cache whose values never expire, using the provided hasher.
+    pub fn with_hasher(hasher: S) -> Self {
+        Self {
+            map: DashMap::with_hasher(hasher),
+            ttl: None,
+        }
+    }
+
+    /// Creates a cache whose values are computed again once they are older than `ttl`, using the provided hasher.
+    pub fn with_ttl_and_hasher(ttl: Duration, hasher: S) -> Self {
+        Self {
+            map: DashMap::with_hasher(hasher),
+            ttl: Some(ttl),
+        }
+    }
+
+    /// Returns the time to live of the values, if they expire.
+    pub fn ttl(&self) -> Option<Duration> {
+        self.ttl
+    }
+
+    fn is_fresh(&self, slot: &Slot<V>) -> bool {
+        match (slot.get(), self.ttl) {
+            (Some((_, computed)), Some(ttl)) => computed.elapsed() < ttl,
+            // A value which is still being computed doesn't expire.
+            _ => true,
+        }
+    }
+
+    /// Returns the slot of a key, replacing it if its value expired.
+    fn slot(&self, key: K) -> Slot<V> {
+        // A fresh slot only needs the read lock, so threads reading a warm cache don't contend.
+        if let Some(slot) = self.map.get(&key) {
+            if self.is_fresh(&slot) {
+                return slot.clone();
+            }
+        }
+
+        match self.map.entry(key) {
+            Entry::Occupied(mut entry) => {
+                if !self.is_fresh(entry.get()) {
+                    entry.insert(Arc::default());
+                }
+
+                entry.get().clone()
+            }
+            Entry::Vacant(entry) => entry.insert(Arc::default()).clone(),
+        }
+    }
+
+    /// Returns the value of a key, computing it with `f` if it is not cached or expired.
+    ///
+    /// If another thread is already computing the value, this waits for its result instead of calling `f`.
+    ///
+    /// **Locking behaviour:** May deadlock if `f` computes the value of the same key from the same cache.
+    pub fn get_or_compute(&self, key: K, f: impl FnOnce() -> V) -> V {
+        let slot = self.slot(key);
+        let (value, _) = slot.get_or_init(|| (f(), Instant::now()));
+        value.clone()
+    }
+
+    /// Like [`get_or_compute`](Self::get_or_compute), but for computations which can fail.
+    ///
+    /// Errors are not cached, so the next caller computes the value again.
+    ///
+    /// **Locking behaviour:** May deadlock if `f` computes the value of the same key from the same cache.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::memo::Memo;
+    ///
+    /// let ports: Memo<&str, u16> = Memo::new();
+    /// assert!(ports.try_get_or_compute("http", || "not a port".parse()).is_err());
+    /// assert_eq!(ports.try_get_or_compute("http", || "80".parse()), Ok(80));
+    /// ```
+    pub fn try_get_or_compute<E>(&self, key: K, f: impl FnOnce() -> Result<V, E>) -> Result<V, E> {
+        let slot = self.slot(key);
+        let (value, _) = slot.get_or_try_init(|| f().map(|value| (value, Instant::now())))?;
+        Ok(value.clone())
+    }
+
+    /// Returns the cached value of a key, if it was computed and didn't expire.
+    pub fn get<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let slot = self.map.get(key)?;
+
+        if !self.is_fresh(&slot) {
+            return None;
+        }
+
+        slot.get().map(|(value, _)| value.clone())
+    }
+
+    /// Removes the cached value of a key, so that it is computed again on the next call.
+    ///
+    /// Threads already waiting for the value of the key still receive it.
+    pub fn invalidate<Q>(&self, key: &Q)
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.remove(key);
+    }
+
+    /// Removes the values which expired, returning how many were removed.
+    pub fn purge_expired(&self) -> usize {
+        self.map
+            .retain_counted(|_, slot| self.is_fresh(slot))
+            .removed()
+    }
+
+    /// Removes all cached values.
+    pub fn clear(&self) {
+        self.map.clear();
+    }
+
+    /// Returns the number of cached keys, including the ones whose values are being computed.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if no keys are cached.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+impl<K: Eq + Hash + fmt::Debug, V: fmt::Debug, S: BuildHasher> fmt::Debug for Memo<K, V, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut map = f.debug_map();
+
+        for r in self.map.iter() {
+            if let Some((value, _)) = r.value().get() {
+                map.entry(r.key(), value);
+            }
+        }
+
+        map.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Memo;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Barrier;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_memo_single_flight() {
+        let memo = Memo::new();
+        let computed = AtomicUsize::new(0);
+        let barrier = Barrier::new(8);
+
+        thread::scope(|s| {
+            for _ in 0..8 {
+                let (memo, computed, barrier) = (&memo, &computed, &barrier);
+
+                s.spawn(move || {
+                    barrier.wait();
+
+                    let value = memo.get_or_compute(1, || {
+                        computed.fetch_add(1, Ordering::SeqCst);
+                        thread::sleep(Duration::from_millis(20));
+                        "one"
+                    });
+                    assert_eq!(value, "one");
+                });
+            }
+        });
+
+        assert_eq!(computed.load(Ordering::SeqCst), 1);
+        assert_eq!(memo.get(&1), Some("one"));
+
+        memo.invalidate(&1);
+        assert!(memo.is_empty());
+        assert_eq!(memo.get_or_compute(1, || "uno"), "uno");
+    }
+
+    #[test]
+    fn test_memo_hit_takes_read_lock() {
+        let memo = Memo::new();
+        assert_eq!(memo.get_or_compute(1, || "one"), "one");
+
+        // Taking the write lock here would deadlock with the held read lock.
+        let held = memo.map.get(&1).unwrap();
+        assert_eq!(memo.get_or_compute(1, || unreachable!()), "one");
+        assert_eq!(memo.try_get_or_compute(1, || Err::<_, ()>(())), Ok("one"));
+        drop(held);
+    }
+
+    #[test]
+    fn test_memo_expiry() {
+        // Only waits for values to expire, since a loaded machine can make them expire at any point.
+        fn wait_expired(memo: &Memo<u32, &str>, key: u32) {
+            while memo.get(&key).is_some() {
+                thread::sleep(Duration::from_millis(1));
+            }
+        }
+
+        let memo = Memo::with_ttl(Duration::from_millis(1));
+
+        assert_eq!(memo.get_or_compute(1, || "one"), "one");
+        wait_expired(&memo, 1);
+        assert_eq!(memo.get_or_compute(1, || "uno"), "uno");
+
+        wait_expired(&memo, 1);
+        assert_eq!(memo.len(), 1);
+        assert_eq!(memo.purge_expired(), 1);
+        assert!(memo.is_empty());
+    }
+}
@@ -0,0 +1,308 @@
+//! Latency histograms of map operations, recorded with the `metrics` feature.
+//!
+//! Every shard keeps a histogram per operation with power-of-two nanosecond buckets,
+//! updated with a single relaxed atomic increment. Without the feature, everything here
+//! compiles to nothing.
+
+use cfg_if::cfg_if;
+
+#[cfg(feature = "metrics")]
+pub use snapshot::{HistogramSnapshot, MetricsSnapshot, ShardMetricsSnapshot};
+
+/// The operations and lock waits which are timed.
+///
+/// Nothing records how long a lock is held on its own. Only `Insert` and `Remove` include the
+/// time the lock is held, since their operations release it before returning; `Get` stops when
+/// the reference is handed out, and the time a caller keeps a guard is not measured.
+#[derive(Clone, Copy)]
+pub(crate) enum Kind {
+    /// A lookup, from starting to lock the shard until the reference or copy is handed out.
+    Get,
+    /// An insertion, from starting to lock the shard until it is unlocked again.
+    Insert,
+    /// A removal, from starting to lock the shard until it is unlocked again.
+    Remove,
+    /// Waiting to acquire a read lock.
+    ReadWait,
+    /// Waiting to acquire a write lock.
+    WriteWait,
+}
+
+cfg_if! {
+    if #[cfg(feature = "metrics")] {
+        use core::sync::atomic::{AtomicU64, Ordering};
+        use core::time::Duration;
+        use crossbeam_utils::CachePadded;
+        use snapshot::BUCKETS;
+        use std::time::Instant;
+
+        struct Histogram {
+            buckets: [AtomicU64; BUCKETS],
+        }
+
+        impl Histogram {
+            fn new() -> Self {
+                Self {
+                    buckets: core::array::from_fn(|_| AtomicU64::new(0)),
+                }
+            }
+
+            fn record(&self, elapsed: Duration) {
+                let nanos = u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX);
+                let bucket = (u64::BITS - 1 - (nanos | 1).leading_zeros()) as usize;
+                self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+            }
+
+            fn snapshot(&self) -> HistogramSnapshot {
+                HistogramSnapshot {
+                    buckets: core::array::from_fn(|i| self.buckets[i].load(Ordering::Relaxed)),
+                }
+            }
+        }
+
+        struct ShardMetrics {
+            histograms: [Histogram; 5],
+        }
+
+        /// The histograms of every shard of a map.
+        pub(crate) struct Metrics {
+            shards: Box<[CachePadded<ShardMetrics>]>,
+        }
+
+        impl Metrics {
+            pub(crate) fn new(shard_amount: usize) -> Self {
+                let shards = (0..shard_amount)
+                    .map(|_| CachePadded::new(ShardMetrics {
+                        histograms: core::array::from_fn(|_| Histogram::new()),
+                    }))
+                    .collect();
+
+                Self { shards }
+            }
+
+            #[inline]
+            pub(crate) fn record(&self, idx: usize, kind: Kind, timer: Timer) {
+                self.shards[idx].histograms[kind as usize].record(timer.0.elapsed());
+            }
+
+            pub(crate) fn snapshot(&self) -> MetricsSnapshot {
+                let shards = self
+                    .shards
+                    .iter()
+                    .map(|shard| {
+                        let [get, insert, remove, read_wait, write_wait] =
+                            &shard.histograms;
+
+                        ShardMetricsSnapshot {
+                            get: get.snapshot(),
+                            insert: insert.snapshot(),
+                            remove: remove.snapshot(),
+                            read_wait: read_wait.snapshot(),
+                            write_wait: write_wait.snapshot(),
+                        }
+                    })
+                    .collect();
+
+                MetricsSnapshot { shards }
+            }
+        }
+
+        /// The start of a timed operation.
+        #[derive(Clone, Copy)]
+        pub(crate) struct Timer(Instant);
+
+        impl Timer {
+            #[inline]
+            pub(crate) fn start() -> Self {
+                Self(Instant::now())
+            }
+        }
+    } else {
+        pub(crate) struct Metrics;
+
+        impl Metrics {
+            #[inline(always)]
+            pub(crate) fn new(_shard_amount: usize) -> Self {
+                Self
+            }
+
+            #[inline(always)]
+            pub(crate) fn record(&self, _idx: usize, _kind: Kind, _timer: Timer) {}
+        }
+
+        #[derive(Clone, Copy)]
+        pub(crate) struct Timer;
+
+        impl Timer {
+            #[inline(always)]
+            pub(crate) fn start() -> Self {
+                Self
+            }
+        }
+    }
+}
+
+#[cfg(feature = "metrics")]
+mod snapshot {
+    use core::time::Duration;
+
+    pub(super) const BUCKETS: usize = 64;
+
+    /// A point-in-time copy of a latency histogram.
+    ///
+    /// Bucket `i` counts the samples of `2^i` up to `2^(i + 1)` nanoseconds,
+    /// except for bucket 0, which also counts samples shorter than a nanosecond.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct HistogramSnapshot {
+        pub(super) buckets: [u64; BUCKETS],
+    }
+
+    impl HistogramSnapshot {
+        /// Returns the sample counts of the buckets.
+        pub fn buckets(&self) -> &[u64] {
+            &self.buckets
+        }
+
+        /// Returns the number of samples.
+        pub fn count(&self) -> u64 {
+            self.buckets.iter().sum()
+        }
+
+        /// Returns an upper bound for the given quantile of the samples, or `None` if there are none.
+        ///
+        /// The bound is the end of the bucket holding the quantile, so it is at most twice the exact value.
+        ///
+        /// # Panics
+        ///
+        /// Panics if `quantile` is not between 0 and 1.
+        pub fn quantile(&self, quantile: f64) -> Option<Duration> {
+            assert!(
+                (0.0..=1.0).contains(&quantile),
+                "quantile must be between 0 and 1"
+            );
+
+            let count = self.count();
+            let rank = ((quantile * count as f64).ceil() as u64).max(1);
+            let mut seen = 0;
+
+            for (i, &samples) in self.buckets.iter().enumerate() {
+                seen += samples;
+
+                if seen >= rank {
+                    let end = 1u64
+                        .checked_shl(i as u32 + 1)
+                        .map_or(u64::MAX, |end| end - 1);
+                    return Some(Duration::from_nanos(end));
+                }
+            }
+
+            None
+        }
+
+        /// Adds the samples of another histogram to this one.
+        pub fn merge(&mut self, other: &HistogramSnapshot) {
+            for (bucket, samples) in self.buckets.iter_mut().zip(other.buckets.iter()) {
+                *bucket += samples;
+            }
+        }
+    }
+
+    impl Default for HistogramSnapshot {
+        fn default() -> Self {
+            Self {
+                buckets: [0; BUCKETS],
+            }
+        }
+    }
+
+    /// The latency histograms of a single shard.
+    ///
+    /// `get`, `insert` and `remove` time complete calls, including waiting for the shard lock:
+    ///
+    /// - `get`: [`get`](crate::DashMap::get), [`get_mut`](crate::DashMap::get_mut) and
+    ///   [`get_snapshot`](crate::DashMap::get_snapshot), up to handing out the reference.
+    /// - `insert`: [`insert`](crate::DashMap::insert), [`insert_with`](crate::DashMap::insert_with)
+    ///   and [`insert_with_cow`](crate::DashMap::insert_with_cow), until the lock is released.
+    /// - `remove`: [`remove`](crate::DashMap::remove), [`remove_entry`](crate::DashMap::remove_entry),
+    ///   [`remove_if`](crate::DashMap::remove_if) and [`remove_if_mut`](crate::DashMap::remove_if_mut),
+    ///   until the lock is released.
+    ///
+    /// The matching [`DashSet`](crate::DashSet) methods are measured the same way. The `try_*` variants,
+    /// the entry API, batches, iteration and bulk methods such as `retain` are not timed as operations.
+    ///
+    /// `read_wait` and `write_wait` time how long the calls above, as well as the entry API, waited to
+    /// acquire the shard lock. `write_wait` also covers every shard locked by
+    /// [`apply_batch`](crate::DashMap::apply_batch), [`update_batch`](crate::DashMap::update_batch)
+    /// and a [`BufferedWriter`](crate::batch::BufferedWriter) flush.
+    ///
+    /// There is no separate histogram of how long locks are held. That time is only contained in
+    /// `insert` and `remove`, whose calls release the lock before returning. The references returned
+    /// by `get`, `get_mut`, the entry API and iterators keep a lock for as long as the caller holds
+    /// them, and that time is not recorded.
+    #[derive(Clone, Debug, Default, PartialEq, Eq)]
+    pub struct ShardMetricsSnapshot {
+        pub get: HistogramSnapshot,
+        pub insert: HistogramSnapshot,
+        pub remove: HistogramSnapshot,
+        pub read_wait: HistogramSnapshot,
+        pub write_wait: HistogramSnapshot,
+    }
+
+    impl ShardMetricsSnapshot {
+        /// Adds the samples of another shard to this one.
+        pub fn merge(&mut self, other: &ShardMetricsSnapshot) {
+            self.get.merge(&other.get);
+            self.insert.merge(&other.insert);
+            self.remove.merge(&other.remove);
+            self.read_wait.merge(&other.read_wait);
+            self.write_wait.merge(&other.write_wait);
+        }
+    }
+
+    /// A point-in-time copy of the latency histograms of a map, returned by
+    /// [`DashMap::metrics_snapshot`](crate::DashMap::metrics_snapshot).
+    #[derive(Clone, Debug, Default, PartialEq, Eq)]
+    pub struct MetricsSnapshot {
+        pub(super) shards: Vec<ShardMetricsSnapshot>,
+    }
+
+    impl MetricsSnapshot {
+        /// Returns the histograms of every shard.
+        pub fn shards(&self) -> &[ShardMetricsSnapshot] {
+            &self.shards
+        }
+
+        /// Returns the histograms of all shards combined.
+        pub fn total(&self) -> ShardMetricsSnapshot {
+            let mut total = ShardMetricsSnapshot::default();
+
+            for shard in &self.shards {
+                total.merge(shard);
+            }
+
+            total
+        }
+    }
+}
+
+#[cfg(all(test, feature = "metrics"))]
+mod tests {
+    use super::{Histogram, HistogramSnapshot};
+    use core::time::Duration;
+
+    #[test]
+    fn test_histogram() {
+        let histogram = Histogram::new();
+
+        for nanos in [0, 1, 2, 3, 900, 1000] {
+            histogram.record(Duration::from_nanos(nanos));
+        }
+
+        let snapshot = histogram.snapshot();
+        assert_eq!(snapshot.count(), 6);
+        assert_eq!(snapshot.buckets()[..3], [2, 2, 0]);
+        assert_eq!(snapshot.quantile(0.5), Some(Duration::from_nanos(3)));
+        assert_eq!(snapshot.quantile(1.0), Some(Duration::from_nanos(1023)));
+        assert_eq!(HistogramSnapshot::default().quantile(0.5), None);
+    }
+}
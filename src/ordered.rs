@@ -0,0 +1,473 @@
+//! A concurrent map which additionally supports ordered range scans.
+//!
+//! Requires the `ordered` feature to be enabled.
+
+use crate::lock::{self, RwLock, RwLockReadGuardDetached};
+use crate::mapref::multiple::RefMulti;
+use crate::mapref::one::Ref;
+use crate::{default_shard_amount, ncb, util, HashMap};
+use core::borrow::Borrow;
+use core::cmp::{Ordering, Reverse};
+use core::fmt;
+use core::hash::{BuildHasher, Hash, Hasher};
+use core::ops::{Bound, RangeBounds};
+use crossbeam_utils::CachePadded;
+use hashbrown::hash_table;
+use std::collections::hash_map::RandomState;
+use std::collections::{BTreeMap, BinaryHeap};
+use std::sync::Arc;
+
+struct Shard<K, V> {
+    table: HashMap<K, V>,
+    // Every key in `table`, mapped to its hash so that scans don't need to rehash.
+    order: BTreeMap<K, u64>,
+}
+
+/// DashOrderedMap is a [`DashMap`](crate::DashMap)-like concurrent map which keeps an ordered
+/// index of its keys next to the hash table of every shard.
+///
+/// Point operations stay O(1) on the hash table, while [`range`](DashOrderedMap::range)
+/// and [`prefix_scan`](DashOrderedMap::prefix_scan) merge the ordered indices of all shards.
+/// Keys are stored twice, so they must be `Clone`.
+///
+/// Requires the `ordered` feature to be enabled.
+pub struct DashOrderedMap<K, V, S = RandomState> {
+    shift: usize,
+    shards: Box<[CachePadded<RwLock<Shard<K, V>>>]>,
+    hasher: S,
+}
+
+impl<K, V, S> Default for DashOrderedMap<K, V, S>
+where
+    K: Ord + Hash + Clone,
+    S: Default + BuildHasher,
+{
+    fn default() -> Self {
+        Self::with_hasher(Default::default())
+    }
+}
+
+impl<'a, K: 'a + Ord + Hash + Clone, V: 'a> DashOrderedMap<K, V, RandomState> {
+    /// Creates a new DashOrderedMap.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::ordered::DashOrderedMap;
+    ///
+    /// let versions = DashOrderedMap::new();
+    /// versions.insert("1.2.0", "stable");
+    /// ```
+    pub fn new() -> Self {
+        Self::with_hasher(RandomState::default())
+    }
+
+    /// Creates a new DashOrderedMap with a specified shard amount
+    ///
+    /// shard_amount should greater than 0 and be a power of two.
+    /// If a shard_amount which is not a power of two is provided, the function will panic.
+    pub fn with_shard_amount(shard_amount: usize) -> Self {
+        Self::with_hasher_and_shard_amount(RandomState::default(), shard_amount)
+    }
+}
+
+impl<'a, K: 'a + Ord + Hash + Clone, V: 'a, S: BuildHasher> DashOrderedMap<K, V, S> {
+    /// Creates a new DashOrderedMap with the provided hasher.
+    pub fn with_hasher(hasher: S) -> Self {
+        Self::with_hasher_and_shard_amount(hasher, default_shard_amount())
+    }
+
+    /// Creates a new DashOrderedMap with a specified hasher and shard amount
+    ///
+    /// shard_amount should be greater than 0 and a power of two.
+    /// If a shard_amount which is not a power of two is provided, the function will panic.
+    pub fn with_hasher_and_shard_amount(hasher: S, shard_amount: usize) -> Self {
+        assert!(shard_amount > 1);
+        assert!(shard_amount.is_power_of_two());
+
+        let shift = util::ptr_size_bits() - ncb(shard_amount);
+
+        let shards = (0..shard_amount)
+            .map(|idx| {
+                CachePadded::new(lock::shard_lock(
+                    idx,
+                    Shard {
+                        table: HashMap::new(),
+                        order: BTreeMap::new(),
+                    },
+                ))
+            })
+            .collect();
+
+        Self {
+            shift,
+            shards,
+            hasher,
+        }
+    }
+
+    /// Returns a reference to the map's [`BuildHasher`].
+    pub fn hasher(&self) -> &S {
+        &self.hasher
+    }
+
+    fn hash_u64<T: Hash + ?Sized>(&self, item: &T) -> u64 {
+        let mut hasher = self.hasher.build_hasher();
+
+        item.hash(&mut hasher);
+
+        hasher.finish()
+    }
+
+    fn determine_shard(&self, hash: u64) -> usize {
+        // Leave the high 7 bits for the HashBrown SIMD tag.
+        ((hash as usize) << 7) >> self.shift
+    }
+
+    /// Inserts a key and a value into the map. Returns the old value associated with the key if there was one.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    pub fn insert(&self, key: K, value: V) -> Option<V> {
+        let hash = self.hash_u64(&key);
+
+        let mut shard = self.shards[self.determine_shard(hash)].write();
+        let shard = &mut *shard;
+
+        match shard.table.entry(
+            hash,
+            |(k, _v)| k == &key,
+            |(k, _v)| {
+                let mut hasher = self.hasher.build_hasher();
+                k.hash(&mut hasher);
+                hasher.finish()
+            },
+        ) {
+            hash_table::Entry::Occupied(mut entry) => {
+                Some(core::mem::replace(&mut entry.get_mut().1, value))
+            }
+            hash_table::Entry::Vacant(entry) => {
+                shard.order.insert(key.clone(), hash);
+                entry.insert((key, value));
+                None
+            }
+        }
+    }
+
+    /// Removes an entry from the map, returning the key and value if they existed in the map.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    pub fn remove<Q>(&self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Ord + ?Sized,
+    {
+        let hash = self.hash_u64(key);
+
+        let mut shard = self.shards[self.determine_shard(hash)].write();
+
+        let entry = shard
+            .table
+            .find_entry(hash, |(k, _v)| key == k.borrow())
+            .ok()?;
+        let ((k, v), _) = entry.remove();
+        shard.order.remove(key);
+
+        Some((k, v))
+    }
+
+    /// Get an immutable reference to an entry in the map
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::ordered::DashOrderedMap;
+    ///
+    /// let versions = DashOrderedMap::new();
+    /// versions.insert("1.2.0", "stable");
+    /// assert_eq!(*versions.get("1.2.0").unwrap(), "stable");
+    /// ```
+    pub fn get<Q>(&'a self, key: &Q) -> Option<Ref<'a, K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Ord + ?Sized,
+    {
+        let hash = self.hash_u64(key);
+
+        let shard = self.shards[self.determine_shard(hash)].read();
+        // SAFETY: The data will not outlive the guard, since we pass the guard to `Ref`.
+        let (guard, shard) = unsafe { RwLockReadGuardDetached::detach_from(shard) };
+
+        let (k, v) = shard.table.find(hash, |(k, _v)| key == k.borrow())?;
+        Some(Ref::new(guard, k, v))
+    }
+
+    /// Checks if the map contains a specific key.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Ord + ?Sized,
+    {
+        let hash = self.hash_u64(key);
+
+        let shard = self.shards[self.determine_shard(hash)].read();
+
+        shard
+            .table
+            .find(hash, |(k, _v)| key == k.borrow())
+            .is_some()
+    }
+
+    /// Fetches the total number of key-value pairs stored in the map.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|s| s.read().table.len()).sum()
+    }
+
+    /// Checks if the map is empty or not.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes all key-value pairs in the map.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    pub fn clear(&self) {
+        self.shards.iter().for_each(|s| {
+            let mut shard = s.write();
+            shard.table.clear();
+            shard.order.clear();
+        });
+    }
+
+    /// Creates an iterator over the entries with a key in `range`, yielding them in ascending key order.
+    ///
+    /// Read locks on all shards are acquired up front and held until the iterator and every
+    /// reference produced by it have been dropped, so the scan observes a consistent state.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::ordered::DashOrderedMap;
+    ///
+    /// let map = DashOrderedMap::new();
+    /// for i in 0..100 {
+    ///     map.insert(i, i * 2);
+    /// }
+    /// let values: Vec<i32> = map.range(10..13).map(|r| *r.value()).collect();
+    /// assert_eq!(values, vec![20, 22, 24]);
+    /// ```
+    pub fn range<Q, R>(&'a self, range: R) -> Range<'a, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q> + Clone + 'a,
+    {
+        Range::new(&self.shards, move |order| {
+            Box::new(order.range::<Q, R>(range.clone()))
+        })
+    }
+
+    /// Creates an iterator over all entries, yielding them in ascending key order.
+    ///
+    /// See [`DashOrderedMap::range`] for the locking behaviour.
+    pub fn iter(&'a self) -> Range<'a, K, V> {
+        Range::new(&self.shards, |order| Box::new(order.iter()))
+    }
+
+    /// Creates an iterator over the entries whose key starts with `prefix`,
+    /// yielding them in ascending key order.
+    ///
+    /// See [`DashOrderedMap::range`] for the locking behaviour.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::ordered::DashOrderedMap;
+    ///
+    /// let map = DashOrderedMap::new();
+    /// map.insert("user/2".to_string(), 2);
+    /// map.insert("group/1".to_string(), 0);
+    /// map.insert("user/1".to_string(), 1);
+    /// let users: Vec<i32> = map.prefix_scan("user/").map(|r| *r.value()).collect();
+    /// assert_eq!(users, vec![1, 2]);
+    /// ```
+    pub fn prefix_scan(&'a self, prefix: &'a str) -> Range<'a, K, V>
+    where
+        K: Borrow<str>,
+    {
+        Range::new(&self.shards, move |order| {
+            Box::new(
+                order
+                    .range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+                    .take_while(move |(k, _hash)| (*k).borrow().starts_with(prefix)),
+            )
+        })
+    }
+}
+
+impl<K: Ord + Hash + Clone + fmt::Debug, V: fmt::Debug, S: BuildHasher> fmt::Debug
+    for DashOrderedMap<K, V, S>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut pmap = f.debug_map();
+
+        for r in self.iter() {
+            let (k, v) = r.pair();
+
+            pmap.entry(k, v);
+        }
+
+        pmap.finish()
+    }
+}
+
+type OrderIter<'a, K> = Box<dyn Iterator<Item = (&'a K, &'a u64)> + 'a>;
+
+struct Cursor<'a, K, V> {
+    guard: Arc<RwLockReadGuardDetached<'a>>,
+    table: &'a HashMap<K, V>,
+    keys: OrderIter<'a, K>,
+}
+
+struct Head<'a, K> {
+    key: &'a K,
+    hash: u64,
+    cursor: usize,
+}
+
+impl<'a, K: Ord> PartialEq for Head<'a, K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl<'a, K: Ord> Eq for Head<'a, K> {}
+
+impl<'a, K: Ord> PartialOrd for Head<'a, K> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<'a, K: Ord> Ord for Head<'a, K> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.cmp(other.key)
+    }
+}
+
+/// Iterator over a [`DashOrderedMap`] yielding entries in ascending key order.
+pub struct Range<'a, K, V> {
+    cursors: Vec<Cursor<'a, K, V>>,
+    heads: BinaryHeap<Reverse<Head<'a, K>>>,
+}
+
+impl<'a, K: Ord + Hash + 'a, V: 'a> Range<'a, K, V> {
+    fn new(
+        shards: &'a [CachePadded<RwLock<Shard<K, V>>>],
+        mut keys: impl FnMut(&'a BTreeMap<K, u64>) -> OrderIter<'a, K>,
+    ) -> Self {
+        let mut cursors = Vec::with_capacity(shards.len());
+        let mut heads = BinaryHeap::with_capacity(shards.len());
+
+        for shard in shards {
+            // SAFETY: we keep the guard alive with the cursor,
+            // and with any refs produced by the iterator
+            let (guard, shard) = unsafe { RwLockReadGuardDetached::detach_from(shard.read()) };
+
+            let mut cursor = Cursor {
+                guard: Arc::new(guard),
+                table: &shard.table,
+                keys: keys(&shard.order),
+            };
+
+            if let Some((key, &hash)) = cursor.keys.next() {
+                heads.push(Reverse(Head {
+                    key,
+                    hash,
+                    cursor: cursors.len(),
+                }));
+            }
+
+            cursors.push(cursor);
+        }
+
+        Self { cursors, heads }
+    }
+}
+
+impl<'a, K: Ord + Hash + 'a, V: 'a> Iterator for Range<'a, K, V> {
+    type Item = RefMulti<'a, K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let Reverse(head) = self.heads.pop()?;
+            let cursor = &mut self.cursors[head.cursor];
+
+            if let Some((key, &hash)) = cursor.keys.next() {
+                self.heads.push(Reverse(Head {
+                    key,
+                    hash,
+                    cursor: head.cursor,
+                }));
+            }
+
+            // The index and the table are only changed together under the write lock, but a key
+            // whose `Ord` disagrees with its `Eq`, or a panic between the two updates, can still
+            // leave a key in the index only. Such a key is skipped rather than panicking.
+            match cursor.table.find(head.hash, |(k, _v)| k == head.key) {
+                Some((k, v)) => return Some(RefMulti::new(cursor.guard.clone(), k, v)),
+                None => debug_assert!(false, "ordered index out of sync with table"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DashOrderedMap;
+
+    #[test]
+    fn test_range_is_ordered() {
+        let map = DashOrderedMap::with_shard_amount(8);
+
+        for i in (0..1000).rev() {
+            map.insert(i, i.to_string());
+        }
+
+        let keys: Vec<i32> = map.range(100..200).map(|r| *r.key()).collect();
+        assert_eq!(keys, (100..200).collect::<Vec<_>>());
+
+        let keys: Vec<i32> = map.iter().map(|r| *r.key()).collect();
+        assert_eq!(keys, (0..1000).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_remove_updates_index() {
+        let map = DashOrderedMap::new();
+
+        for i in 0..10 {
+            map.insert(i, i);
+        }
+
+        assert_eq!(map.insert(3, 30), Some(3));
+        assert_eq!(map.remove(&4), Some((4, 4)));
+        assert_eq!(map.remove(&4), None);
+        assert_eq!(map.len(), 9);
+
+        let values: Vec<i32> = map.range(2..6).map(|r| *r.value()).collect();
+        assert_eq!(values, vec![2, 30, 5]);
+
+        map.clear();
+        assert!(map.is_empty());
+        assert_eq!(map.iter().count(), 0);
+    }
+}
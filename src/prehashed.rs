@@ -0,0 +1,174 @@
+use crate::hasher;
+use core::fmt;
+use core::hash::{BuildHasher, Hash, Hasher};
+use core::ops::Deref;
+use once_cell::sync::OnceCell;
+use std::collections::hash_map::RandomState;
+
+/// A key which carries its own hash, computed once when the key was created.
+///
+/// Implementations must hash themselves by writing [`pre_hash`](Self::pre_hash) with a single
+/// [`Hasher::write_u64`] call, and equal keys must have equal pre-hashes. In a map using
+/// [`PreHashedState`], looking up, inserting and resizing then never hash the key's contents again,
+/// which helps when the keys are long, such as strings with long common prefixes.
+pub trait PreHashedKey: Hash + Eq {
+    /// Returns the hash of the key, computed when the key was created.
+    fn pre_hash(&self) -> u64;
+}
+
+/// A value together with its hash, computed once on creation.
+///
+/// Comparisons check the hashes first, so unequal keys are usually told apart without comparing their contents.
+/// `Hashed<T>` deliberately doesn't implement `Borrow<T>`, since it hashes differently than `T`;
+/// lookups take a `Hashed<T>` as well, which can be created once and reused.
+///
+/// # Examples
+///
+/// ```
+/// use dashmap::{DashMap, HashedString, PreHashedState};
+///
+/// let routes: DashMap<HashedString, u32, PreHashedState> = DashMap::with_hasher(PreHashedState);
+///
+/// let key = HashedString::new("/api/v1/users/profile/settings".to_owned());
+/// routes.insert(key.clone(), 200);
+/// assert_eq!(*routes.get(&key).unwrap(), 200);
+/// assert_eq!(key.as_str(), "/api/v1/users/profile/settings");
+/// ```
+#[derive(Clone)]
+pub struct Hashed<T> {
+    hash: u64,
+    value: T,
+}
+
+/// A [`String`] together with its hash.
+pub type HashedString = Hashed<String>;
+
+impl<T: Hash> Hashed<T> {
+    /// Hashes a value with a hasher which is seeded randomly once per process.
+    pub fn new(value: T) -> Self {
+        static STATE: OnceCell<RandomState> = OnceCell::new();
+
+        Self::with_hasher(value, STATE.get_or_init(RandomState::new))
+    }
+
+    /// Hashes a value with the provided hasher.
+    ///
+    /// Keys which are compared with each other must be hashed with equally seeded hashers.
+    pub fn with_hasher<S: BuildHasher>(value: T, hasher: &S) -> Self {
+        let mut hasher = hasher.build_hasher();
+        value.hash(&mut hasher);
+
+        Self {
+            hash: hasher.finish(),
+            value,
+        }
+    }
+}
+
+impl<T> Hashed<T> {
+    /// Returns the wrapped value.
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// Consumes the wrapper, returning the value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl Hashed<String> {
+    /// Returns the wrapped string as a `&str`.
+    pub fn as_str(&self) -> &str {
+        &self.value
+    }
+}
+
+impl<T: Hash> From<T> for Hashed<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T: Eq> PartialEq for Hashed<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.hash == other.hash && self.value == other.value
+    }
+}
+
+impl<T: Eq> Eq for Hashed<T> {}
+
+impl<T> Hash for Hashed<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash)
+    }
+}
+
+impl<T: Eq> PreHashedKey for Hashed<T> {
+    fn pre_hash(&self) -> u64 {
+        self.hash
+    }
+}
+
+impl<T> Deref for Hashed<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Hashed<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Hashed<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+/// A [`BuildHasher`] for maps keyed by [`PreHashedKey`]s, which only mixes the pre-computed hashes.
+///
+/// Keys of other types still hash correctly, with a fast but weak hash that is not resistant to collision attacks.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct PreHashedState;
+
+impl BuildHasher for PreHashedState {
+    type Hasher = PreHashedHasher;
+
+    fn build_hasher(&self) -> PreHashedHasher {
+        PreHashedHasher { hash: 0 }
+    }
+}
+
+/// The [`Hasher`] built by [`PreHashedState`].
+#[derive(Clone, Copy, Debug)]
+pub struct PreHashedHasher {
+    hash: u64,
+}
+
+impl PreHashedHasher {
+    #[inline]
+    fn add(&mut self, word: u64) {
+        self.hash = hasher::mix(self.hash, word);
+    }
+}
+
+impl Hasher for PreHashedHasher {
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+
+    #[inline]
+    fn write_u64(&mut self, n: u64) {
+        self.add(n);
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        hasher::write_words(bytes, |word| self.add(word));
+    }
+}
@@ -0,0 +1,344 @@
+use crate::mapref::entry::Entry;
+use crate::mapref::one::{Ref, RefMut};
+use crate::DashMap;
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::{BuildHasher, Hash};
+use std::collections::hash_map::RandomState;
+
+struct Namespace {
+    count: usize,
+    limit: Option<usize>,
+}
+
+/// A [`DashMap`] limiting the number of entries per namespace, such as per tenant.
+///
+/// The namespace of a key is computed by the projection the map is created with, and limits are set
+/// per namespace with [`set_limit`](Self::set_limit). Inserting a new key into a namespace which is
+/// full fails with a [`QuotaExceeded`] error, while replacing the value of an existing key always succeeds.
+///
+/// The counters are updated while the shard of the entry is still locked, so they never drift from
+/// the entries in the map. This is why the map can only be modified through this wrapper.
+///
+/// # Examples
+///
+/// ```
+/// use dashmap::QuotaMap;
+///
+/// let sessions = QuotaMap::new(|(tenant, _session): &(u32, u64)| *tenant);
+/// sessions.set_limit(1, 2);
+///
+/// assert!(sessions.insert((1, 10), "a").is_ok());
+/// assert!(sessions.insert((1, 11), "b").is_ok());
+/// assert!(sessions.insert((1, 12), "c").is_err());
+/// assert!(sessions.insert((2, 10), "d").is_ok());
+///
+/// sessions.remove(&(1, 10));
+/// assert!(sessions.insert((1, 12), "c").is_ok());
+/// assert_eq!(sessions.count(&1), 2);
+/// ```
+pub struct QuotaMap<K, V, N, S = RandomState> {
+    map: DashMap<K, V, S>,
+    namespaces: DashMap<N, Namespace, S>,
+    project: Box<dyn Fn(&K) -> N + Send + Sync>,
+}
+
+impl<K: Eq + Hash, V, N: Eq + Hash> QuotaMap<K, V, N, RandomState> {
+    /// Creates a map whose keys are put into namespaces by `project`, without any limits.
+    pub fn new(project: impl Fn(&K) -> N + Send + Sync + 'static) -> Self {
+        Self::with_hasher(project, RandomState::new())
+    }
+}
+
+impl<K: Eq + Hash, V, N: Eq + Hash, S: BuildHasher + Clone> QuotaMap<K, V, N, S> {
+    /// Creates a map whose keys are put into namespaces by `project`, using the provided hasher.
+    pub fn with_hasher(project: impl Fn(&K) -> N + Send + Sync + 'static, hasher: S) -> Self {
+        Self {
+            map: DashMap::with_hasher(hasher.clone()),
+            namespaces: DashMap::with_hasher(hasher),
+            project: Box::new(project),
+        }
+    }
+
+    /// Sets the maximum number of entries of a namespace.
+    ///
+    /// A namespace which already holds more entries keeps them, but no new keys can be inserted into it.
+    pub fn set_limit(&self, namespace: N, limit: usize) {
+        self.namespaces
+            .entry(namespace)
+            .or_insert(Namespace {
+                count: 0,
+                limit: None,
+            })
+            .limit = Some(limit);
+    }
+
+    /// Removes the limit of a namespace.
+    pub fn remove_limit<Q>(&self, namespace: &Q)
+    where
+        N: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if let Some(mut ns) = self.namespaces.get_mut(namespace) {
+            ns.limit = None;
+        }
+
+        self.namespaces.remove_if(namespace, |_, ns| ns.count == 0);
+    }
+
+    /// Returns the limit of a namespace, if it has one.
+    pub fn limit<Q>(&self, namespace: &Q) -> Option<usize>
+    where
+        N: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.namespaces.get(namespace).and_then(|ns| ns.limit)
+    }
+
+    /// Returns the number of entries in a namespace.
+    pub fn count<Q>(&self, namespace: &Q) -> usize
+    where
+        N: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.namespaces.get(namespace).map_or(0, |ns| ns.count)
+    }
+
+    /// Inserts a key and a value into the map, unless the key is new and its namespace is full.
+    ///
+    /// Returns the old value if the key was present, or the key and value back in a
+    /// [`QuotaExceeded`] error if the namespace is full.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    pub fn insert(&self, key: K, value: V) -> Result<Option<V>, QuotaExceeded<K, V>> {
+        match self.map.entry(key) {
+            Entry::Occupied(mut entry) => Ok(Some(entry.insert(value))),
+            Entry::Vacant(entry) => {
+                let namespace = (self.project)(entry.key());
+                let mut ns = self.namespaces.entry(namespace).or_insert(Namespace {
+                    count: 0,
+                    limit: None,
+                });
+
+                if let Some(limit) = ns.limit.filter(|&limit| ns.count >= limit) {
+                    drop(ns);
+                    return Err(QuotaExceeded {
+                        key: entry.into_key(),
+                        value,
+                        limit,
+                    });
+                }
+
+                ns.count += 1;
+                drop(ns);
+                entry.insert(value);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Removes an entry from the map, returning the key and value if they existed in the map.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    pub fn remove<Q>(&self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.remove_if(key, |k, _v| {
+            self.released(k);
+            true
+        })
+    }
+
+    /// Retain elements that whose predicates return true and discard elements whose predicates return false.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    pub fn retain(&self, mut f: impl FnMut(&K, &mut V) -> bool) {
+        self.map.retain(|k, v| {
+            let keep = f(k, v);
+            if !keep {
+                self.released(k);
+            }
+            keep
+        });
+    }
+
+    /// Removes all entries from the map, keeping the limits.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    pub fn clear(&self) {
+        self.retain(|_, _| false);
+    }
+
+    fn released(&self, key: &K) {
+        let namespace = (self.project)(key);
+
+        if let Some(mut ns) = self.namespaces.get_mut(&namespace) {
+            ns.count -= 1;
+        }
+
+        self.namespaces
+            .remove_if(&namespace, |_, ns| ns.count == 0 && ns.limit.is_none());
+    }
+
+    /// Get an immutable reference to an entry in the map.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    pub fn get<Q>(&self, key: &Q) -> Option<Ref<'_, K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.get(key)
+    }
+
+    /// Get a mutable reference to an entry in the map.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    pub fn get_mut<Q>(&self, key: &Q) -> Option<RefMut<'_, K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.get_mut(key)
+    }
+
+    /// Checks if the map contains a specific key.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.contains_key(key)
+    }
+
+    /// Returns the number of elements in the map.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the map contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Creates an iterator over the map yielding immutable references.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    pub fn iter(&self) -> crate::iter::Iter<'_, K, V> {
+        self.map.iter()
+    }
+
+    /// Consumes the wrapper, returning the map without its limits.
+    pub fn into_inner(self) -> DashMap<K, V, S> {
+        self.map
+    }
+}
+
+impl<K: Eq + Hash + fmt::Debug, V: fmt::Debug, N, S: BuildHasher> fmt::Debug
+    for QuotaMap<K, V, N, S>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.map.fmt(f)
+    }
+}
+
+/// The error returned by [`QuotaMap::insert`] when the namespace of a new key is full.
+///
+/// It holds the key and value which were not inserted.
+pub struct QuotaExceeded<K, V> {
+    key: K,
+    value: V,
+    limit: usize,
+}
+
+impl<K, V> QuotaExceeded<K, V> {
+    /// Returns the key which was not inserted.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Returns the limit of the namespace.
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Returns the key and value which were not inserted.
+    pub fn into_pair(self) -> (K, V) {
+        (self.key, self.value)
+    }
+}
+
+impl<K: fmt::Debug, V> fmt::Debug for QuotaExceeded<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QuotaExceeded")
+            .field("key", &self.key)
+            .field("limit", &self.limit)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<K, V> fmt::Display for QuotaExceeded<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the namespace of the key is full with {} entries",
+            self.limit
+        )
+    }
+}
+
+impl<K: fmt::Debug, V> std::error::Error for QuotaExceeded<K, V> {}
+
+#[cfg(test)]
+mod tests {
+    use super::QuotaMap;
+    use std::sync::Barrier;
+
+    #[test]
+    fn test_quota_concurrent() {
+        let map = QuotaMap::new(|k: &u32| k % 4);
+
+        for ns in 0..4 {
+            map.set_limit(ns, 50);
+        }
+
+        let barrier = Barrier::new(4);
+
+        std::thread::scope(|s| {
+            for t in 0..4 {
+                let (map, barrier) = (&map, &barrier);
+
+                s.spawn(move || {
+                    barrier.wait();
+
+                    for i in 0..200 {
+                        let key = t * 1000 + i;
+                        if map.insert(key, i).is_ok() && i % 3 == 0 {
+                            map.remove(&key);
+                        }
+                    }
+                });
+            }
+        });
+
+        for ns in 0..4 {
+            let entries = map.iter().filter(|r| r.key() % 4 == ns).count();
+            assert_eq!(map.count(&ns), entries);
+            assert!(entries <= 50);
+        }
+
+        let err = map.insert(10_000, 0).unwrap_err();
+        assert_eq!((err.limit(), err.into_pair()), (50, (10_000, 0)));
+
+        map.remove_limit(&0);
+        assert!(map.insert(10_000, 0).is_ok());
+
+        map.clear();
+        assert!((0..4).all(|ns| map.count(&ns) == 0));
+        assert_eq!(map.limit(&1), Some(50));
+    }
+}
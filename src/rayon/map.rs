@@ -1,17 +1,21 @@
+use crate::iter::ShardIter;
 use crate::lock::{RwLock, RwLockReadGuardDetached, RwLockWriteGuardDetached};
 use crate::mapref::multiple::{RefMulti, RefMutMulti};
-use crate::{DashMap, HashMap};
+use crate::{util, DashMap, HashMap};
 use core::hash::{BuildHasher, Hash};
 use crossbeam_utils::CachePadded;
 use rayon::iter::plumbing::UnindexedConsumer;
-use rayon::iter::{FromParallelIterator, IntoParallelIterator, ParallelExtend, ParallelIterator};
+use rayon::iter::{
+    FromParallelIterator, IntoParallelIterator, IntoParallelRefIterator, ParallelExtend,
+    ParallelIterator,
+};
 use std::sync::Arc;
 
 impl<K, V, S> ParallelExtend<(K, V)> for DashMap<K, V, S>
 where
     K: Send + Sync + Eq + Hash,
     V: Send + Sync,
-    S: Send + Sync + Clone + BuildHasher,
+    S: Send + Sync + BuildHasher,
 {
     fn par_extend<I>(&mut self, par_iter: I)
     where
@@ -27,7 +31,7 @@ impl<K, V, S> ParallelExtend<(K, V)> for &'_ DashMap<K, V, S>
 where
     K: Send + Sync + Eq + Hash,
     V: Send + Sync,
-    S: Send + Sync + Clone + BuildHasher,
+    S: Send + Sync + BuildHasher,
 {
     fn par_extend<I>(&mut self, par_iter: I)
     where
@@ -44,7 +48,7 @@ impl<K, V, S> FromParallelIterator<(K, V)> for DashMap<K, V, S>
 where
     K: Send + Sync + Eq + Hash,
     V: Send + Sync,
-    S: Send + Sync + Clone + Default + BuildHasher,
+    S: Send + Sync + Default + BuildHasher,
 {
     fn from_par_iter<I>(par_iter: I) -> Self
     where
@@ -57,16 +61,15 @@ where
 }
 
 // Implementation note: while the shards will iterate in parallel, we flatten
-// sequentially within each shard (`flat_map_iter`), because the standard
-// `HashMap` only implements `ParallelIterator` by collecting to a `Vec` first.
-// There is real parallel support in the `hashbrown/rayon` feature, but we don't
-// always use that map.
+// sequentially within each shard (`flat_map_iter`), which is cheaper when the
+// shards are evenly sized. `par_iter_balanced` uses the parallel iterators from
+// the `hashbrown/rayon` feature to split within shards as well.
 
 impl<K, V, S> IntoParallelIterator for DashMap<K, V, S>
 where
     K: Send + Eq + Hash,
     V: Send,
-    S: Send + Clone + BuildHasher,
+    S: Send + BuildHasher,
 {
     type Iter = OwningIter<K, V>;
     type Item = (K, V);
@@ -105,7 +108,7 @@ impl<'a, K, V, S> IntoParallelIterator for &'a DashMap<K, V, S>
 where
     K: Send + Sync + Eq + Hash,
     V: Send + Sync,
-    S: Send + Sync + Clone + BuildHasher,
+    S: Send + Sync + BuildHasher,
 {
     type Iter = Iter<'a, K, V>;
     type Item = RefMulti<'a, K, V>;
@@ -176,6 +179,154 @@ where
             shards: &self.shards,
         }
     }
+
+    /// Creates a parallel iterator which also splits the work within shards,
+    /// so that shards holding many more entries than others are spread over more threads.
+    ///
+    /// Like [`par_iter`](rayon::iter::IntoParallelRefIterator::par_iter),
+    /// every shard stays read-locked until all references into it are dropped.
+    pub fn par_iter_balanced(&self) -> BalancedIter<'_, K, V> {
+        BalancedIter {
+            shards: &self.shards,
+        }
+    }
+
+    /// Modify every value in the map according to a function, altering the shards in parallel.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Panics
+    ///
+    /// If the given closure panics, then `par_alter_all` will abort the process
+    pub fn par_alter_all(&self, f: impl Fn(&K, V) -> V + Sync + Send) {
+        self.par_iter_mut()
+            .for_each(|mut m| util::map_in_place_2(m.pair_mut(), &f));
+    }
+}
+
+impl<K, V, S> DashMap<K, V, S>
+where
+    K: Send + Sync + Eq + Hash,
+    V: Send + Sync,
+    S: Send + Sync + BuildHasher,
+{
+    /// Returns a reference to an entry satisfying a predicate, scanning the shards in parallel.
+    ///
+    /// The scan stops on all threads as soon as a match is found.
+    /// Like [`par_iter`](rayon::iter::IntoParallelRefIterator::par_iter),
+    /// the shard of the returned reference stays read-locked until it is dropped.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    pub fn par_find_any(
+        &self,
+        pred: impl Fn(&K, &V) -> bool + Sync + Send,
+    ) -> Option<RefMulti<'_, K, V>> {
+        self.par_iter().find_any(|r| pred(r.key(), r.value()))
+    }
+
+    /// Checks if any entry satisfies a predicate, scanning the shards in parallel
+    /// and stopping on all threads at the first match.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    pub fn par_any_entry(&self, pred: impl Fn(&K, &V) -> bool + Sync + Send) -> bool {
+        self.shards
+            .par_iter()
+            .any(|shard| shard.read().iter().any(|(k, v)| pred(k, v)))
+    }
+
+    /// Aggregates the map one shard at a time, processing the shards in parallel.
+    ///
+    /// See [`DashMap::map_reduce`] for details.
+    pub fn par_map_reduce<T: Send>(
+        &self,
+        map_fn: impl Fn(ShardIter<'_, K, V>) -> T + Sync + Send,
+        reduce_fn: impl Fn(T, T) -> T + Sync + Send,
+    ) -> T {
+        self.shards
+            .par_iter()
+            .map(|shard| map_fn(ShardIter::new(&shard.read())))
+            .reduce_with(reduce_fn)
+            .expect("a map always has shards")
+    }
+
+    /// Folds the entries of every shard into an accumulator created once per shard,
+    /// processing the shards in parallel, and then reduces the accumulators.
+    ///
+    /// Unlike rayon's `fold`, which creates an accumulator for every split of the work,
+    /// `init` is called exactly once per shard, so it can allocate scratch space such as
+    /// a histogram up front.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let latencies: DashMap<u32, u32> = (0..1000).map(|i| (i, i % 100)).collect();
+    ///
+    /// let histogram = latencies.par_fold_shards(
+    ///     || vec![0u32; 10],
+    ///     |buckets, _, latency| buckets[*latency as usize / 10] += 1,
+    ///     |mut a, b| {
+    ///         a.iter_mut().zip(b).for_each(|(a, b)| *a += b);
+    ///         a
+    ///     },
+    /// );
+    /// assert_eq!(histogram, [100; 10]);
+    /// ```
+    pub fn par_fold_shards<A: Send>(
+        &self,
+        init: impl Fn() -> A + Sync + Send,
+        fold: impl Fn(&mut A, &K, &V) + Sync + Send,
+        reduce: impl Fn(A, A) -> A + Sync + Send,
+    ) -> A {
+        self.shards
+            .par_iter()
+            .map(|shard| {
+                let mut acc = init();
+
+                for (k, v) in shard.read().iter() {
+                    fold(&mut acc, k, v);
+                }
+
+                acc
+            })
+            .reduce_with(reduce)
+            .expect("a map always has shards")
+    }
+}
+
+pub struct BalancedIter<'a, K, V> {
+    shards: &'a [CachePadded<RwLock<HashMap<K, V>>>],
+}
+
+impl<'a, K, V> ParallelIterator for BalancedIter<'a, K, V>
+where
+    K: Send + Sync + Eq + Hash,
+    V: Send + Sync,
+{
+    type Item = RefMulti<'a, K, V>;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        self.shards
+            .into_par_iter()
+            .flat_map(|shard| {
+                // SAFETY: we keep the guard alive with the shard iterator,
+                // and with any refs produced by the iterator
+                let (guard, shard) = unsafe { RwLockReadGuardDetached::detach_from(shard.read()) };
+
+                let guard = Arc::new(guard);
+                shard.par_iter().map(move |(k, v)| {
+                    let guard = Arc::clone(&guard);
+                    RefMulti::new(guard, k, v)
+                })
+            })
+            .drive_unindexed(consumer)
+    }
 }
 
 pub struct IterMut<'a, K, V> {
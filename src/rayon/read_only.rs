@@ -1,14 +1,15 @@
 use crate::mapref::multiple::RefMulti;
 use crate::rayon::map::Iter;
 use crate::ReadOnlyView;
+use core::borrow::Borrow;
 use core::hash::{BuildHasher, Hash};
-use rayon::iter::IntoParallelIterator;
+use rayon::iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator};
 
 impl<K, V, S> IntoParallelIterator for ReadOnlyView<K, V, S>
 where
     K: Send + Eq + Hash,
     V: Send,
-    S: Send + Clone + BuildHasher,
+    S: Send + BuildHasher,
 {
     type Iter = super::map::OwningIter<K, V>;
     type Item = (K, V);
@@ -25,7 +26,7 @@ impl<'a, K, V, S> IntoParallelIterator for &'a ReadOnlyView<K, V, S>
 where
     K: Send + Sync + Eq + Hash,
     V: Send + Sync,
-    S: Send + Sync + Clone + BuildHasher,
+    S: Send + Sync + BuildHasher,
 {
     type Iter = Iter<'a, K, V>;
     type Item = RefMulti<'a, K, V>;
@@ -37,6 +38,42 @@ where
     }
 }
 
+impl<K, V, S> ReadOnlyView<K, V, S>
+where
+    K: Send + Sync + Eq + Hash,
+    V: Send + Sync,
+    S: Send + Sync + BuildHasher,
+{
+    /// Returns references to the values corresponding to several keys, in the order of the keys,
+    /// looking them up in parallel.
+    ///
+    /// Since the view needs no locks, the lookups scale with the number of threads.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    /// use rayon::iter::IntoParallelRefIterator;
+    ///
+    /// let map: DashMap<u32, u32> = (0..1000).map(|i| (i, i * 2)).collect();
+    /// let view = map.into_read_only();
+    ///
+    /// let keys: Vec<u32> = (990..1010).collect();
+    /// let values = view.par_get(keys.par_iter());
+    /// assert_eq!(values[0], Some(&1980));
+    /// assert_eq!(values[10], None);
+    /// ```
+    pub fn par_get<'a, 'k, Q, I>(&'a self, keys: I) -> Vec<Option<&'a V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + Sync + ?Sized + 'k,
+        I: IntoParallelIterator<Item = &'k Q>,
+        I::Iter: IndexedParallelIterator,
+    {
+        keys.into_par_iter().map(|key| self.get(key)).collect()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::DashMap;
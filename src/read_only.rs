@@ -1,10 +1,13 @@
 use crate::lock::RwLock;
+use crate::tracker;
 use crate::{DashMap, HashMap};
 use cfg_if::cfg_if;
 use core::borrow::Borrow;
 use core::fmt;
-use core::hash::{BuildHasher, Hash};
+use core::hash::{BuildHasher, Hash, Hasher};
+use core::mem;
 use crossbeam_utils::CachePadded;
+use hashbrown::hash_table;
 use std::collections::hash_map::RandomState;
 
 /// A read-only view into a `DashMap`. Allows to obtain raw references to the stored values.
@@ -20,7 +23,7 @@ impl<K: Eq + Hash + Clone, V: Clone, S: Clone> Clone for ReadOnlyView<K, V, S> {
     }
 }
 
-impl<K: Eq + Hash + fmt::Debug, V: fmt::Debug, S: BuildHasher + Clone> fmt::Debug
+impl<K: Eq + Hash + fmt::Debug, V: fmt::Debug, S: BuildHasher> fmt::Debug
     for ReadOnlyView<K, V, S>
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -39,7 +42,7 @@ impl<K, V, S> ReadOnlyView<K, V, S> {
     }
 }
 
-impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher + Clone> ReadOnlyView<K, V, S> {
+impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher> ReadOnlyView<K, V, S> {
     /// Returns the number of elements in the map.
     pub fn len(&self) -> usize {
         self.map.len()
@@ -91,6 +94,28 @@ impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher + Clone> ReadOnlyView<K, V, S>
             .map(|(k, v)| (k, v))
     }
 
+    /// Returns references to the values corresponding to several keys, in the order of the keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    ///
+    /// let map = DashMap::new();
+    /// map.insert(1, "one");
+    /// map.insert(2, "two");
+    /// let view = map.into_read_only();
+    ///
+    /// assert_eq!(view.get_many(&[2, 3, 1]), [Some(&"two"), None, Some(&"one")]);
+    /// ```
+    pub fn get_many<'k, Q>(&'a self, keys: impl IntoIterator<Item = &'k Q>) -> Vec<Option<&'a V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized + 'k,
+    {
+        keys.into_iter().map(|key| self.get(key)).collect()
+    }
+
     /// An iterator visiting all key-value pairs in arbitrary order. The iterator element type is `(&'a K, &'a V)`.
     pub fn iter(&'a self) -> impl Iterator<Item = (&'a K, &'a V)> + 'a {
         self.map
@@ -111,6 +136,94 @@ impl<'a, K: 'a + Eq + Hash, V: 'a, S: BuildHasher + Clone> ReadOnlyView<K, V, S>
         self.iter().map(|(_k, v)| v)
     }
 
+    /// Returns a mutable reference to the value corresponding to the key.
+    ///
+    /// No locking is required since the view is exclusively borrowed.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.map.hash_u64(&key);
+
+        let idx = self.map.determine_shard(hash as usize);
+
+        let shard = self.map.shards[idx].get_mut();
+
+        shard
+            .find_mut(hash, |(k, _v)| key == k.borrow())
+            .map(|(_k, v)| v)
+    }
+
+    /// Inserts a key and a value into the map. Returns the old value associated with the key if there was one.
+    ///
+    /// No locking is required since the view is exclusively borrowed.
+    pub fn insert_mut(&mut self, key: K, value: V) -> Option<V> {
+        let hash = self.map.hash_u64(&key);
+
+        let idx = self.map.determine_shard(hash as usize);
+
+        let hasher = &self.map.hasher;
+        let tracker = self.map.tracker.as_deref();
+        let shard = self.map.shards[idx].get_mut();
+
+        let before = shard.allocation_size();
+
+        let old = match shard.entry(
+            hash,
+            |(k, _v)| k == &key,
+            |(k, _v)| {
+                let mut hasher = hasher.build_hasher();
+                k.hash(&mut hasher);
+                hasher.finish()
+            },
+        ) {
+            hash_table::Entry::Occupied(mut entry) => {
+                let (k, v) = entry.get_mut();
+                tracker::entry_added(tracker, k, &value);
+                let old = mem::replace(v, value);
+                tracker::entry_removed(tracker, k, &old);
+                Some(old)
+            }
+            hash_table::Entry::Vacant(entry) => {
+                tracker::entry_added(tracker, &key, &value);
+                entry.insert((key, value));
+                *self.map.lens[idx].get_mut() += 1;
+                None
+            }
+        };
+
+        tracker::resized(tracker, before, shard.allocation_size());
+
+        old
+    }
+
+    /// Removes an entry from the map, returning the key and value if they existed in the map.
+    ///
+    /// No locking is required since the view is exclusively borrowed.
+    pub fn remove_mut<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.map.hash_u64(&key);
+
+        let idx = self.map.determine_shard(hash as usize);
+
+        let tracker = self.map.tracker.as_deref();
+        let shard = self.map.shards[idx].get_mut();
+
+        match shard.find_entry(hash, |(k, _v)| key == k.borrow()) {
+            Ok(entry) => {
+                let (k, v) = entry.remove().0;
+                *self.map.lens[idx].get_mut() -= 1;
+                tracker::entry_removed(tracker, &k, &v);
+                Some((k, v))
+            }
+            Err(_) => None,
+        }
+    }
+
     cfg_if! {
         if #[cfg(feature = "raw-api")] {
             /// Allows you to peek at the inner shards that store your data.
@@ -255,4 +368,35 @@ mod tests {
             assert!(visited_items.contains(&(key, value)));
         }
     }
+
+    #[test]
+
+    fn test_mut() {
+        let map = construct_sample_map();
+
+        let mut view = map.into_read_only();
+
+        view.get_mut(&1).unwrap().push('!');
+
+        assert_eq!(view.get(&1).unwrap(), "one!");
+
+        assert_eq!(view.insert_mut(2, "two".to_string()), None);
+
+        assert_eq!(view.insert_mut(10, "TEN".to_string()).unwrap(), "ten");
+
+        assert_eq!(
+            view.remove_mut(&27).unwrap(),
+            (27, "twenty seven".to_string())
+        );
+
+        assert_eq!(view.remove_mut(&27), None);
+
+        assert_eq!(view.len(), 4);
+
+        let map = view.into_inner();
+
+        assert_eq!(*map.get(&2).unwrap(), "two");
+
+        assert_eq!(*map.get(&10).unwrap(), "TEN");
+    }
 }
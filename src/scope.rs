@@ -0,0 +1,110 @@
+use crate::audit;
+use crate::lock::RawRwLock;
+use crate::{DashMap, HashMap};
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::{BuildHasher, Hash};
+use lock_api::RwLockReadGuard;
+
+type Guard<'a, K, V> = (RwLockReadGuard<'a, RawRwLock, HashMap<K, V>>, audit::Held);
+
+/// A batch of lookups which reuses shard read locks, created by [`DashMap::read_scope`].
+///
+/// The read lock of a shard is taken the first time a key of it is looked up and kept until the
+/// scope ends, so further lookups in the same shard don't lock again. A scope never blocks while
+/// holding locks: if a shard can't be locked right away, every lock held by the scope is released
+/// before waiting for it. This is why each lookup borrows the scope mutably, and references returned
+/// by one lookup can't be kept across the next.
+pub struct ReadScope<'a, K, V, S> {
+    map: &'a DashMap<K, V, S>,
+    guards: Box<[Option<Guard<'a, K, V>>]>,
+}
+
+impl<'a, K: Eq + Hash, V, S: BuildHasher> ReadScope<'a, K, V, S> {
+    pub(crate) fn new(map: &'a DashMap<K, V, S>) -> Self {
+        Self {
+            map,
+            guards: map.shards.iter().map(|_| None).collect(),
+        }
+    }
+
+    fn shard(&mut self, idx: usize) -> &HashMap<K, V> {
+        if self.guards[idx].is_none() {
+            let map = self.map;
+            let lock = &map.shards[idx];
+
+            let guard = match lock.try_read() {
+                Some(guard) => guard,
+                None => {
+                    // Waiting while holding other shards could deadlock with a thread holding
+                    // this shard and waiting for one of ours, so let go of them first.
+                    self.release();
+                    lock.read()
+                }
+            };
+
+            // SAFETY: the raw lock is only used for its address.
+            let held = audit::Held::new(unsafe { lock.raw() }, false);
+            self.guards[idx] = Some((guard, held));
+        }
+
+        match &self.guards[idx] {
+            Some((guard, _)) => guard,
+            None => unreachable!(),
+        }
+    }
+
+    /// Returns a reference to the value corresponding to the key.
+    pub fn get<Q>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_key_value(key).map(|(_k, v)| v)
+    }
+
+    /// Returns the key-value pair corresponding to the supplied key.
+    pub fn get_key_value<Q>(&mut self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.map.hash_u64(&key);
+        let idx = self.map.determine_shard(hash as usize);
+
+        self.shard(idx)
+            .find(hash, |(k, _v)| key == k.borrow())
+            .map(|(k, v)| (k, v))
+    }
+
+    /// Returns `true` if the map contains a value for the specified key.
+    pub fn contains_key<Q>(&mut self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_key_value(key).is_some()
+    }
+
+    /// Returns the number of shards the scope currently holds read locks on.
+    pub fn locked_shards(&self) -> usize {
+        self.guards.iter().filter(|guard| guard.is_some()).count()
+    }
+
+    /// Releases every read lock held by the scope. Later lookups lock their shards again.
+    pub fn release(&mut self) {
+        for guard in self.guards.iter_mut() {
+            *guard = None;
+        }
+    }
+}
+
+impl<K, V, S> fmt::Debug for ReadScope<'_, K, V, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let locked = self.guards.iter().filter(|guard| guard.is_some()).count();
+
+        f.debug_struct("ReadScope")
+            .field("locked_shards", &locked)
+            .finish()
+    }
+}
@@ -1,22 +1,28 @@
-use crate::{mapref, setref, DashMap, DashSet};
+use crate::lock::RwLock;
+use crate::{mapref, setref, DashMap, DashSet, HashMap, ReadOnlyView};
 use core::fmt;
 use core::hash::{BuildHasher, Hash};
 use core::marker::PhantomData;
-use serde::de::{Deserialize, MapAccess, SeqAccess, Visitor};
+use crossbeam_utils::CachePadded;
+#[cfg(feature = "rayon")]
+use rayon::iter::{IndexedParallelIterator, IntoParallelRefMutIterator, ParallelIterator};
+use serde::de::{Deserialize, DeserializeSeed, MapAccess, SeqAccess, Visitor};
 use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
 use serde::Deserializer;
 
 pub struct DashMapVisitor<K, V, S> {
+    hasher: S,
     marker: PhantomData<fn() -> DashMap<K, V, S>>,
 }
 
 impl<K, V, S> DashMapVisitor<K, V, S>
 where
     K: Eq + Hash,
-    S: BuildHasher + Clone,
+    S: BuildHasher,
 {
-    fn new() -> Self {
+    fn new(hasher: S) -> Self {
         DashMapVisitor {
+            hasher,
             marker: PhantomData,
         }
     }
@@ -26,7 +32,7 @@ impl<'de, K, V, S> Visitor<'de> for DashMapVisitor<K, V, S>
 where
     K: Deserialize<'de> + Eq + Hash,
     V: Deserialize<'de>,
-    S: BuildHasher + Clone + Default,
+    S: BuildHasher,
 {
     type Value = DashMap<K, V, S>;
 
@@ -38,8 +44,7 @@ where
     where
         M: MapAccess<'de>,
     {
-        let map =
-            DashMap::with_capacity_and_hasher(access.size_hint().unwrap_or(0), Default::default());
+        let map = DashMap::with_capacity_and_hasher(access.size_hint().unwrap_or(0), self.hasher);
 
         while let Some((key, value)) = access.next_entry()? {
             map.insert(key, value);
@@ -47,19 +52,85 @@ where
 
         Ok(map)
     }
+
+    fn visit_seq<A>(self, mut access: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let map = DashMap::with_capacity_and_hasher(access.size_hint().unwrap_or(0), self.hasher);
+
+        while let Some((key, value)) = access.next_element()? {
+            map.insert(key, value);
+        }
+
+        Ok(map)
+    }
 }
 
 impl<'de, K, V, S> Deserialize<'de> for DashMap<K, V, S>
 where
     K: Deserialize<'de> + Eq + Hash,
     V: Deserialize<'de>,
-    S: BuildHasher + Clone + Default,
+    S: BuildHasher + Default,
 {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: Deserializer<'de>,
     {
-        deserializer.deserialize_map(DashMapVisitor::<K, V, S>::new())
+        deserializer.deserialize_map(DashMapVisitor::<K, V, S>::new(Default::default()))
+    }
+}
+
+/// A [`DeserializeSeed`] that deserializes a [`DashMap`] using the provided hasher
+/// instead of [`Default::default`]. This allows keyed hashers to round-trip.
+///
+/// Requires the `serde` feature to be enabled.
+///
+/// # Examples
+///
+/// ```
+/// use dashmap::{DashMap, DashMapSeed};
+/// use serde::de::value::{Error, MapDeserializer};
+/// use serde::de::DeserializeSeed;
+/// use std::collections::hash_map::RandomState;
+///
+/// let hasher = RandomState::new();
+/// let input = MapDeserializer::<_, Error>::new(vec![(1, 2), (3, 4)].into_iter());
+/// let map: DashMap<i32, i32> = DashMapSeed::new(hasher).deserialize(input).unwrap();
+/// assert_eq!(*map.get(&3).unwrap(), 4);
+/// ```
+pub struct DashMapSeed<K, V, S> {
+    hasher: S,
+    marker: PhantomData<fn() -> DashMap<K, V, S>>,
+}
+
+impl<K, V, S> DashMapSeed<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    /// Creates a new seed which will construct the map with `hasher`.
+    pub fn new(hasher: S) -> Self {
+        DashMapSeed {
+            hasher,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'de, K, V, S> DeserializeSeed<'de> for DashMapSeed<K, V, S>
+where
+    K: Deserialize<'de> + Eq + Hash,
+    V: Deserialize<'de>,
+    S: BuildHasher,
+{
+    type Value = DashMap<K, V, S>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(DashMapVisitor::<K, V, S>::new(self.hasher))
     }
 }
 
@@ -67,7 +138,7 @@ impl<K, V, H> Serialize for DashMap<K, V, H>
 where
     K: Serialize + Eq + Hash,
     V: Serialize,
-    H: BuildHasher + Clone,
+    H: BuildHasher,
 {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -83,17 +154,87 @@ where
     }
 }
 
+/// Serializes a [`DashMap`] as a sequence of key-value pairs instead of a map,
+/// for use with `#[serde(with = "dashmap::serde_pairs")]`.
+///
+/// Formats like JSON only allow string keys in maps, so maps with integer or struct keys
+/// fail to serialize as maps. Deserializing accepts both a sequence of pairs and a map,
+/// which requires a self-describing format.
+///
+/// Requires the `serde` feature to be enabled.
+///
+/// # Examples
+///
+/// ```
+/// use dashmap::DashMap;
+/// use serde::de::value::{Error, MapDeserializer, SeqDeserializer};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Index {
+///     #[serde(with = "dashmap::serde_pairs")]
+///     positions: DashMap<u32, u32>,
+/// }
+///
+/// let pairs = SeqDeserializer::<_, Error>::new(vec![vec![1, 10], vec![2, 20]].into_iter());
+/// let positions: DashMap<u32, u32> = dashmap::serde_pairs::deserialize(pairs).unwrap();
+/// assert_eq!(*positions.get(&2).unwrap(), 20);
+///
+/// let entries = MapDeserializer::<_, Error>::new(vec![(1, 10)].into_iter());
+/// let positions: DashMap<u32, u32> = dashmap::serde_pairs::deserialize(entries).unwrap();
+/// assert_eq!(*positions.get(&1).unwrap(), 10);
+/// ```
+pub mod serde_pairs {
+    use super::DashMapVisitor;
+    use crate::DashMap;
+    use core::hash::{BuildHasher, Hash};
+    use serde::de::{Deserialize, Deserializer};
+    use serde::ser::{Serialize, SerializeSeq, Serializer};
+
+    /// Serializes the entries of a map as a sequence of `(key, value)` pairs.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    pub fn serialize<K, V, H, S>(map: &DashMap<K, V, H>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        K: Serialize + Eq + Hash,
+        V: Serialize,
+        H: BuildHasher,
+        S: Serializer,
+    {
+        let mut seq = serializer.serialize_seq(Some(map.len()))?;
+
+        for ref_multi in map.iter() {
+            seq.serialize_element(&(ref_multi.key(), ref_multi.value()))?;
+        }
+
+        seq.end()
+    }
+
+    /// Deserializes a map from either a sequence of `(key, value)` pairs or a map.
+    pub fn deserialize<'de, K, V, H, D>(deserializer: D) -> Result<DashMap<K, V, H>, D::Error>
+    where
+        K: Deserialize<'de> + Eq + Hash,
+        V: Deserialize<'de>,
+        H: BuildHasher + Default,
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(DashMapVisitor::new(H::default()))
+    }
+}
+
 pub struct DashSetVisitor<K, S> {
+    hasher: S,
     marker: PhantomData<fn() -> DashSet<K, S>>,
 }
 
 impl<K, S> DashSetVisitor<K, S>
 where
     K: Eq + Hash,
-    S: BuildHasher + Clone,
+    S: BuildHasher,
 {
-    fn new() -> Self {
+    fn new(hasher: S) -> Self {
         DashSetVisitor {
+            hasher,
             marker: PhantomData,
         }
     }
@@ -102,7 +243,7 @@ where
 impl<'de, K, S> Visitor<'de> for DashSetVisitor<K, S>
 where
     K: Deserialize<'de> + Eq + Hash,
-    S: BuildHasher + Clone + Default,
+    S: BuildHasher,
 {
     type Value = DashSet<K, S>;
 
@@ -114,8 +255,7 @@ where
     where
         M: SeqAccess<'de>,
     {
-        let map =
-            DashSet::with_capacity_and_hasher(access.size_hint().unwrap_or(0), Default::default());
+        let map = DashSet::with_capacity_and_hasher(access.size_hint().unwrap_or(0), self.hasher);
 
         while let Some(key) = access.next_element()? {
             map.insert(key);
@@ -128,20 +268,58 @@ where
 impl<'de, K, S> Deserialize<'de> for DashSet<K, S>
 where
     K: Deserialize<'de> + Eq + Hash,
-    S: BuildHasher + Clone + Default,
+    S: BuildHasher + Default,
 {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: Deserializer<'de>,
     {
-        deserializer.deserialize_seq(DashSetVisitor::<K, S>::new())
+        deserializer.deserialize_seq(DashSetVisitor::<K, S>::new(Default::default()))
+    }
+}
+
+/// A [`DeserializeSeed`] that deserializes a [`DashSet`] using the provided hasher
+/// instead of [`Default::default`].
+///
+/// Requires the `serde` feature to be enabled.
+pub struct DashSetSeed<K, S> {
+    hasher: S,
+    marker: PhantomData<fn() -> DashSet<K, S>>,
+}
+
+impl<K, S> DashSetSeed<K, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    /// Creates a new seed which will construct the set with `hasher`.
+    pub fn new(hasher: S) -> Self {
+        DashSetSeed {
+            hasher,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'de, K, S> DeserializeSeed<'de> for DashSetSeed<K, S>
+where
+    K: Deserialize<'de> + Eq + Hash,
+    S: BuildHasher,
+{
+    type Value = DashSet<K, S>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(DashSetVisitor::<K, S>::new(self.hasher))
     }
 }
 
 impl<K, H> Serialize for DashSet<K, H>
 where
     K: Serialize + Eq + Hash,
-    H: BuildHasher + Clone,
+    H: BuildHasher,
 {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -157,6 +335,40 @@ where
     }
 }
 
+impl<'de, K, V, S> Deserialize<'de> for ReadOnlyView<K, V, S>
+where
+    K: Deserialize<'de> + Eq + Hash,
+    V: Deserialize<'de>,
+    S: BuildHasher + Default,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        DashMap::deserialize(deserializer).map(DashMap::into_read_only)
+    }
+}
+
+impl<K, V, H> Serialize for ReadOnlyView<K, V, H>
+where
+    K: Serialize + Eq + Hash,
+    V: Serialize,
+    H: BuildHasher,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+
+        for (key, value) in self.iter() {
+            map.serialize_entry(key, value)?;
+        }
+
+        map.end()
+    }
+}
+
 macro_rules! serialize_impl {
     () => {
         fn serialize<Ser>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error>
@@ -193,6 +405,10 @@ impl<'a, K: Eq + Hash, T: Serialize> Serialize for mapref::one::MappedRefMut<'a,
     serialize_impl! {}
 }
 
+impl<'a, T: Serialize> Serialize for mapref::one::RefPart<'a, T> {
+    serialize_impl! {}
+}
+
 // Set
 impl<'a, V: Hash + Eq + Serialize> Serialize for setref::multiple::RefMulti<'a, V> {
     serialize_impl! {}
@@ -201,3 +417,337 @@ impl<'a, V: Hash + Eq + Serialize> Serialize for setref::multiple::RefMulti<'a,
 impl<'a, V: Hash + Eq + Serialize> Serialize for setref::one::Ref<'a, V> {
     serialize_impl! {}
 }
+
+/// A group of shards of a [`DashMap`], serialized as a map of their entries.
+/// The shards are read-locked for the duration of the serialization.
+///
+/// See [`DashMap::serialize_shards`].
+///
+/// Requires the `serde` feature to be enabled.
+pub struct ShardGroup<'a, K, V> {
+    shards: &'a [CachePadded<RwLock<HashMap<K, V>>>],
+}
+
+impl<'a, K, V> Serialize for ShardGroup<'a, K, V>
+where
+    K: Serialize,
+    V: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let shards: Vec<_> = self.shards.iter().map(|shard| shard.read()).collect();
+        let len = shards.iter().map(|shard| shard.len()).sum();
+
+        let mut map = serializer.serialize_map(Some(len))?;
+
+        for shard in shards.iter() {
+            for (key, value) in shard.iter() {
+                map.serialize_entry(key, value)?;
+            }
+        }
+
+        map.end()
+    }
+}
+
+/// A [`DeserializeSeed`] that inserts the entries of a serialized map into an existing [`DashMap`].
+///
+/// See [`DashMap::deserialize_shards`].
+///
+/// Requires the `serde` feature to be enabled.
+pub struct ShardGroupSeed<'a, K, V, S> {
+    map: &'a DashMap<K, V, S>,
+}
+
+impl<'a, 'de, K, V, S> Visitor<'de> for ShardGroupSeed<'a, K, V, S>
+where
+    K: Deserialize<'de> + Eq + Hash,
+    V: Deserialize<'de>,
+    S: BuildHasher,
+{
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a map of DashMap entries")
+    }
+
+    fn visit_map<M>(self, mut access: M) -> Result<Self::Value, M::Error>
+    where
+        M: MapAccess<'de>,
+    {
+        while let Some((key, value)) = access.next_entry()? {
+            self.map.insert(key, value);
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a, 'de, K, V, S> DeserializeSeed<'de> for ShardGroupSeed<'a, K, V, S>
+where
+    K: Deserialize<'de> + Eq + Hash,
+    V: Deserialize<'de>,
+    S: BuildHasher,
+{
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(self)
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<K, V, S> DashMap<K, V, S>
+where
+    K: Send + Sync + Eq + Hash,
+    V: Send + Sync,
+    S: Send + Sync + BuildHasher,
+{
+    /// Serializes the map into several writers in parallel, for example one file per core.
+    ///
+    /// The shards are split into `writers.len()` contiguous groups, and `serialize` is called
+    /// on the rayon thread pool with each writer and its [`ShardGroup`], which serializes as a map,
+    /// e.g. with `serde_json::to_writer(writer, &group)`. If there are more writers than shards,
+    /// the extra writers receive empty groups. The first error returned by `serialize` is returned.
+    ///
+    /// Since every group covers distinct shards, loading the writers back with
+    /// [`deserialize_shards`](Self::deserialize_shards) into a map with the same hasher and shard amount
+    /// has the loaders insert into distinct shards.
+    ///
+    /// Requires the `serde` and `rayon` features to be enabled.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the map.
+    pub fn serialize_shards<W, E, F>(&self, writers: &mut [W], serialize: F) -> Result<(), E>
+    where
+        W: Send,
+        E: Send,
+        F: Fn(&mut W, ShardGroup<'_, K, V>) -> Result<(), E> + Sync,
+    {
+        let groups = writers.len();
+        let shards = &self.shards;
+
+        writers
+            .par_iter_mut()
+            .enumerate()
+            .try_for_each(|(i, writer)| {
+                let start = i * shards.len() / groups;
+                let end = (i + 1) * shards.len() / groups;
+
+                serialize(
+                    writer,
+                    ShardGroup {
+                        shards: &shards[start..end],
+                    },
+                )
+            })
+    }
+
+    /// Loads the entries of several serialized maps into the map in parallel,
+    /// typically the output of [`serialize_shards`](Self::serialize_shards).
+    ///
+    /// `deserialize` is called on the rayon thread pool with each reader and a [`ShardGroupSeed`],
+    /// which inserts the entries of a serialized map into this map,
+    /// e.g. with `seed.deserialize(&mut serde_json::Deserializer::from_reader(reader))`.
+    /// The first error returned by `deserialize` is returned, leaving the entries loaded so far in the map.
+    ///
+    /// Requires the `serde` and `rayon` features to be enabled.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashMap;
+    /// use serde::de::value::{Error, MapDeserializer};
+    /// use serde::de::DeserializeSeed;
+    ///
+    /// let mut files = vec![vec![(1, 2), (3, 4)], vec![(5, 6)]];
+    ///
+    /// let map: DashMap<i32, i32> = DashMap::new();
+    /// map.deserialize_shards(&mut files, |file, seed| {
+    ///     seed.deserialize(MapDeserializer::<_, Error>::new(file.drain(..)))
+    /// })
+    /// .unwrap();
+    /// assert_eq!(map.len(), 3);
+    /// ```
+    pub fn deserialize_shards<R, E, F>(&self, readers: &mut [R], deserialize: F) -> Result<(), E>
+    where
+        R: Send,
+        E: Send,
+        F: Fn(&mut R, ShardGroupSeed<'_, K, V, S>) -> Result<(), E> + Sync,
+    {
+        readers
+            .par_iter_mut()
+            .try_for_each(|reader| deserialize(reader, ShardGroupSeed { map: self }))
+    }
+}
+
+#[cfg(all(test, feature = "rayon"))]
+mod tests {
+    use crate::DashMap;
+    use serde::de::value::{Error, MapDeserializer};
+    use serde::de::{DeserializeSeed, Error as _};
+    use serde::ser::{Impossible, Serialize, SerializeMap, Serializer};
+    use std::collections::hash_map::RandomState;
+
+    /// Writes maps of `u32`s as little-endian pairs, the only format these tests need.
+    struct Pairs<'w>(&'w mut Vec<u8>);
+
+    macro_rules! unsupported {
+        ($($method:ident($($ty:ty),*) -> $ok:ty;)*) => {
+            $(fn $method(self, $(_: $ty),*) -> Result<$ok, Error> {
+                Err(Error::custom(concat!(stringify!($method), " is not supported")))
+            })*
+        };
+    }
+
+    impl<'w> Serializer for Pairs<'w> {
+        type Ok = ();
+        type Error = Error;
+        type SerializeSeq = Impossible<(), Error>;
+        type SerializeTuple = Impossible<(), Error>;
+        type SerializeTupleStruct = Impossible<(), Error>;
+        type SerializeTupleVariant = Impossible<(), Error>;
+        type SerializeMap = Self;
+        type SerializeStruct = Impossible<(), Error>;
+        type SerializeStructVariant = Impossible<(), Error>;
+
+        fn serialize_u32(self, v: u32) -> Result<(), Error> {
+            self.0.extend_from_slice(&v.to_le_bytes());
+            Ok(())
+        }
+
+        fn serialize_map(self, _len: Option<usize>) -> Result<Self, Error> {
+            Ok(self)
+        }
+
+        fn serialize_some<T: Serialize + ?Sized>(self, _: &T) -> Result<(), Error> {
+            Err(Error::custom("serialize_some is not supported"))
+        }
+
+        fn serialize_newtype_struct<T: Serialize + ?Sized>(
+            self,
+            _: &'static str,
+            _: &T,
+        ) -> Result<(), Error> {
+            Err(Error::custom("serialize_newtype_struct is not supported"))
+        }
+
+        fn serialize_newtype_variant<T: Serialize + ?Sized>(
+            self,
+            _: &'static str,
+            _: u32,
+            _: &'static str,
+            _: &T,
+        ) -> Result<(), Error> {
+            Err(Error::custom("serialize_newtype_variant is not supported"))
+        }
+
+        unsupported! {
+            serialize_bool(bool) -> ();
+            serialize_i8(i8) -> ();
+            serialize_i16(i16) -> ();
+            serialize_i32(i32) -> ();
+            serialize_i64(i64) -> ();
+            serialize_u8(u8) -> ();
+            serialize_u16(u16) -> ();
+            serialize_u64(u64) -> ();
+            serialize_f32(f32) -> ();
+            serialize_f64(f64) -> ();
+            serialize_char(char) -> ();
+            serialize_str(&str) -> ();
+            serialize_bytes(&[u8]) -> ();
+            serialize_none() -> ();
+            serialize_unit() -> ();
+            serialize_unit_struct(&'static str) -> ();
+            serialize_unit_variant(&'static str, u32, &'static str) -> ();
+            serialize_seq(Option<usize>) -> Self::SerializeSeq;
+            serialize_tuple(usize) -> Self::SerializeTuple;
+            serialize_tuple_struct(&'static str, usize) -> Self::SerializeTupleStruct;
+            serialize_tuple_variant(&'static str, u32, &'static str, usize) -> Self::SerializeTupleVariant;
+            serialize_struct(&'static str, usize) -> Self::SerializeStruct;
+            serialize_struct_variant(&'static str, u32, &'static str, usize) -> Self::SerializeStructVariant;
+        }
+    }
+
+    impl<'w> SerializeMap for Pairs<'w> {
+        type Ok = ();
+        type Error = Error;
+
+        fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+            key.serialize(Pairs(self.0))
+        }
+
+        fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+            value.serialize(Pairs(self.0))
+        }
+
+        fn end(self) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    fn read_pairs(bytes: &[u8]) -> impl Iterator<Item = (u32, u32)> + '_ {
+        bytes.chunks_exact(8).map(|pair| {
+            let word = |i: usize| u32::from_le_bytes(pair[i..i + 4].try_into().unwrap());
+            (word(0), word(4))
+        })
+    }
+
+    #[test]
+    fn test_shards_round_trip() {
+        let hasher = RandomState::new();
+        let map: DashMap<u32, u32, _> = DashMap::with_hasher_and_shard_amount(hasher.clone(), 4);
+        for i in 0..1000 {
+            map.insert(i, i * 3);
+        }
+
+        for writer_amount in [1, 3, 4, 6] {
+            let mut files = vec![Vec::new(); writer_amount];
+            map.serialize_shards(&mut files, |file, group| group.serialize(Pairs(file)))
+                .unwrap();
+
+            // Every entry is written exactly once, and the writers beyond the shard amount get nothing.
+            let written: usize = files.iter().map(|file| read_pairs(file).count()).sum();
+            assert_eq!(written, map.len());
+            let empty = files.iter().filter(|file| file.is_empty()).count();
+            assert_eq!(empty, writer_amount.saturating_sub(4));
+
+            let loaded: DashMap<u32, u32, _> =
+                DashMap::with_hasher_and_shard_amount(hasher.clone(), 4);
+            loaded
+                .deserialize_shards(&mut files, |file, seed| {
+                    seed.deserialize(MapDeserializer::<_, Error>::new(read_pairs(file)))
+                })
+                .unwrap();
+
+            assert_eq!(loaded.len(), map.len());
+            assert!(map
+                .iter()
+                .all(|r| loaded.get(r.key()).map(|v| *v) == Some(*r.value())));
+        }
+    }
+
+    #[test]
+    fn test_serialize_shards_error() {
+        let map: DashMap<u32, u32> = DashMap::with_shard_amount(4);
+        for i in 0..100 {
+            map.insert(i, i);
+        }
+
+        let mut files: Vec<(bool, Vec<u8>)> = (0..4).map(|i| (i == 2, Vec::new())).collect();
+        let result = map.serialize_shards(&mut files, |(broken, file), group| {
+            if *broken {
+                return Err(Error::custom("disk full"));
+            }
+            group.serialize(Pairs(file))
+        });
+
+        assert_eq!(result.unwrap_err().to_string(), "disk full");
+    }
+}
@@ -1,18 +1,26 @@
-use crate::iter_set::{Iter, OwningIter};
+use crate::iter_set::{ClonedIter, Iter, OwningIter};
 #[cfg(feature = "raw-api")]
 use crate::lock::RwLock;
+use crate::lock::RwLockWriteGuardDetached;
+use crate::setref::entry::{EntryRef, OccupiedEntryRef, VacantEntryRef};
 use crate::setref::one::Ref;
+use crate::tracker;
+use crate::util;
+use crate::watch;
 use crate::DashMap;
 #[cfg(feature = "raw-api")]
 use crate::HashMap;
 use cfg_if::cfg_if;
 use core::borrow::Borrow;
 use core::fmt;
-use core::hash::{BuildHasher, Hash};
+use core::hash::{BuildHasher, Hash, Hasher};
 use core::iter::FromIterator;
 #[cfg(feature = "raw-api")]
 use crossbeam_utils::CachePadded;
+use hashbrown::hash_table;
+use std::borrow::Cow;
 use std::collections::hash_map::RandomState;
+use std::sync::Arc;
 
 /// DashSet is a thin wrapper around [`DashMap`] using `()` as the value type. It uses
 /// methods and types which are more convenient to work with on a set.
@@ -22,7 +30,7 @@ pub struct DashSet<K, S = RandomState> {
     pub(crate) inner: DashMap<K, (), S>,
 }
 
-impl<K: Eq + Hash + fmt::Debug, S: BuildHasher + Clone> fmt::Debug for DashSet<K, S> {
+impl<K: Eq + Hash + fmt::Debug, S: BuildHasher> fmt::Debug for DashSet<K, S> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt::Debug::fmt(&self.inner, f)
     }
@@ -43,7 +51,7 @@ impl<K: Eq + Hash + Clone, S: Clone> Clone for DashSet<K, S> {
 impl<K, S> Default for DashSet<K, S>
 where
     K: Eq + Hash,
-    S: Default + BuildHasher + Clone,
+    S: Default + BuildHasher,
 {
     fn default() -> Self {
         Self::with_hasher(Default::default())
@@ -81,7 +89,7 @@ impl<'a, K: 'a + Eq + Hash> DashSet<K, RandomState> {
     }
 }
 
-impl<'a, K: 'a + Eq + Hash, S: BuildHasher + Clone> DashSet<K, S> {
+impl<'a, K: 'a + Eq + Hash, S: BuildHasher> DashSet<K, S> {
     /// Creates a new DashMap with a capacity of 0 and the provided hasher.
     ///
     /// # Examples
@@ -117,6 +125,22 @@ impl<'a, K: 'a + Eq + Hash, S: BuildHasher + Clone> DashSet<K, S> {
         }
     }
 
+    /// Converts the set into the underlying [`DashMap`] with `()` values, without moving the entries.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashSet;
+    ///
+    /// let set = DashSet::new();
+    /// set.insert("Johnny");
+    /// let map = set.into_map();
+    /// assert!(map.contains_key("Johnny"));
+    /// ```
+    pub fn into_map(self) -> DashMap<K, (), S> {
+        self.inner
+    }
+
     /// Hash a given item to produce a usize.
     /// Uses the provided or default HashBuilder.
     pub fn hash_usize<T: Hash>(&self, item: &T) -> usize {
@@ -207,6 +231,27 @@ impl<'a, K: 'a + Eq + Hash, S: BuildHasher + Clone> DashSet<K, S> {
         self.inner.insert(key, ()).is_none()
     }
 
+    /// Inserts a possibly borrowed key into the set, converting it into an owned key only if
+    /// it isn't in the set yet. Returns true if the key was not already in the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashSet;
+    /// use std::borrow::Cow;
+    ///
+    /// let seen: DashSet<String> = DashSet::new();
+    /// assert!(seen.insert_with_cow(Cow::Borrowed("alice")));
+    /// assert!(!seen.insert_with_cow(Cow::Borrowed("alice")));
+    /// ```
+    pub fn insert_with_cow<Q>(&self, key: Cow<'_, Q>) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ToOwned<Owned = K> + ?Sized,
+    {
+        self.inner.insert_with_cow(key, ()).is_none()
+    }
+
     /// Removes an entry from the map, returning the key if it existed in the map.
     ///
     /// # Examples
@@ -226,6 +271,28 @@ impl<'a, K: 'a + Eq + Hash, S: BuildHasher + Clone> DashSet<K, S> {
         self.inner.remove(key).map(|(k, _)| k)
     }
 
+    /// Removes a key from the set, returning it if it existed in the set.
+    ///
+    /// This is the same as [`remove`](Self::remove), under the name used by `std::collections::HashSet`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashSet;
+    ///
+    /// let names = DashSet::new();
+    /// names.insert(String::from("Jack"));
+    /// assert_eq!(names.take("Jack"), Some(String::from("Jack")));
+    /// assert_eq!(names.take("Jack"), None);
+    /// ```
+    pub fn take<Q>(&self, key: &Q) -> Option<K>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.remove(key)
+    }
+
     /// Removes an entry from the set, returning the key
     /// if the entry existed and the provided conditional function returned true.
     ///
@@ -271,6 +338,31 @@ impl<'a, K: 'a + Eq + Hash, S: BuildHasher + Clone> DashSet<K, S> {
         Iter::new(iter)
     }
 
+    /// Creates an iterator over clones of the keys, which holds no lock between items.
+    ///
+    /// **Locking behaviour:** Each shard is read-locked only while its keys are cloned.
+    /// May deadlock if called or advanced when holding a mutable reference into the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashSet;
+    ///
+    /// let words = DashSet::new();
+    /// words.insert("hello".to_owned());
+    ///
+    /// for word in words.iter_cloned() {
+    ///     words.insert(word.to_uppercase());
+    /// }
+    /// assert!(words.contains("HELLO"));
+    /// ```
+    pub fn iter_cloned(&'a self) -> ClonedIter<'a, K>
+    where
+        K: Clone,
+    {
+        ClonedIter::new(self.inner.iter_cloned())
+    }
+
     /// Get a reference to an entry in the set
     ///
     /// # Examples
@@ -290,6 +382,63 @@ impl<'a, K: 'a + Eq + Hash, S: BuildHasher + Clone> DashSet<K, S> {
         self.inner.get(key).map(Ref::new)
     }
 
+    /// Look up a key by reference, keeping the shard locked so that a missing key
+    /// can be inserted without hashing it again.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashSet;
+    ///
+    /// let interned: DashSet<String> = DashSet::new();
+    /// let name = interned.entry_ref("Dan").or_insert_with(|name| name.to_owned());
+    /// assert_eq!(*name, "Dan");
+    /// ```
+    pub fn entry_ref<'q, Q>(&'a self, key: &'q Q) -> EntryRef<'a, 'q, K, Q>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let map = &self.inner;
+        let hash = map.hash_u64(&key);
+
+        let idx = map.determine_shard(hash as usize);
+
+        let shard = map.shards[idx].write();
+        // SAFETY: The data will not outlive the guard, since we pass the guard to `EntryRef`.
+        let (guard, shard) = unsafe { RwLockWriteGuardDetached::detach_from(shard) };
+
+        map.reserve_one_tracked(shard);
+
+        match shard.entry(
+            hash,
+            |(k, _v)| key == k.borrow(),
+            |(k, _v)| {
+                let mut hasher = map.hasher.build_hasher();
+                k.hash(&mut hasher);
+                hasher.finish()
+            },
+        ) {
+            hash_table::Entry::Occupied(entry) => EntryRef::Occupied(OccupiedEntryRef::new(
+                guard,
+                entry,
+                map.tracker(),
+                map.len_counter(idx),
+                map.shrinker(),
+            )),
+            hash_table::Entry::Vacant(entry) => EntryRef::Vacant(VacantEntryRef::new(
+                guard,
+                key,
+                entry,
+                map.tracker(),
+                map.notify(idx, hash),
+                map.len_counter(idx),
+            )),
+        }
+    }
+
     /// Remove excess capacity to reduce memory usage.
     pub fn shrink_to_fit(&self) {
         self.inner.shrink_to_fit()
@@ -387,7 +536,95 @@ impl<'a, K: 'a + Eq + Hash, S: BuildHasher + Clone> DashSet<K, S> {
     }
 }
 
-impl<K: Eq + Hash, S: BuildHasher + Clone> IntoIterator for DashSet<K, S> {
+impl<T: Eq + Hash + ?Sized, S: BuildHasher> DashSet<Arc<T>, S> {
+    /// Returns the interned copy of a value, inserting a shared copy of it if it isn't in the set yet.
+    ///
+    /// The value is hashed once, and when it is already interned nothing is allocated
+    /// and only a read lock is taken.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the conversion into `Arc<T>` produces a value which is not equal to `value`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashSet;
+    /// use std::sync::Arc;
+    ///
+    /// let symbols: DashSet<Arc<str>> = DashSet::new();
+    /// let a = symbols.get_or_insert_owned("main");
+    /// let b = symbols.get_or_insert_owned("main");
+    /// assert!(Arc::ptr_eq(&a, &b));
+    /// ```
+    pub fn get_or_insert_owned<'v>(&self, value: &'v T) -> Arc<T>
+    where
+        Arc<T>: From<&'v T>,
+    {
+        self.get_or_insert_with(value, |_| Arc::from(value))
+    }
+
+    /// Returns the interned copy of a value, inserting the result of `make` if it isn't in the set yet.
+    ///
+    /// This works for values without a conversion from `&T` into `Arc<T>`, such as
+    /// `|v| Arc::new(v.clone())`.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the created value is not equal to `value`, since it would be stored under the hash of `value`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use dashmap::DashSet;
+    /// use std::sync::Arc;
+    ///
+    /// let routes: DashSet<Arc<Vec<u32>>> = DashSet::new();
+    /// let route = routes.get_or_insert_with(&vec![1, 4, 2], |v| Arc::new(v.clone()));
+    /// assert_eq!(*route, [1, 4, 2]);
+    /// ```
+    pub fn get_or_insert_with(&self, value: &T, make: impl FnOnce(&T) -> Arc<T>) -> Arc<T> {
+        let map = &self.inner;
+        let hash = map.hash_u64(&value);
+
+        let idx = map.determine_shard(hash as usize);
+
+        if let Some((k, _)) = map.shards[idx].read().find(hash, |(k, _)| **k == *value) {
+            return Arc::clone(k);
+        }
+
+        let mut shard = map.shards[idx].write();
+        map.reserve_one_tracked(&mut shard);
+
+        match shard.entry(
+            hash,
+            |(k, _)| **k == *value,
+            |(k, _)| {
+                let mut hasher = map.hasher.build_hasher();
+                k.hash(&mut hasher);
+                hasher.finish()
+            },
+        ) {
+            hash_table::Entry::Occupied(entry) => Arc::clone(&entry.get().0),
+            hash_table::Entry::Vacant(entry) => {
+                let key = make(value);
+                assert!(*key == *value, "created value differs from the original");
+
+                tracker::entry_added(map.tracker(), &key, &());
+                entry.insert((Arc::clone(&key), ()));
+                util::count_inserted(map.len_counter(idx));
+                watch::key_inserted(map.notify(idx, hash));
+                key
+            }
+        }
+    }
+}
+
+impl<K: Eq + Hash, S: BuildHasher> IntoIterator for DashSet<K, S> {
     type Item = K;
 
     type IntoIter = OwningIter<K>;
@@ -397,7 +634,7 @@ impl<K: Eq + Hash, S: BuildHasher + Clone> IntoIterator for DashSet<K, S> {
     }
 }
 
-impl<K: Eq + Hash, S: BuildHasher + Clone> Extend<K> for DashSet<K, S> {
+impl<K: Eq + Hash, S: BuildHasher> Extend<K> for DashSet<K, S> {
     fn extend<T: IntoIterator<Item = K>>(&mut self, iter: T) {
         let iter = iter.into_iter().map(|k| (k, ()));
 
@@ -405,7 +642,7 @@ impl<K: Eq + Hash, S: BuildHasher + Clone> Extend<K> for DashSet<K, S> {
     }
 }
 
-impl<K: Eq + Hash, S: BuildHasher + Clone + Default> FromIterator<K> for DashSet<K, S> {
+impl<K: Eq + Hash, S: BuildHasher + Default> FromIterator<K> for DashSet<K, S> {
     fn from_iter<I: IntoIterator<Item = K>>(iter: I) -> Self {
         let mut set = DashSet::default();
 
@@ -419,7 +656,7 @@ impl<K: Eq + Hash, S: BuildHasher + Clone + Default> FromIterator<K> for DashSet
 impl<K, S> typesize::TypeSize for DashSet<K, S>
 where
     K: typesize::TypeSize + Eq + Hash,
-    S: typesize::TypeSize + Clone + BuildHasher,
+    S: typesize::TypeSize + BuildHasher,
 {
     fn extra_size(&self) -> usize {
         self.inner.extra_size()
@@ -454,6 +691,122 @@ mod tests {
         assert_eq!(set.get(&0).as_deref(), Some(&0));
     }
 
+    #[test]
+    fn test_get_or_insert_owned() {
+        use std::sync::Arc;
+
+        let set: DashSet<Arc<str>> = DashSet::new();
+        let a = set.get_or_insert_owned("a");
+        assert!(Arc::ptr_eq(&a, &set.get_or_insert_owned("a")));
+        assert!(!Arc::ptr_eq(&a, &set.get_or_insert_owned("b")));
+        assert_eq!(set.len(), 2);
+        assert_eq!(Arc::strong_count(&a), 2);
+    }
+
+    #[test]
+    fn test_get_or_insert_with_mismatched_value() {
+        use std::panic::{self, AssertUnwindSafe};
+        use std::sync::Arc;
+
+        let set: DashSet<Arc<u32>> = DashSet::new();
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            set.get_or_insert_with(&1, |_| Arc::new(2));
+        }));
+        assert!(result.is_err());
+
+        assert!(set.is_empty());
+        assert_eq!(*set.get_or_insert_with(&2, |v| Arc::new(*v)), 2);
+        assert!(!set.contains(&1));
+    }
+
+    #[test]
+    fn test_ref_projection() {
+        let set = DashSet::new();
+        set.insert(("alice", Some(7)));
+
+        let name = set.get(&("alice", Some(7))).unwrap().map(|(name, _)| name);
+        assert_eq!(format!("{} {:?}", name, name), "alice \"alice\"");
+        drop(name);
+
+        let id = set
+            .get(&("alice", Some(7)))
+            .unwrap()
+            .try_map(|(_, id)| id.as_ref())
+            .unwrap();
+        assert_eq!(*id, 7);
+        drop(id);
+
+        let r = set.iter().next().unwrap();
+        let copy = r.clone();
+        assert!(r
+            .try_map(|(_, id)| id.as_ref().filter(|&&id| id > 7))
+            .is_err());
+        assert_eq!(format!("{:?}", copy.map(|(_, id)| id)), "Some(7)");
+    }
+
+    #[test]
+    fn test_entry_ref() {
+        use crate::setref::entry::EntryRef;
+
+        let set: DashSet<String> = DashSet::new();
+        let mut created = 0;
+
+        for name in ["a", "b", "a", "c", "b"] {
+            let r = set.entry_ref(name).or_insert_with(|name| {
+                created += 1;
+                name.to_owned()
+            });
+            assert_eq!(*r, name);
+        }
+
+        assert_eq!(created, 3);
+        assert_eq!(set.len(), 3);
+
+        match set.entry_ref("a") {
+            EntryRef::Occupied(entry) => assert_eq!(entry.remove(), "a"),
+            EntryRef::Vacant(_) => unreachable!(),
+        }
+        assert!(matches!(set.entry_ref("a"), EntryRef::Vacant(_)));
+    }
+
+    #[test]
+    fn test_entry_ref_mismatched_key() {
+        use std::panic::{self, AssertUnwindSafe};
+
+        let set: DashSet<String> = DashSet::new();
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            set.entry_ref("a").or_insert_with(|_| "b".to_owned());
+        }));
+        assert!(result.is_err());
+
+        // Nothing was stored, and the shard was unlocked.
+        assert!(set.is_empty());
+        assert!(set.insert("b".to_owned()));
+        assert!(set.contains("b"));
+        assert!(!set.contains("a"));
+    }
+
+    #[test]
+    fn test_into_map_and_back() {
+        let set = DashSet::with_capacity(64);
+
+        for i in 0..32 {
+            set.insert(i);
+        }
+
+        let capacity = set.capacity();
+        let map = set.into_map();
+        assert_eq!(map.len(), 32);
+        assert_eq!(map.capacity(), capacity);
+
+        map.insert(32, ());
+        let set = map.into_set();
+        assert!(set.contains(&32));
+        assert_eq!(set.len(), 33);
+    }
+
     #[test]
     fn test_multiple_hashes() {
         let set = DashSet::<u32>::default();
@@ -0,0 +1,144 @@
+use hashbrown::hash_table;
+
+use super::one::Ref;
+use crate::layout::ShrinkShard;
+use crate::lock::RwLockWriteGuardDetached;
+use crate::mapref;
+use crate::tracker::{self, MemoryTracker};
+use crate::util;
+use crate::watch::{self, Notify};
+use core::borrow::Borrow;
+use core::hash::Hash;
+use core::sync::atomic::AtomicUsize;
+
+pub enum EntryRef<'a, 'q, K, Q: ?Sized> {
+    Occupied(OccupiedEntryRef<'a, K>),
+    Vacant(VacantEntryRef<'a, 'q, K, Q>),
+}
+
+impl<'a, 'q, K: Eq + Hash + Borrow<Q>, Q: Eq + Hash + ?Sized> EntryRef<'a, 'q, K, Q> {
+    /// Get the key of the entry.
+    pub fn key(&self) -> &Q {
+        match *self {
+            EntryRef::Occupied(ref entry) => entry.key().borrow(),
+            EntryRef::Vacant(ref entry) => entry.key(),
+        }
+    }
+
+    /// Return a reference to the key if it exists,
+    /// otherwise insert the key created by a provided function and return a reference to that.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the created key is not equal to the borrowed key of the entry.
+    pub fn or_insert_with(self, make_key: impl FnOnce(&Q) -> K) -> Ref<'a, K> {
+        match self {
+            EntryRef::Occupied(entry) => entry.into_ref(),
+            EntryRef::Vacant(entry) => entry.insert_with(make_key),
+        }
+    }
+}
+
+pub struct VacantEntryRef<'a, 'q, K, Q: ?Sized> {
+    shard: RwLockWriteGuardDetached<'a>,
+    key: &'q Q,
+    entry: hash_table::VacantEntry<'a, (K, ())>,
+    tracker: Option<&'a dyn MemoryTracker<K, ()>>,
+    notify: Notify<'a>,
+    len: Option<&'a AtomicUsize>,
+}
+
+impl<'a, 'q, K: Eq + Hash + Borrow<Q>, Q: Eq + Hash + ?Sized> VacantEntryRef<'a, 'q, K, Q> {
+    pub(crate) fn new(
+        shard: RwLockWriteGuardDetached<'a>,
+        key: &'q Q,
+        entry: hash_table::VacantEntry<'a, (K, ())>,
+        tracker: Option<&'a dyn MemoryTracker<K, ()>>,
+        notify: Notify<'a>,
+        len: Option<&'a AtomicUsize>,
+    ) -> Self {
+        Self {
+            shard,
+            key,
+            entry,
+            tracker,
+            notify,
+            len,
+        }
+    }
+
+    /// Inserts the key created by a provided function.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the created key is not equal to the borrowed key of the entry. The entry is placed
+    /// by the hash of the borrowed key, so a different key could not be found again.
+    pub fn insert_with(self, make_key: impl FnOnce(&Q) -> K) -> Ref<'a, K> {
+        let key = make_key(self.key);
+        assert!(
+            key.borrow() == self.key,
+            "created key differs from the entry"
+        );
+
+        tracker::entry_added(self.tracker, &key, &());
+
+        let occupied = self.entry.insert((key, ()));
+        util::count_inserted(self.len);
+        watch::key_inserted(self.notify);
+
+        let (k, v) = occupied.into_mut();
+
+        Ref::new(mapref::one::RefMut::new(self.shard, k, v).downgrade())
+    }
+
+    pub fn key(&self) -> &'q Q {
+        self.key
+    }
+}
+
+pub struct OccupiedEntryRef<'a, K> {
+    shard: RwLockWriteGuardDetached<'a>,
+    entry: hash_table::OccupiedEntry<'a, (K, ())>,
+    tracker: Option<&'a dyn MemoryTracker<K, ()>>,
+    len: Option<&'a AtomicUsize>,
+    shrink: Option<&'a dyn ShrinkShard<K, ()>>,
+}
+
+impl<'a, K: Eq + Hash> OccupiedEntryRef<'a, K> {
+    pub(crate) fn new(
+        shard: RwLockWriteGuardDetached<'a>,
+        entry: hash_table::OccupiedEntry<'a, (K, ())>,
+        tracker: Option<&'a dyn MemoryTracker<K, ()>>,
+        len: Option<&'a AtomicUsize>,
+        shrink: Option<&'a dyn ShrinkShard<K, ()>>,
+    ) -> Self {
+        Self {
+            shard,
+            entry,
+            tracker,
+            len,
+            shrink,
+        }
+    }
+
+    pub fn key(&self) -> &K {
+        &self.entry.get().0
+    }
+
+    pub fn into_ref(self) -> Ref<'a, K> {
+        let (k, v) = self.entry.into_mut();
+        Ref::new(mapref::one::RefMut::new(self.shard, k, v).downgrade())
+    }
+
+    pub fn remove(self) -> K {
+        let ((k, v), vacant) = self.entry.remove();
+        util::entry_removed(
+            vacant.into_table(),
+            self.len,
+            self.tracker,
+            self.shrink,
+            (&k, &v),
+        );
+        k
+    }
+}
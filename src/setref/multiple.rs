@@ -1,6 +1,9 @@
+use crate::lock::RwLockReadGuardDetached;
 use crate::mapref;
 use core::hash::Hash;
 use core::ops::Deref;
+use std::fmt::{Debug, Display, Formatter};
+use std::sync::Arc;
 
 pub struct RefMulti<'a, K> {
     inner: mapref::multiple::RefMulti<'a, K, ()>,
@@ -14,6 +17,50 @@ impl<'a, K: Eq + Hash> RefMulti<'a, K> {
     pub fn key(&self) -> &K {
         self.inner.key()
     }
+
+    pub fn map<F, T>(self, f: F) -> MappedRefMulti<'a, T>
+    where
+        F: FnOnce(&K) -> &T,
+    {
+        let (guard, k, _v) = self.inner.into_raw_parts();
+
+        MappedRefMulti {
+            _guard: guard,
+            v: f(k),
+        }
+    }
+
+    pub fn try_map<F, T>(self, f: F) -> Result<MappedRefMulti<'a, T>, Self>
+    where
+        F: FnOnce(&K) -> Option<&T>,
+    {
+        let (guard, k, v) = self.inner.into_raw_parts();
+
+        match f(k) {
+            Some(v) => Ok(MappedRefMulti { _guard: guard, v }),
+            None => Err(Self::new(mapref::multiple::RefMulti::new(guard, k, v))),
+        }
+    }
+}
+
+impl<'a, K> Clone for RefMulti<'a, K> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<'a, K: Eq + Hash + Debug> Debug for RefMulti<'a, K> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(self.key(), f)
+    }
+}
+
+impl<'a, K: Eq + Hash + Display> Display for RefMulti<'a, K> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(self.key(), f)
+    }
 }
 
 impl<'a, K: Eq + Hash> Deref for RefMulti<'a, K> {
@@ -23,3 +70,44 @@ impl<'a, K: Eq + Hash> Deref for RefMulti<'a, K> {
         self.key()
     }
 }
+
+/// A reference to a part of a set element, produced by [`RefMulti::map`] and [`RefMulti::try_map`].
+pub struct MappedRefMulti<'a, T> {
+    _guard: Arc<RwLockReadGuardDetached<'a>>,
+    v: &'a T,
+}
+
+impl<'a, T> MappedRefMulti<'a, T> {
+    pub fn value(&self) -> &T {
+        self.v
+    }
+}
+
+impl<'a, T> Clone for MappedRefMulti<'a, T> {
+    fn clone(&self) -> Self {
+        Self {
+            _guard: self._guard.clone(),
+            v: self.v,
+        }
+    }
+}
+
+impl<'a, T: Debug> Debug for MappedRefMulti<'a, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(self.value(), f)
+    }
+}
+
+impl<'a, T: Display> Display for MappedRefMulti<'a, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(self.value(), f)
+    }
+}
+
+impl<'a, T> Deref for MappedRefMulti<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value()
+    }
+}
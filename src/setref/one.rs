@@ -1,6 +1,8 @@
+use crate::lock::RwLockReadGuardDetached;
 use crate::mapref;
 use core::hash::Hash;
 use core::ops::Deref;
+use std::fmt::{Debug, Display, Formatter};
 
 pub struct Ref<'a, K> {
     inner: mapref::one::Ref<'a, K, ()>,
@@ -14,6 +16,42 @@ impl<'a, K: Eq + Hash> Ref<'a, K> {
     pub fn key(&self) -> &K {
         self.inner.key()
     }
+
+    pub fn map<F, T>(self, f: F) -> MappedRef<'a, T>
+    where
+        F: FnOnce(&K) -> &T,
+    {
+        let (guard, k, _v) = self.inner.into_parts();
+
+        MappedRef {
+            _guard: guard,
+            v: f(k),
+        }
+    }
+
+    pub fn try_map<F, T>(self, f: F) -> Result<MappedRef<'a, T>, Self>
+    where
+        F: FnOnce(&K) -> Option<&T>,
+    {
+        let (guard, k, v) = self.inner.into_parts();
+
+        match f(k) {
+            Some(v) => Ok(MappedRef { _guard: guard, v }),
+            None => Err(Self::new(mapref::one::Ref::new(guard, k, v))),
+        }
+    }
+}
+
+impl<'a, K: Eq + Hash + Debug> Debug for Ref<'a, K> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(self.key(), f)
+    }
+}
+
+impl<'a, K: Eq + Hash + Display> Display for Ref<'a, K> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(self.key(), f)
+    }
 }
 
 impl<'a, K: Eq + Hash> Deref for Ref<'a, K> {
@@ -23,3 +61,58 @@ impl<'a, K: Eq + Hash> Deref for Ref<'a, K> {
         self.key()
     }
 }
+
+/// A reference to a part of a set element, produced by [`Ref::map`] and [`Ref::try_map`].
+pub struct MappedRef<'a, T> {
+    _guard: RwLockReadGuardDetached<'a>,
+    v: &'a T,
+}
+
+impl<'a, T> MappedRef<'a, T> {
+    pub fn value(&self) -> &T {
+        self.v
+    }
+
+    pub fn map<F, T2>(self, f: F) -> MappedRef<'a, T2>
+    where
+        F: FnOnce(&T) -> &T2,
+    {
+        MappedRef {
+            _guard: self._guard,
+            v: f(self.v),
+        }
+    }
+
+    pub fn try_map<F, T2>(self, f: F) -> Result<MappedRef<'a, T2>, Self>
+    where
+        F: FnOnce(&T) -> Option<&T2>,
+    {
+        match f(self.v) {
+            Some(v) => Ok(MappedRef {
+                _guard: self._guard,
+                v,
+            }),
+            None => Err(self),
+        }
+    }
+}
+
+impl<'a, T: Debug> Debug for MappedRef<'a, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(self.value(), f)
+    }
+}
+
+impl<'a, T: Display> Display for MappedRef<'a, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(self.value(), f)
+    }
+}
+
+impl<'a, T> Deref for MappedRef<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value()
+    }
+}
@@ -0,0 +1,464 @@
+//! Coordination of several maps which are read together.
+
+use crate::audit;
+use crate::lock::{RwLockReadGuardDetached, RwLockWriteGuardDetached};
+use crate::DashMap;
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::{BuildHasher, Hash};
+
+/// A map whose shards can all be read-locked at once, with the key and value types erased.
+trait Freeze {
+    fn freeze(&self) -> Vec<(RwLockReadGuardDetached<'_>, audit::Held)>;
+}
+
+impl<K, V, S> Freeze for DashMap<K, V, S> {
+    fn freeze(&self) -> Vec<(RwLockReadGuardDetached<'_>, audit::Held)> {
+        self.shards
+            .iter()
+            .map(|shard| {
+                // SAFETY: Only the guard is kept. The shard is read through `FrozenMap`,
+                // which borrows the token holding the guard.
+                let (guard, _) = unsafe { RwLockReadGuardDetached::detach_from(shard.read()) };
+                let held = audit::Held::new(unsafe { shard.raw() }, false);
+                (guard, held)
+            })
+            .collect()
+    }
+}
+
+fn address<K, V, S>(map: &DashMap<K, V, S>) -> usize {
+    map as *const DashMap<K, V, S> as usize
+}
+
+/// A set of maps which can be read-locked together, for consistent snapshots over all of them.
+///
+/// [`freeze`](Self::freeze) read-locks every shard of every registered map and returns a
+/// [`SnapshotToken`]. While the token is alive no registered map can be modified, so everything
+/// read through it — for example to checkpoint several related caches — reflects a single point
+/// in time. The maps are always locked in the order of their addresses and their shards in order,
+/// so barriers sharing maps cannot deadlock each other.
+///
+/// # Examples
+///
+/// ```
+/// use dashmap::sync::SnapshotBarrier;
+/// use dashmap::DashMap;
+///
+/// let users: DashMap<u32, &str> = DashMap::new();
+/// let sessions: DashMap<u64, u32> = DashMap::new();
+///
+/// let mut barrier = SnapshotBarrier::new();
+/// barrier.register(&users).register(&sessions);
+///
+/// users.insert(1, "alice");
+/// sessions.insert(7, 1);
+///
+/// let token = barrier.freeze();
+/// let users = token.map(&users).unwrap();
+/// let sessions = token.map(&sessions).unwrap();
+/// assert!(sessions.iter().all(|(_, user)| users.contains_key(user)));
+/// ```
+pub struct SnapshotBarrier<'a> {
+    maps: Vec<(usize, &'a (dyn Freeze + Sync + 'a))>,
+}
+
+impl<'a> SnapshotBarrier<'a> {
+    /// Creates a barrier without any maps.
+    pub fn new() -> Self {
+        Self { maps: Vec::new() }
+    }
+
+    /// Adds a map to the barrier. Registering a map which is already registered does nothing.
+    pub fn register<K, V, S>(&mut self, map: &'a DashMap<K, V, S>) -> &mut Self
+    where
+        DashMap<K, V, S>: Sync,
+    {
+        let address = address(map);
+
+        if let Err(i) = self.maps.binary_search_by_key(&address, |&(a, _)| a) {
+            self.maps.insert(i, (address, map));
+        }
+
+        self
+    }
+
+    /// Returns the number of registered maps.
+    pub fn len(&self) -> usize {
+        self.maps.len()
+    }
+
+    /// Checks if no maps are registered.
+    pub fn is_empty(&self) -> bool {
+        self.maps.is_empty()
+    }
+
+    /// Read-locks every shard of every registered map, returning a token through which they can be read.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding a mutable reference into any of the
+    /// registered maps. Modifying a registered map while the token is alive deadlocks as well.
+    pub fn freeze(&self) -> SnapshotToken<'a> {
+        let maps = self
+            .maps
+            .iter()
+            .map(|&(address, map)| (address, map.freeze()))
+            .collect();
+
+        SnapshotToken { maps }
+    }
+}
+
+impl Default for SnapshotBarrier<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for SnapshotBarrier<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SnapshotBarrier")
+            .field("maps", &self.maps.len())
+            .finish()
+    }
+}
+
+/// Read locks over all shards of the maps of a [`SnapshotBarrier`], released when dropped.
+pub struct SnapshotToken<'a> {
+    maps: Vec<(usize, Vec<(RwLockReadGuardDetached<'a>, audit::Held)>)>,
+}
+
+impl<'a> SnapshotToken<'a> {
+    /// Returns a frozen view of a map, or `None` if it isn't registered with the barrier.
+    pub fn map<K, V, S>(&self, map: &'a DashMap<K, V, S>) -> Option<FrozenMap<'_, K, V, S>> {
+        self.maps
+            .binary_search_by_key(&address(map), |&(a, _)| a)
+            .ok()
+            .map(|_| FrozenMap { map })
+    }
+}
+
+impl fmt::Debug for SnapshotToken<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SnapshotToken")
+            .field("maps", &self.maps.len())
+            .finish()
+    }
+}
+
+/// A read-only view of a map whose shards are all locked by a [`SnapshotToken`].
+pub struct FrozenMap<'t, K, V, S> {
+    map: &'t DashMap<K, V, S>,
+}
+
+impl<'t, K: Eq + Hash, V, S: BuildHasher> FrozenMap<'t, K, V, S> {
+    fn shard(&self, idx: usize) -> &'t crate::HashMap<K, V> {
+        // SAFETY: The token borrowed by `self` read-locks every shard of the map.
+        unsafe { &*self.map.shards[idx].data_ptr() }
+    }
+
+    /// Returns the number of elements in the map.
+    pub fn len(&self) -> usize {
+        (0..self.map.shards.len())
+            .map(|idx| self.shard(idx).len())
+            .sum()
+    }
+
+    /// Returns `true` if the map contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if the map contains a value for the specified key.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Returns a reference to the value corresponding to the key.
+    pub fn get<Q>(&self, key: &Q) -> Option<&'t V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_key_value(key).map(|(_k, v)| v)
+    }
+
+    /// Returns the key-value pair corresponding to the supplied key.
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&'t K, &'t V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.map.hash_u64(&key);
+        let idx = self.map.determine_shard(hash as usize);
+
+        self.shard(idx)
+            .find(hash, |(k, _v)| key == k.borrow())
+            .map(|(k, v)| (k, v))
+    }
+
+    /// An iterator visiting all key-value pairs in arbitrary order. The iterator element type is `(&'t K, &'t V)`.
+    pub fn iter(&self) -> impl Iterator<Item = (&'t K, &'t V)> + 't {
+        let this = FrozenMap { map: self.map };
+
+        (0..self.map.shards.len())
+            .flat_map(move |idx| this.shard(idx).iter())
+            .map(|(k, v)| (k, v))
+    }
+}
+
+impl<K: Eq + Hash + fmt::Debug, V: fmt::Debug, S: BuildHasher> fmt::Debug
+    for FrozenMap<'_, K, V, S>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// A handle keeping every shard of a map read-locked, created by [`DashMap::pin`].
+///
+/// References returned by its lookups borrow the handle and stay valid for as long as it lives,
+/// like the pinned guards of epoch-based maps. Unlike those, the map can't be modified while the
+/// handle is alive, so it is meant to be held briefly.
+pub struct Pinned<'a, K, V, S> {
+    map: &'a DashMap<K, V, S>,
+    _guards: Vec<(RwLockReadGuardDetached<'a>, audit::Held)>,
+}
+
+impl<'a, K: Eq + Hash, V, S: BuildHasher> Pinned<'a, K, V, S> {
+    pub(crate) fn new(map: &'a DashMap<K, V, S>) -> Self {
+        Self {
+            map,
+            _guards: Freeze::freeze(map),
+        }
+    }
+
+    fn frozen(&self) -> FrozenMap<'_, K, V, S> {
+        FrozenMap { map: self.map }
+    }
+
+    /// Returns the number of elements in the map.
+    pub fn len(&self) -> usize {
+        self.frozen().len()
+    }
+
+    /// Returns `true` if the map contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if the map contains a value for the specified key.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.frozen().contains_key(key)
+    }
+
+    /// Returns a reference to the value corresponding to the key.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.frozen().get(key)
+    }
+
+    /// Returns the key-value pair corresponding to the supplied key.
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.frozen().get_key_value(key)
+    }
+
+    /// An iterator visiting all key-value pairs in arbitrary order. The iterator element type is `(&K, &V)`.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> + '_ {
+        self.frozen().iter()
+    }
+}
+
+impl<K: Eq + Hash + fmt::Debug, V: fmt::Debug, S: BuildHasher> fmt::Debug for Pinned<'_, K, V, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.frozen().fmt(f)
+    }
+}
+
+/// A read-only view of a map whose shards are all write-locked, created by [`DashMap::freeze`].
+///
+/// Reads through the view don't touch any lock, so it can be shared between threads which then read
+/// without any synchronization. Other users of the map can neither read nor modify it until the guard
+/// is dropped, which thaws the map again.
+pub struct FrozenGuard<'a, K, V, S> {
+    map: &'a DashMap<K, V, S>,
+    _guards: Vec<(RwLockWriteGuardDetached<'a>, audit::Held)>,
+}
+
+impl<'a, K: Eq + Hash, V, S: BuildHasher> FrozenGuard<'a, K, V, S> {
+    pub(crate) fn new(map: &'a DashMap<K, V, S>) -> Self {
+        let guards = map
+            .shards
+            .iter()
+            .map(|shard| {
+                // SAFETY: Only the guard is kept. The shard is read through `FrozenMap`,
+                // which borrows the guard holding the lock.
+                let (guard, _) = unsafe { RwLockWriteGuardDetached::detach_from(shard.write()) };
+                let held = audit::Held::new(unsafe { shard.raw() }, true);
+                (guard, held)
+            })
+            .collect();
+
+        Self {
+            map,
+            _guards: guards,
+        }
+    }
+
+    fn frozen(&self) -> FrozenMap<'_, K, V, S> {
+        FrozenMap { map: self.map }
+    }
+
+    /// Returns the number of elements in the map.
+    pub fn len(&self) -> usize {
+        self.frozen().len()
+    }
+
+    /// Returns `true` if the map contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if the map contains a value for the specified key.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.frozen().contains_key(key)
+    }
+
+    /// Returns a reference to the value corresponding to the key.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.frozen().get(key)
+    }
+
+    /// Returns the key-value pair corresponding to the supplied key.
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.frozen().get_key_value(key)
+    }
+
+    /// An iterator visiting all key-value pairs in arbitrary order. The iterator element type is `(&K, &V)`.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> + '_ {
+        self.frozen().iter()
+    }
+}
+
+impl<K: Eq + Hash + fmt::Debug, V: fmt::Debug, S: BuildHasher> fmt::Debug
+    for FrozenGuard<'_, K, V, S>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.frozen().fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SnapshotBarrier;
+    use crate::DashMap;
+
+    #[test]
+    fn test_frozen_guard() {
+        let map = DashMap::new();
+
+        for i in 0..100 {
+            map.insert(i, i * 2);
+        }
+
+        {
+            let frozen = map.freeze();
+            assert!(map.try_get(&1).is_locked());
+
+            let sum: i32 = std::thread::scope(|s| {
+                let halves = [0..50, 50..100].map(|range| {
+                    let frozen = &frozen;
+                    s.spawn(move || range.map(|i| *frozen.get(&i).unwrap()).sum::<i32>())
+                });
+                halves.into_iter().map(|h| h.join().unwrap()).sum()
+            });
+
+            assert_eq!(sum, 9900);
+            assert_eq!(frozen.iter().count(), 100);
+        }
+
+        assert!(map.try_get_mut(&1).is_present());
+    }
+
+    #[test]
+    fn test_pin() {
+        let map = DashMap::new();
+
+        for i in 0..100 {
+            map.insert(i, i.to_string());
+        }
+
+        {
+            let pinned = map.pin();
+            let a = pinned.get(&1).unwrap();
+            let b = pinned.get(&2).unwrap();
+            assert_eq!((a.as_str(), b.as_str()), ("1", "2"));
+            assert_eq!(pinned.len(), 100);
+            assert!(map.try_get_mut(&1).is_locked());
+            assert_eq!(*map.get(&3).unwrap(), "3");
+        }
+
+        assert!(map.try_get_mut(&1).is_present());
+    }
+
+    #[test]
+    fn test_freeze() {
+        let a = DashMap::new();
+        let b = DashMap::new();
+        let other: DashMap<u32, u32> = DashMap::new();
+
+        for i in 0..100 {
+            a.insert(i, i);
+            b.insert(i.to_string(), i);
+        }
+
+        let mut barrier = SnapshotBarrier::new();
+        barrier.register(&b).register(&a).register(&b);
+        assert_eq!(barrier.len(), 2);
+
+        {
+            let token = barrier.freeze();
+            assert!(a.try_get_mut(&1).is_locked());
+            assert!(b.try_get_mut("1").is_locked());
+            assert!(other.try_get_mut(&1).is_absent());
+
+            let a = token.map(&a).unwrap();
+            let b = token.map(&b).unwrap();
+            assert!(token.map(&other).is_none());
+
+            assert_eq!(a.len(), 100);
+            assert_eq!(a.iter().count(), 100);
+            assert!(b
+                .iter()
+                .all(|(k, v)| a.get(v) == Some(v) && k == &v.to_string()));
+        }
+
+        assert!(a.try_get_mut(&1).is_present());
+    }
+}
@@ -0,0 +1,61 @@
+//! A minimal executor for the tests of the map's futures.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+
+/// A waker which unparks the thread that created it and counts how often it was woken.
+pub(crate) struct ThreadWaker {
+    thread: Thread,
+    wakes: AtomicUsize,
+}
+
+impl ThreadWaker {
+    pub(crate) fn new() -> Arc<Self> {
+        Arc::new(Self {
+            thread: thread::current(),
+            wakes: AtomicUsize::new(0),
+        })
+    }
+
+    pub(crate) fn wakes(&self) -> usize {
+        self.wakes.load(Ordering::SeqCst)
+    }
+}
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.wakes.fetch_add(1, Ordering::SeqCst);
+        self.thread.unpark();
+    }
+}
+
+/// Returns a waker which unparks the current thread.
+pub(crate) fn waker() -> Waker {
+    Waker::from(ThreadWaker::new())
+}
+
+/// Polls a future on the current thread until it is ready, parking the thread while it is pending.
+pub(crate) fn block_on<F: Future>(future: F) -> F::Output {
+    block_on_counting(future).0
+}
+
+/// Like [`block_on`], but also returns how often the future yielded.
+pub(crate) fn block_on_counting<F: Future>(future: F) -> (F::Output, usize) {
+    let waker = waker();
+    let mut cx = Context::from_waker(&waker);
+    let mut future = Box::pin(future);
+    let mut yields = 0;
+
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return (output, yields),
+            Poll::Pending => {
+                yields += 1;
+                thread::park();
+            }
+        }
+    }
+}
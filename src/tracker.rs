@@ -0,0 +1,64 @@
+use std::panic::{self, AssertUnwindSafe};
+
+/// A hook which is informed about changes in the memory used by a [`DashMap`](crate::DashMap).
+///
+/// A single tracker can be shared between many maps to enforce a common budget.
+/// See [`DashMap::with_memory_tracker`](crate::DashMap::with_memory_tracker).
+pub trait MemoryTracker<K, V>: Send + Sync {
+    /// Returns the number of bytes attributed to an entry, including any heap memory it owns.
+    fn entry_size(&self, key: &K, value: &V) -> usize;
+
+    /// Called with the change in bytes whenever entries are inserted, replaced or removed,
+    /// or when the allocation of a shard grows or shrinks.
+    fn on_change(&self, delta: isize);
+}
+
+pub(crate) fn entry_added<K, V>(tracker: Option<&dyn MemoryTracker<K, V>>, key: &K, value: &V) {
+    if let Some(tracker) = tracker {
+        tracker.on_change(tracker.entry_size(key, value) as isize);
+    }
+}
+
+pub(crate) fn entry_removed<K, V>(tracker: Option<&dyn MemoryTracker<K, V>>, key: &K, value: &V) {
+    if let Some(tracker) = tracker {
+        tracker.on_change(-(tracker.entry_size(key, value) as isize));
+    }
+}
+
+/// Runs `f` on a value in place and reports how much its size changed.
+///
+/// The value is measured again even if `f` panics, since it stays in the map with whatever changes
+/// `f` made before panicking.
+pub(crate) fn entry_updated<K, V, R>(
+    tracker: Option<&dyn MemoryTracker<K, V>>,
+    key: &K,
+    value: &mut V,
+    f: impl FnOnce(&mut V) -> R,
+) -> R {
+    match tracker {
+        Some(tracker) => {
+            let before = tracker.entry_size(key, value);
+            let result = panic::catch_unwind(AssertUnwindSafe(|| f(value)));
+            let after = tracker.entry_size(key, value);
+
+            if before != after {
+                tracker.on_change(after as isize - before as isize);
+            }
+
+            result.unwrap_or_else(|payload| panic::resume_unwind(payload))
+        }
+        None => f(value),
+    }
+}
+
+pub(crate) fn resized<K, V>(
+    tracker: Option<&dyn MemoryTracker<K, V>>,
+    before: usize,
+    after: usize,
+) {
+    if let Some(tracker) = tracker {
+        if before != after {
+            tracker.on_change(after as isize - before as isize);
+        }
+    }
+}
@@ -44,3 +44,71 @@ impl<R> TryResult<R> {
         }
     }
 }
+
+/// Represents the result of a non-blocking access to a [DashMap](crate::DashMap),
+/// with details about the lock holders when the shard was locked.
+#[derive(Debug)]
+pub enum TryResultDetailed<R> {
+    /// The value was present in the map, and the lock for the shard was successfully obtained.
+    Present(R),
+    /// The shard wasn't locked, and the value wasn't present in the map.
+    Absent,
+    /// The shard was locked, so the access would have blocked.
+    ///
+    /// The holders are a best-effort snapshot taken right after the attempt failed,
+    /// and may already be out of date.
+    WouldBlock {
+        /// The number of readers holding the shard.
+        readers: usize,
+        /// Whether a writer is holding the shard.
+        writer: bool,
+    },
+}
+
+impl<R> TryResultDetailed<R> {
+    /// Returns `true` if the value was present in the map, and the lock for the shard was successfully obtained.
+    pub fn is_present(&self) -> bool {
+        matches!(self, TryResultDetailed::Present(_))
+    }
+
+    /// Returns `true` if the shard wasn't locked, and the value wasn't present in the map.
+    pub fn is_absent(&self) -> bool {
+        matches!(self, TryResultDetailed::Absent)
+    }
+
+    /// Returns `true` if the shard was locked.
+    pub fn is_locked(&self) -> bool {
+        matches!(self, TryResultDetailed::WouldBlock { .. })
+    }
+
+    /// If `self` is [Present](TryResultDetailed::Present), returns the reference to the value in the map.
+    /// Panics if `self` is not [Present](TryResultDetailed::Present).
+    pub fn unwrap(self) -> R {
+        match self {
+            TryResultDetailed::Present(r) => r,
+            TryResultDetailed::WouldBlock { .. } => {
+                panic!("Called unwrap() on TryResultDetailed::WouldBlock")
+            }
+            TryResultDetailed::Absent => panic!("Called unwrap() on TryResultDetailed::Absent"),
+        }
+    }
+
+    /// If `self` is [Present](TryResultDetailed::Present), returns the reference to the value in the map.
+    /// If `self` is not [Present](TryResultDetailed::Present), returns `None`.
+    pub fn try_unwrap(self) -> Option<R> {
+        match self {
+            TryResultDetailed::Present(r) => Some(r),
+            _ => None,
+        }
+    }
+}
+
+impl<R> From<TryResultDetailed<R>> for TryResult<R> {
+    fn from(result: TryResultDetailed<R>) -> Self {
+        match result {
+            TryResultDetailed::Present(r) => TryResult::Present(r),
+            TryResultDetailed::Absent => TryResult::Absent,
+            TryResultDetailed::WouldBlock { .. } => TryResult::Locked,
+        }
+    }
+}
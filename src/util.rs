@@ -1,10 +1,135 @@
 //! This module is full of hackery and dark magic.
 //! Either spend a day fixing it and quietly submit a PR or don't mention it to anybody.
-use core::{mem, ptr};
+use core::sync::atomic::{AtomicUsize, Ordering};
+use core::{cmp, mem, ptr};
+use std::collections::BinaryHeap;
 use std::{marker::PhantomData, mem::ManuallyDrop};
 
 use lock_api::{RawRwLock, RawRwLockDowngrade, RwLockReadGuard, RwLockWriteGuard};
 
+use crate::layout::ShrinkShard;
+use crate::tracker::{self, MemoryTracker};
+use crate::HashMap;
+
+/// Counts an entry inserted into a shard, if the shard has a length counter.
+pub(crate) fn count_inserted(len: Option<&AtomicUsize>) {
+    if let Some(len) = len {
+        len.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Counts an entry removed from a shard, if the shard has a length counter.
+pub(crate) fn count_removed(len: Option<&AtomicUsize>) {
+    if let Some(len) = len {
+        len.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Does the bookkeeping for an entry which was just removed from a shard: counts it,
+/// reports it to the memory tracker and applies the shrink policy.
+pub(crate) fn entry_removed<K, V>(
+    shard: &mut HashMap<K, V>,
+    len: Option<&AtomicUsize>,
+    tracker: Option<&dyn MemoryTracker<K, V>>,
+    shrink: Option<&dyn ShrinkShard<K, V>>,
+    (k, v): (&K, &V),
+) {
+    count_removed(len);
+    tracker::entry_removed(tracker, k, v);
+
+    if let Some(shrink) = shrink {
+        shrink.shrink_after_removal(shard);
+    }
+}
+
+/// Merges runs of entries which are each sorted by key into a single sorted vector.
+pub(crate) fn merge_sorted_runs<K: Ord, V>(runs: Vec<Vec<(K, V)>>) -> Vec<(K, V)> {
+    struct Head<K, V> {
+        entry: (K, V),
+        run: usize,
+    }
+
+    impl<K: Ord, V> PartialEq for Head<K, V> {
+        fn eq(&self, other: &Self) -> bool {
+            self.entry.0 == other.entry.0
+        }
+    }
+
+    impl<K: Ord, V> Eq for Head<K, V> {}
+
+    impl<K: Ord, V> PartialOrd for Head<K, V> {
+        fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl<K: Ord, V> Ord for Head<K, V> {
+        fn cmp(&self, other: &Self) -> cmp::Ordering {
+            // Reversed, so that the heap pops the smallest key first.
+            other.entry.0.cmp(&self.entry.0)
+        }
+    }
+
+    let mut merged = Vec::with_capacity(runs.iter().map(Vec::len).sum());
+    let mut runs: Vec<_> = runs.into_iter().map(Vec::into_iter).collect();
+    let mut heads = BinaryHeap::with_capacity(runs.len());
+
+    for (run, entries) in runs.iter_mut().enumerate() {
+        if let Some(entry) = entries.next() {
+            heads.push(Head { entry, run });
+        }
+    }
+
+    while let Some(Head { entry, run }) = heads.pop() {
+        merged.push(entry);
+
+        if let Some(entry) = runs[run].next() {
+            heads.push(Head { entry, run });
+        }
+    }
+
+    merged
+}
+
+/// Estimates the number of buckets of a hash table of `T`s from its allocation size.
+///
+/// hashbrown doesn't expose the bucket count, so this searches for the layout the table was
+/// allocated with: `size_of::<T>() * buckets` rounded up to the control alignment, followed by
+/// a control byte per bucket and a group of trailing control bytes. The group width depends on
+/// the target, so every width hashbrown uses is tried.
+pub(crate) fn estimate_buckets<T>(allocation_size: usize) -> Option<usize> {
+    if allocation_size == 0 {
+        return Some(0);
+    }
+
+    let size = mem::size_of::<T>();
+    let mut buckets = 1usize;
+
+    while buckets <= allocation_size {
+        for width in [16, 8, 4] {
+            let align = cmp::max(mem::align_of::<T>(), width);
+            let ctrl_offset = (size * buckets + align - 1) & !(align - 1);
+
+            if ctrl_offset + buckets + width == allocation_size {
+                return Some(buckets);
+            }
+        }
+
+        buckets *= 2;
+    }
+
+    None
+}
+
+/// Returns how many entries a hash table with the given number of buckets holds before it grows.
+pub(crate) fn buckets_to_capacity(buckets: usize) -> usize {
+    if buckets <= 8 {
+        buckets.saturating_sub(1)
+    } else {
+        buckets / 8 * 7
+    }
+}
+
 pub const fn ptr_size_bits() -> usize {
     mem::size_of::<usize>() * 8
 }
@@ -113,11 +238,13 @@ impl<'a, R: RawRwLockDowngrade> RwLockWriteGuardDetached<'a, R> {
     ///
     /// The associated data must not mut mutated after downgrading
     pub(crate) unsafe fn downgrade(self) -> RwLockReadGuardDetached<'a, R> {
+        // Do not drop the write guard, or the downgraded lock would be unlocked exclusively.
+        let this = ManuallyDrop::new(self);
         // Safety: An RwLockWriteGuardDetached always holds an exclusive lock.
-        unsafe { self.lock.downgrade() }
+        unsafe { this.lock.downgrade() }
         RwLockReadGuardDetached {
-            lock: self.lock,
-            _marker: self._marker,
+            lock: this.lock,
+            _marker: PhantomData,
         }
     }
 }
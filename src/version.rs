@@ -0,0 +1,10 @@
+/// A value carrying the generation it was last modified in, for change tracking with
+/// [`DashMap::iter_changed_since`](crate::DashMap::iter_changed_since).
+///
+/// The map doesn't assign generations itself. Writers usually take the next generation from a counter
+/// shared by everyone modifying the map, such as an `AtomicU64`, and store it in the value they write.
+/// A reader which remembers the highest generation it has seen can then fetch only what changed since.
+pub trait Versioned {
+    /// Returns the generation in which the value was last modified.
+    fn version(&self) -> u64;
+}
@@ -0,0 +1,294 @@
+use crate::lock::{RwLock, RwLockReadGuardDetached};
+use crate::mapref::one::Ref;
+use crate::DashMap;
+use core::borrow::Borrow;
+use core::future::Future;
+use core::hash::{BuildHasher, Hash};
+use core::pin::Pin;
+use core::sync::atomic::{AtomicU64, Ordering};
+use core::task::{Context, Poll, Waker};
+use core::{fmt, mem};
+
+struct Watcher {
+    id: u64,
+    hash: u64,
+    waker: Waker,
+}
+
+#[derive(Default)]
+struct State {
+    waiting: Vec<Watcher>,
+    /// The hashes of the keys being initialized by [`DashMap::get_or_insert_with_async`].
+    in_flight: Vec<u64>,
+}
+
+impl State {
+    fn register(&mut self, id: u64, hash: u64, waker: &Waker) {
+        match self.waiting.iter_mut().find(|w| w.id == id) {
+            Some(watcher) => {
+                if !watcher.waker.will_wake(waker) {
+                    watcher.waker = waker.clone();
+                }
+            }
+            None => self.waiting.push(Watcher {
+                id,
+                hash,
+                waker: waker.clone(),
+            }),
+        }
+    }
+}
+
+/// The wakers of the futures waiting for keys of a single shard.
+///
+/// Registration happens under the read lock of the shard and waking under its write lock,
+/// so an insertion can't slip in between a failed lookup and the registration.
+#[derive(Default)]
+pub(crate) struct ShardWatchers {
+    state: RwLock<State>,
+}
+
+impl ShardWatchers {
+    fn register(&self, id: u64, hash: u64, waker: &Waker) {
+        self.state.write().register(id, hash, waker);
+    }
+
+    fn unregister(&self, id: u64) {
+        self.state.write().waiting.retain(|w| w.id != id);
+    }
+
+    fn wake(&self, hash: u64) {
+        let woken = {
+            let mut state = self.state.write();
+
+            if state.waiting.iter().all(|w| w.hash != hash) {
+                return;
+            }
+
+            let (woken, waiting) = mem::take(&mut state.waiting)
+                .into_iter()
+                .partition(|w| w.hash == hash);
+            state.waiting = waiting;
+            woken
+        };
+
+        // Wake outside of the lock, since wakers may run arbitrary code.
+        for watcher in woken {
+            watcher.waker.wake();
+        }
+    }
+
+    /// Marks a key as being initialized, unless a key with the same hash already is.
+    pub(crate) fn try_claim(&self, hash: u64) -> Option<Claim<'_>> {
+        let mut state = self.state.write();
+
+        if state.in_flight.contains(&hash) {
+            return None;
+        }
+
+        state.in_flight.push(hash);
+        Some(Claim {
+            watchers: self,
+            hash,
+        })
+    }
+}
+
+fn next_id() -> u64 {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// A handle to wake the futures waiting for a key, carried by vacant entries.
+pub(crate) type Notify<'a> = Option<(&'a ShardWatchers, u64)>;
+
+/// Wakes the futures waiting for the key of an entry which was just inserted.
+pub(crate) fn key_inserted(notify: Notify<'_>) {
+    if let Some((watchers, hash)) = notify {
+        watchers.wake(hash);
+    }
+}
+
+/// A future which resolves once a key is present in a [`DashMap`], returned by [`DashMap::watch_key`].
+///
+/// Dropping the future before it resolves is safe and unregisters it from the map.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct WatchKey<'a, 'q, K, V, S, Q: ?Sized> {
+    map: &'a DashMap<K, V, S>,
+    key: &'q Q,
+    hash: u64,
+    idx: usize,
+    id: u64,
+    registered: bool,
+}
+
+impl<'a, 'q, K, V, S, Q> WatchKey<'a, 'q, K, V, S, Q>
+where
+    K: Eq + Hash + Borrow<Q>,
+    S: BuildHasher,
+    Q: Hash + Eq + ?Sized,
+{
+    pub(crate) fn new(map: &'a DashMap<K, V, S>, key: &'q Q) -> Self {
+        let hash = map.hash_u64(&key);
+        let idx = map.determine_shard(hash as usize);
+
+        Self {
+            map,
+            key,
+            hash,
+            idx,
+            id: next_id(),
+            registered: false,
+        }
+    }
+}
+
+impl<'a, 'q, K, V, S, Q> Future for WatchKey<'a, 'q, K, V, S, Q>
+where
+    K: Eq + Hash + Borrow<Q>,
+    S: BuildHasher,
+    Q: Hash + Eq + ?Sized,
+{
+    type Output = Ref<'a, K, V>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let map = this.map;
+
+        let shard = match map.shards[this.idx].try_read() {
+            Some(shard) => shard,
+            None => {
+                // The shard is written to, possibly to insert the key. Writers don't wake
+                // anyone when they release the lock, so yield and try again.
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+        };
+        // SAFETY: The data will not outlive the guard, since we pass the guard to `Ref`.
+        let (guard, shard) = unsafe { RwLockReadGuardDetached::detach_from(shard) };
+
+        let watchers = &map.watchers()[this.idx];
+
+        if let Some((k, v)) = shard.find(this.hash, |(k, _v)| this.key == k.borrow()) {
+            if this.registered {
+                watchers.unregister(this.id);
+                this.registered = false;
+            }
+
+            return Poll::Ready(Ref::new(guard, k, v));
+        }
+
+        watchers.register(this.id, this.hash, cx.waker());
+        this.registered = true;
+
+        Poll::Pending
+    }
+}
+
+impl<'a, 'q, K, V, S, Q: ?Sized> Drop for WatchKey<'a, 'q, K, V, S, Q> {
+    fn drop(&mut self) {
+        if self.registered {
+            if let Some(watchers) = self.map.watchers.get() {
+                watchers[self.idx].unregister(self.id);
+            }
+        }
+    }
+}
+
+impl<'a, 'q, K, V, S, Q: fmt::Debug + ?Sized> fmt::Debug for WatchKey<'a, 'q, K, V, S, Q> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WatchKey").field("key", &self.key).finish()
+    }
+}
+
+/// The mark of a key being initialized, which wakes the callers waiting for it once dropped,
+/// even if the initializer panicked or was cancelled.
+pub(crate) struct Claim<'a> {
+    watchers: &'a ShardWatchers,
+    hash: u64,
+}
+
+impl Drop for Claim<'_> {
+    fn drop(&mut self) {
+        self.watchers
+            .state
+            .write()
+            .in_flight
+            .retain(|&hash| hash != self.hash);
+        self.watchers.wake(self.hash);
+    }
+}
+
+/// A future which resolves once a key is no longer being initialized.
+pub(crate) struct InFlight<'a> {
+    watchers: &'a ShardWatchers,
+    hash: u64,
+    id: u64,
+    registered: bool,
+}
+
+impl<'a> InFlight<'a> {
+    pub(crate) fn new(watchers: &'a ShardWatchers, hash: u64) -> Self {
+        Self {
+            watchers,
+            hash,
+            id: next_id(),
+            registered: false,
+        }
+    }
+}
+
+impl Future for InFlight<'_> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let this = self.get_mut();
+        let mut state = this.watchers.state.write();
+
+        if state.in_flight.contains(&this.hash) {
+            // The claim is released under the same lock, so the wakeup can't be missed.
+            state.register(this.id, this.hash, cx.waker());
+            this.registered = true;
+            return Poll::Pending;
+        }
+
+        if this.registered {
+            state.waiting.retain(|w| w.id != this.id);
+            this.registered = false;
+        }
+
+        Poll::Ready(())
+    }
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        if self.registered {
+            self.watchers.unregister(self.id);
+        }
+    }
+}
+
+/// A future which asks to be polled again once before resolving, to wait for a locked shard without blocking.
+pub(crate) struct YieldNow(bool);
+
+impl YieldNow {
+    pub(crate) fn new() -> Self {
+        Self(false)
+    }
+}
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.0 {
+            return Poll::Ready(());
+        }
+
+        self.get_mut().0 = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
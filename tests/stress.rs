@@ -0,0 +1,142 @@
+//! Randomized mixes of concurrent operations, with invariants checked during and after each round.
+//!
+//! A short run is part of `cargo test`. For a longer one, set the number of rounds, for example
+//! `DASHMAP_STRESS_ROUNDS=200 cargo test --release --test stress`.
+
+use dashmap::DashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Barrier;
+use std::thread;
+
+const THREADS: u64 = 8;
+const OPS_PER_THREAD: usize = 5_000;
+
+/// Keys below this are counters, which are only ever incremented.
+const COUNTERS: u64 = 32;
+/// Keys from `COUNTERS` up to this are inserted and removed, always with the value `churn_value(key)`.
+const KEYS: u64 = COUNTERS + 512;
+
+fn churn_value(key: u64) -> u64 {
+    key.wrapping_mul(0x9e37_79b9_7f4a_7c15)
+}
+
+fn is_consistent(key: u64, value: u64) -> bool {
+    key < COUNTERS || value == churn_value(key)
+}
+
+/// A xorshift generator, so that the test doesn't need any dependencies.
+struct Rng(u64);
+
+impl Rng {
+    fn below(&mut self, n: u64) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 % n
+    }
+
+    fn churn_key(&mut self) -> u64 {
+        COUNTERS + self.below(KEYS - COUNTERS)
+    }
+}
+
+fn rounds() -> u64 {
+    std::env::var("DASHMAP_STRESS_ROUNDS")
+        .ok()
+        .and_then(|rounds| rounds.parse().ok())
+        .unwrap_or(2)
+}
+
+fn worker(map: &DashMap<u64, u64>, increments: &AtomicU64, seed: u64) {
+    let mut rng = Rng(seed | 1);
+
+    for _ in 0..OPS_PER_THREAD {
+        match rng.below(100) {
+            0..=19 => {
+                *map.entry(rng.below(COUNTERS)).or_insert(0) += 1;
+                increments.fetch_add(1, Ordering::Relaxed);
+            }
+            20..=29 => {
+                if let Some(mut counter) = map.get_mut(&rng.below(COUNTERS)) {
+                    *counter += 1;
+                    increments.fetch_add(1, Ordering::Relaxed);
+
+                    let counter = counter.downgrade();
+                    assert!(*counter > 0);
+                }
+            }
+            30..=49 => {
+                let key = rng.churn_key();
+                if let Some(old) = map.insert(key, churn_value(key)) {
+                    assert_eq!(old, churn_value(key));
+                }
+            }
+            50..=64 => {
+                if let Some((key, value)) = map.remove(&rng.churn_key()) {
+                    assert_eq!(value, churn_value(key));
+                }
+            }
+            65..=79 => {
+                let key = rng.churn_key();
+                if let Some(value) = map.get(&key) {
+                    assert_eq!(*value, churn_value(key));
+                }
+            }
+            80..=84 => {
+                let key = rng.churn_key();
+                map.entry(key)
+                    .and_modify(|v| assert_eq!(*v, churn_value(key)))
+                    .or_insert_with(|| churn_value(key));
+            }
+            85..=89 => {
+                map.alter(&rng.churn_key(), |_, v| v);
+            }
+            90..=94 => {
+                assert!(map.iter().all(|r| is_consistent(*r.key(), *r.value())));
+            }
+            95..=97 => {
+                let modulus = 2 + rng.below(16);
+                map.retain(|k, v| {
+                    assert!(is_consistent(*k, *v));
+                    *k < COUNTERS || k % modulus != 0
+                });
+            }
+            _ => {
+                let clone = map.clone();
+                assert_eq!(clone.len(), clone.iter().count());
+                assert!(clone.iter().all(|r| is_consistent(*r.key(), *r.value())));
+            }
+        }
+    }
+}
+
+#[test]
+fn stress_mixed_operations() {
+    for round in 0..rounds() {
+        let map = DashMap::with_shard_amount(8);
+        let increments = AtomicU64::new(0);
+        let barrier = Barrier::new(THREADS as usize);
+
+        thread::scope(|s| {
+            for t in 0..THREADS {
+                let (map, increments, barrier) = (&map, &increments, &barrier);
+
+                s.spawn(move || {
+                    barrier.wait();
+                    worker(
+                        map,
+                        increments,
+                        (round << 32) ^ (t + 1).wrapping_mul(0x2545_f491),
+                    );
+                });
+            }
+        });
+
+        let counted: u64 = (0..COUNTERS).map(|k| map.get(&k).map_or(0, |v| *v)).sum();
+        assert_eq!(counted, increments.load(Ordering::Relaxed), "lost updates");
+
+        assert_eq!(map.len(), map.iter().count());
+        assert_eq!(map.len(), map.len_exact());
+        assert!(map.iter().all(|r| is_consistent(*r.key(), *r.value())));
+    }
+}